@@ -0,0 +1,283 @@
+//! AI Context - Resolve `@path` mentions in prompts to workspace file content.
+//!
+//! Mentions are `@relative/path.md` (or `@"path with spaces.md"`) at the start
+//! of the prompt or after whitespace. Each is resolved against the workspace
+//! root, rejecting anything that escapes it, and replaced by a fenced block
+//! with the file content. Total injected content is capped by a byte budget.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+/// Default cap on injected file content (bytes)
+const DEFAULT_CONTEXT_BUDGET: usize = 100 * 1024;
+
+/// Marker appended when content is cut to fit the budget
+const TRUNCATION_MARKER: &str = "[... truncated: context budget exceeded ...]";
+
+/// Trailing punctuation that ends a mention rather than belonging to the path
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', ')', ']', '}', '"', '\''];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedMention {
+    pub mention: String,
+    pub path: String,
+    pub bytes: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnresolvedMention {
+    pub mention: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedPrompt {
+    pub prompt: String,
+    pub resolved: Vec<ResolvedMention>,
+    pub unresolved: Vec<UnresolvedMention>,
+}
+
+/// A mention found in the prompt: byte range of the full token and the path.
+#[derive(Debug, PartialEq)]
+struct MentionToken {
+    start: usize,
+    end: usize,
+    path: String,
+}
+
+/// Find `@path` tokens in the prompt.
+fn find_mentions(prompt: &str) -> Vec<MentionToken> {
+    let mut mentions = Vec::new();
+    let bytes = prompt.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        // '@' is ASCII, so `i` is a char boundary once it matches
+        if bytes[i] != b'@' || !(i == 0 || prompt[..i].ends_with(char::is_whitespace)) {
+            i += 1;
+            continue;
+        }
+
+        let rest = &prompt[i + 1..];
+        if let Some(quoted) = rest.strip_prefix('"') {
+            // @"path with spaces"
+            if let Some(close) = quoted.find('"') {
+                let path = &quoted[..close];
+                let end = i + 1 + 1 + close + 1;
+                if !path.is_empty() {
+                    mentions.push(MentionToken { start: i, end, path: path.to_string() });
+                }
+                i = end;
+                continue;
+            }
+        } else {
+            let token_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let path = rest[..token_len].trim_end_matches(TRAILING_PUNCTUATION);
+            if !path.is_empty() {
+                mentions.push(MentionToken {
+                    start: i,
+                    end: i + 1 + path.len(),
+                    path: path.to_string(),
+                });
+            }
+            i += 1 + token_len;
+            continue;
+        }
+        i += 1;
+    }
+    mentions
+}
+
+/// Resolve a mention path inside the workspace root.
+fn resolve_in_root(root: &Path, mention_path: &str) -> Result<PathBuf, String> {
+    let candidate = Path::new(mention_path);
+    if candidate.is_absolute() {
+        return Err("Absolute paths are not allowed".to_string());
+    }
+    let resolved = fs::canonicalize(root.join(candidate)).map_err(|_| "File not found".to_string())?;
+    if !resolved.starts_with(root) {
+        return Err("Path is outside the workspace".to_string());
+    }
+    if !resolved.is_file() {
+        return Err("Not a file".to_string());
+    }
+    Ok(resolved)
+}
+
+/// Truncate at a char boundary no later than `max_bytes`.
+fn truncate_to_boundary(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// Fence long enough not to collide with backtick runs in the content.
+fn fence_for(content: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for ch in content.chars() {
+        if ch == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn fenced_block(path: &str, content: &str, truncated: bool) -> String {
+    let fence = fence_for(content);
+    let lang = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    let mut block = format!("\n`{}`:\n{}{}\n{}", path, fence, lang, content);
+    if !content.is_empty() && !content.ends_with('\n') {
+        block.push('\n');
+    }
+    if truncated {
+        block.push_str(TRUNCATION_MARKER);
+        block.push('\n');
+    }
+    block.push_str(&fence);
+    block.push('\n');
+    block
+}
+
+fn resolve_mentions_impl(root: &Path, prompt: &str, budget: usize) -> Result<ResolvedPrompt, String> {
+    let root = fs::canonicalize(root).map_err(|e| format!("Invalid workspace root: {}", e))?;
+
+    let mut output = String::with_capacity(prompt.len());
+    let mut resolved: Vec<ResolvedMention> = Vec::new();
+    let mut unresolved: Vec<UnresolvedMention> = Vec::new();
+    let mut remaining = budget;
+    let mut cursor = 0;
+
+    for token in find_mentions(prompt) {
+        output.push_str(&prompt[cursor..token.start]);
+        let mention = prompt[token.start..token.end].to_string();
+        cursor = token.end;
+
+        // Inject each file once; repeated mentions stay as plain text
+        if resolved.iter().any(|r| r.mention == mention) {
+            output.push_str(&mention);
+            continue;
+        }
+
+        let content = resolve_in_root(&root, &token.path).and_then(|path| {
+            fs::read_to_string(&path).map_err(|_| "File is not valid UTF-8 text".to_string())
+        });
+        match content {
+            Ok(content) => {
+                let injected = truncate_to_boundary(&content, remaining);
+                let truncated = injected.len() < content.len();
+                remaining -= injected.len();
+                output.push_str(&fenced_block(&token.path, injected, truncated));
+                resolved.push(ResolvedMention {
+                    mention,
+                    path: token.path,
+                    bytes: injected.len(),
+                    truncated,
+                });
+            }
+            Err(reason) => {
+                output.push_str(&mention);
+                unresolved.push(UnresolvedMention { mention, reason });
+            }
+        }
+    }
+    output.push_str(&prompt[cursor..]);
+
+    Ok(ResolvedPrompt {
+        prompt: output,
+        resolved,
+        unresolved,
+    })
+}
+
+/// Replace `@path` mentions in a prompt with the content of workspace files.
+#[tauri::command]
+pub fn resolve_context_mentions(
+    root_path: String,
+    prompt: String,
+    budget: Option<usize>,
+) -> Result<ResolvedPrompt, String> {
+    resolve_mentions_impl(Path::new(&root_path), &prompt, budget.unwrap_or(DEFAULT_CONTEXT_BUDGET))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn finds_plain_and_quoted_mentions() {
+        let mentions = find_mentions("séé @notes.md, and @\"my file.md\" but not a@b.com");
+        let paths: Vec<&str> = mentions.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(paths, vec!["notes.md", "my file.md"]);
+    }
+
+    #[test]
+    fn resolves_and_inlines_file() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "# A\n").unwrap();
+
+        let result = resolve_mentions_impl(dir.path(), "Summarize @a.md please", 1000).unwrap();
+        assert_eq!(result.resolved.len(), 1);
+        assert!(result.unresolved.is_empty());
+        assert!(result.prompt.contains("```md\n# A\n```"));
+        assert!(result.prompt.starts_with("Summarize \n"));
+        assert!(result.prompt.ends_with(" please"));
+    }
+
+    #[test]
+    fn rejects_escape_and_missing() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir(&root).unwrap();
+        fs::write(dir.path().join("secret.md"), "secret").unwrap();
+
+        let result = resolve_mentions_impl(&root, "@../secret.md @missing.md @/etc/passwd", 1000).unwrap();
+        assert!(result.resolved.is_empty());
+        assert_eq!(result.unresolved.len(), 3);
+        assert_eq!(result.unresolved[0].reason, "Path is outside the workspace");
+        assert!(!result.prompt.contains("secret\n"));
+    }
+
+    #[test]
+    fn truncates_to_budget() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "x".repeat(100)).unwrap();
+        fs::write(dir.path().join("b.txt"), "y".repeat(100)).unwrap();
+
+        let result = resolve_mentions_impl(dir.path(), "@a.txt @b.txt", 150).unwrap();
+        assert_eq!(result.resolved[0].bytes, 100);
+        assert!(!result.resolved[0].truncated);
+        assert_eq!(result.resolved[1].bytes, 50);
+        assert!(result.resolved[1].truncated);
+        assert!(result.prompt.contains(TRUNCATION_MARKER));
+    }
+
+    #[test]
+    fn fence_avoids_backtick_collision() {
+        assert_eq!(fence_for("plain"), "```");
+        assert_eq!(fence_for("has ```` inside"), "`````");
+    }
+
+    #[test]
+    fn truncate_respects_char_boundary() {
+        assert_eq!(truncate_to_boundary("héllo", 2), "h");
+    }
+}
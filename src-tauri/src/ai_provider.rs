@@ -3,10 +3,22 @@
 //! Detects available CLI AI providers and executes prompts via shell commands
 //! or REST APIs. Streams results back to the frontend via Tauri events.
 
-use serde::Serialize;
-use std::io::{BufRead, BufReader, Write as IoWrite};
-use std::process::{Command, Stdio};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write as IoWrite};
+use std::pin::Pin;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::{command, Emitter, WebviewWindow};
+use tokio::sync::{watch, Semaphore};
+
+use crate::ai_transcript;
 
 // ============================================================================
 // Types
@@ -20,6 +32,8 @@ pub struct CliProviderEntry {
     pub command: String,
     pub available: bool,
     pub path: Option<String>,
+    /// First line of `<command> --version`, when it answers in time
+    pub version: Option<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -29,34 +43,253 @@ pub struct AiResponseChunk {
     pub chunk: String,
     pub done: bool,
     pub error: Option<String>,
+    /// Request state (e.g. "queued", "started"; "refused" alongside `error`); no text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+/// An image sent with the last user message to a vision-capable provider.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageAttachment {
+    /// Base64-encoded image bytes (no `data:` prefix)
+    pub data: String,
+    /// e.g. "image/png"
+    pub mime_type: String,
+}
+
+/// Image types accepted by the vision-capable providers
+const SUPPORTED_IMAGE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/webp", "image/gif"];
+/// Largest decoded image accepted (Anthropic's per-image limit; OpenAI allows more)
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Check attachments against what `provider` accepts. Providers without
+/// vision support reject any image rather than silently dropping it.
+fn validate_images(provider: &str, images: &[ImageAttachment]) -> Result<(), String> {
+    if images.is_empty() {
+        return Ok(());
+    }
+    if !matches!(provider, "anthropic" | "openai" | "azure-openai") {
+        return Err(format!("{} does not support image attachments", provider));
+    }
+    for image in images {
+        if !SUPPORTED_IMAGE_TYPES.contains(&image.mime_type.as_str()) {
+            return Err(format!(
+                "Unsupported image type {} (expected PNG, JPEG, WebP or GIF)",
+                image.mime_type
+            ));
+        }
+        let size = image.data.len() / 4 * 3;
+        if image.data.is_empty() || size > MAX_IMAGE_BYTES {
+            return Err(format!(
+                "Images must be non-empty and at most {} MB",
+                MAX_IMAGE_BYTES / (1024 * 1024)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// One turn of a conversation passed to `run_ai_prompt`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// "user" or "assistant"
+    pub role: String,
+    pub content: String,
+}
+
+/// Optional sampling parameters; only those set are sent to the provider.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenerationParams {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+    /// Sequences that halt generation; an empty list is not sent
+    pub stop: Option<Vec<String>>,
+}
+
+/// Most stop sequences a provider accepts, where it enforces a limit.
+fn max_stop_sequences(provider: &str) -> Option<usize> {
+    match provider {
+        "openai" | "azure-openai" => Some(4),
+        "google-ai" => Some(5),
+        _ => None,
+    }
+}
+
+impl GenerationParams {
+    /// Stop sequences to send, if any.
+    fn stop_sequences(&self) -> Option<&Vec<String>> {
+        self.stop.as_ref().filter(|stop| !stop.is_empty())
+    }
+
+    /// Check values against the ranges the provider accepts.
+    fn validate(&self, provider: &str) -> Result<(), String> {
+        let max_temperature = if provider == "anthropic" { 1.0 } else { 2.0 };
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=max_temperature).contains(&temperature) {
+                return Err(format!(
+                    "Temperature must be between 0 and {} for {}",
+                    max_temperature, provider
+                ));
+            }
+        }
+        if let Some(top_p) = self.top_p {
+            if !(0.0..=1.0).contains(&top_p) {
+                return Err("top_p must be between 0 and 1".to_string());
+            }
+        }
+        if self.max_tokens == Some(0) {
+            return Err("max_tokens must be at least 1".to_string());
+        }
+        if let (Some(stop), Some(max)) = (self.stop_sequences(), max_stop_sequences(provider)) {
+            if stop.len() > max {
+                return Err(format!(
+                    "{} accepts at most {} stop sequences, got {}",
+                    provider,
+                    max,
+                    stop.len()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What to send to a REST provider, independent of its wire format.
+#[derive(Debug, Clone, Copy)]
+struct PromptInput<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    system: Option<&'a str>,
+    params: &'a GenerationParams,
+    /// Attached to the last (user) message
+    images: &'a [ImageAttachment],
 }
 
 // ============================================================================
 // CLI Provider Detection
 // ============================================================================
 
+/// Upper bound on a single `--version` probe during detection
+const VERSION_PROBE_TIMEOUT_MS: u64 = 1000;
+
+/// Provider type reported for user-configured CLI tools
+const CUSTOM_PROVIDER_TYPE: &str = "custom";
+
 /// Detect which CLI AI providers are available on the system.
+///
+/// `extra_commands` adds user-configured tools (reported with type "custom").
+/// Providers are probed in parallel so slow `--version` calls don't add up.
 #[command]
-pub fn detect_ai_providers() -> Vec<CliProviderEntry> {
-    let providers = [
+pub fn detect_ai_providers(extra_commands: Option<Vec<String>>) -> Vec<CliProviderEntry> {
+    let mut providers: Vec<(String, String, String)> = [
         ("claude", "Claude", "claude"),
         ("codex", "Codex", "codex"),
         ("gemini", "Gemini", "gemini"),
-    ];
+    ]
+    .iter()
+    .map(|(typ, name, cmd)| (typ.to_string(), name.to_string(), cmd.to_string()))
+    .collect();
+
+    for cmd in extra_commands.unwrap_or_default() {
+        let Ok(cmd) = validate_custom_command(&cmd) else {
+            continue;
+        };
+        if !providers.iter().any(|(_, _, existing)| existing == cmd) {
+            providers.push((
+                CUSTOM_PROVIDER_TYPE.to_string(),
+                cmd.to_string(),
+                cmd.to_string(),
+            ));
+        }
+    }
 
-    providers
-        .iter()
-        .map(|(typ, name, cmd)| {
-            let (available, path) = check_command(cmd);
-            CliProviderEntry {
-                provider_type: typ.to_string(),
-                name: name.to_string(),
-                command: cmd.to_string(),
-                available,
-                path,
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = providers
+            .iter()
+            .map(|(typ, name, cmd)| scope.spawn(move || probe_provider(typ, name, cmd)))
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().ok())
+            .collect()
+    })
+}
+
+/// Detect an arbitrary CLI tool (e.g. a wrapper script) by command name or path.
+#[command]
+pub fn detect_custom_ai_provider(command: String) -> Result<CliProviderEntry, String> {
+    let cmd = validate_custom_command(&command)?;
+    Ok(probe_provider(CUSTOM_PROVIDER_TYPE, cmd, cmd))
+}
+
+/// Check a user-supplied command name; it is passed to `which`/`where`
+/// without a shell, so only flag-like values need rejecting.
+fn validate_custom_command(command: &str) -> Result<&str, String> {
+    let cmd = command.trim();
+    if cmd.is_empty() {
+        return Err("Command must not be empty".to_string());
+    }
+    if cmd.starts_with('-') {
+        return Err(format!("Invalid command: {}", cmd));
+    }
+    Ok(cmd)
+}
+
+/// Resolve a provider's command and, when found, its version.
+fn probe_provider(provider_type: &str, name: &str, cmd: &str) -> CliProviderEntry {
+    let (available, path) = check_command(cmd);
+    let version = path.as_deref().filter(|_| available).and_then(cli_version);
+    CliProviderEntry {
+        provider_type: provider_type.to_string(),
+        name: name.to_string(),
+        command: cmd.to_string(),
+        available,
+        path,
+        version,
+    }
+}
+
+/// Run `<exe> --version`, killing it if it doesn't exit within the probe timeout.
+fn cli_version(exe: &str) -> Option<String> {
+    let mut child = build_command(exe, &["--version"])
+        .env("PATH", login_shell_path())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let deadline = std::time::Instant::now() + Duration::from_millis(VERSION_PROBE_TIMEOUT_MS);
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if std::time::Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(20));
             }
-        })
-        .collect()
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_version_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// First non-empty line of a `--version` output.
+fn parse_version_output(output: &str) -> Option<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
 }
 
 /// Resolve the user's full login-shell `$PATH`.
@@ -69,6 +302,8 @@ pub fn detect_ai_providers() -> Vec<CliProviderEntry> {
 ///
 /// On Windows, GUI apps inherit the full system PATH — no shell dance
 /// needed.  On fish shell, `$PATH` is a list so we use `string join`.
+/// Inherited entries missing from the shell's PATH are appended, and the
+/// inherited PATH is used as-is if the probe fails or comes back empty.
 ///
 /// The result is cached for the lifetime of the process.
 pub(crate) fn login_shell_path() -> String {
@@ -101,19 +336,34 @@ pub(crate) fn login_shell_path() -> String {
                 .filter(|o| o.status.success())
                 .map(|o| String::from_utf8_lossy(&o.stdout).to_string());
 
+            let inherited = std::env::var("PATH").unwrap_or_default();
             if let Some(raw) = output {
                 if let Some(start) = raw.find(START) {
                     if let Some(end) = raw.find(END) {
-                        let path = &raw[start + START.len()..end];
-                        return path.trim().to_string();
+                        let path = raw[start + START.len()..end].trim();
+                        if !path.is_empty() {
+                            return merge_paths(path, &inherited);
+                        }
                     }
                 }
             }
-            std::env::var("PATH").unwrap_or_default()
+            inherited
         })
         .clone()
 }
 
+/// `primary` followed by any `extra` entries it lacks, so nothing the app
+/// inherited is lost when the login-shell PATH replaces it.
+fn merge_paths(primary: &str, extra: &str) -> String {
+    let mut entries: Vec<&str> = Vec::new();
+    for entry in primary.split(':').chain(extra.split(':')) {
+        if !entry.is_empty() && !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+    entries.join(":")
+}
+
 fn check_command(cmd: &str) -> (bool, Option<String>) {
     let which_cmd = if cfg!(target_os = "windows") {
         "where"
@@ -174,6 +424,43 @@ pub fn read_env_api_keys() -> std::collections::HashMap<String, String> {
 // Shared Helpers (test / list / validate)
 // ============================================================================
 
+/// Shared HTTP client for prompt execution.
+///
+/// Reusing one client keeps the connection pool (DNS + TLS sessions) warm
+/// across prompts. No global timeout — set per request where needed.
+/// Proxies come from `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY`, with hosts in
+/// `NO_PROXY` (e.g. `localhost` for Ollama) connected to directly.
+fn http_client() -> &'static reqwest::Client {
+    use std::sync::OnceLock;
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+/// Clients for explicitly configured proxies, keyed by proxy URL.
+static PROXY_CLIENTS: LazyLock<Mutex<HashMap<String, reqwest::Client>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Client for a prompt: the shared client, or one that routes through
+/// `proxy_url` (still honoring `NO_PROXY`).
+fn client_for_proxy(proxy_url: Option<&str>) -> Result<reqwest::Client, String> {
+    let Some(url) = proxy_url.map(str::trim).filter(|u| !u.is_empty()) else {
+        return Ok(http_client().clone());
+    };
+    let mut clients = PROXY_CLIENTS.lock().unwrap_or_else(|p| p.into_inner());
+    if let Some(client) = clients.get(url) {
+        return Ok(client.clone());
+    }
+    let proxy = reqwest::Proxy::all(url)
+        .map_err(|e| format!("Invalid proxy URL {}: {}", url, e))?
+        .no_proxy(reqwest::NoProxy::from_env());
+    let client = reqwest::Client::builder()
+        .proxy(proxy)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    clients.insert(url.to_string(), client.clone());
+    Ok(client)
+}
+
 fn make_client(timeout_secs: u64) -> Result<reqwest::Client, String> {
     reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(timeout_secs))
@@ -279,6 +566,38 @@ pub async fn test_api_key(
     }
 }
 
+/// Timeout for key validation so the settings UI never hangs
+const KEY_VALIDATION_TIMEOUT_SECS: u64 = 10;
+
+/// Check whether an API key is accepted, via the provider's (free) models
+/// endpoint. Returns `Ok(false)` when the key is rejected (401/403) and
+/// `Err` for network failures or unexpected responses.
+#[command]
+pub async fn validate_ai_key(
+    provider: String,
+    api_key: String,
+    endpoint: Option<String>,
+) -> Result<bool, String> {
+    if !matches!(provider.as_str(), "openai" | "anthropic" | "google-ai") {
+        return Err(format!("{} does not use an API key", provider));
+    }
+    let resp = models_request(http_client(), &provider, Some(api_key), endpoint)?
+        .timeout(std::time::Duration::from_secs(KEY_VALIDATION_TIMEOUT_SECS))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    key_accepted(resp.status().as_u16())
+}
+
+/// Map a validation response status to accepted / rejected / error.
+fn key_accepted(status: u16) -> Result<bool, String> {
+    match status {
+        200..=299 => Ok(true),
+        401 | 403 => Ok(false),
+        _ => Err(format!("Unexpected HTTP status {}", status)),
+    }
+}
+
 // ============================================================================
 // Model Listing
 // ============================================================================
@@ -298,90 +617,26 @@ pub async fn list_models(
     let client = make_client(10)?;
 
     match provider.as_str() {
-        "ollama-api" => {
-            let base = resolve_endpoint(endpoint, "http://localhost:11434");
-            let resp = client
-                .get(format!("{}/api/tags", base))
-                .send()
-                .await
-                .map_err(|e| format!("Request failed: {}", e))?;
-            let resp = check_response(resp).await?;
-            let json: serde_json::Value = resp
-                .json()
-                .await
-                .map_err(|e| format!("Failed to parse response: {}", e))?;
-            let models = json
-                .get("models")
-                .and_then(|m| m.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|m| m.get("name").and_then(|n| n.as_str()).map(String::from))
-                        .collect()
-                })
-                .unwrap_or_default();
-            Ok(models)
-        }
+        "ollama-api" => fetch_model_ids(&client, &provider, api_key, endpoint).await,
 
         "openai" => {
-            let key = require_key(api_key)?;
-            let base = resolve_endpoint(endpoint, "https://api.openai.com");
-            let resp = client
-                .get(format!("{}/v1/models", base))
-                .header("Authorization", format!("Bearer {}", key))
-                .send()
-                .await
-                .map_err(|e| format!("Request failed: {}", e))?;
-            let resp = check_response(resp).await?;
-            let json: serde_json::Value = resp
-                .json()
-                .await
-                .map_err(|e| format!("Failed to parse response: {}", e))?;
             // Use dash-suffixed prefixes to avoid false matches (e.g. "o1" matching "o100-*")
             let prefixes = ["gpt-", "o1-", "o3-", "o4-", "chatgpt-"];
             let exact = ["o1", "o3", "o4"];
-            let mut models: Vec<String> = json
-                .get("data")
-                .and_then(|d| d.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|m| m.get("id").and_then(|id| id.as_str()).map(String::from))
-                        .filter(|id| {
-                            prefixes.iter().any(|p| id.starts_with(p))
-                                || exact.iter().any(|e| id.as_str() == *e)
-                        })
-                        .collect()
+            let mut models: Vec<String> = fetch_model_ids(&client, &provider, api_key, endpoint)
+                .await?
+                .into_iter()
+                .filter(|id| {
+                    prefixes.iter().any(|p| id.starts_with(p))
+                        || exact.iter().any(|e| id.as_str() == *e)
                 })
-                .unwrap_or_default();
+                .collect();
             models.sort();
             Ok(models)
         }
 
         "google-ai" => {
-            let key = require_key(api_key)?;
-            let resp = client
-                .get("https://generativelanguage.googleapis.com/v1beta/models")
-                .header("x-goog-api-key", &key)
-                .send()
-                .await
-                .map_err(|e| format!("Request failed: {}", e))?;
-            let resp = check_response(resp).await?;
-            let json: serde_json::Value = resp
-                .json()
-                .await
-                .map_err(|e| format!("Failed to parse response: {}", e))?;
-            let mut models: Vec<String> = json
-                .get("models")
-                .and_then(|m| m.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|m| {
-                            m.get("name")
-                                .and_then(|n| n.as_str())
-                                .map(|n| n.strip_prefix("models/").unwrap_or(n).to_string())
-                        })
-                        .collect()
-                })
-                .unwrap_or_default();
+            let mut models = fetch_model_ids(&client, &provider, api_key, endpoint).await?;
             models.sort();
             Ok(models)
         }
@@ -395,6 +650,104 @@ pub async fn list_models(
     }
 }
 
+/// List every model id a REST provider reports, sorted alphabetically.
+///
+/// Unlike `list_models`, nothing is filtered or curated: Anthropic is queried
+/// through its `/v1/models` endpoint too.
+#[command]
+pub async fn list_ai_models(
+    provider: String,
+    api_key: Option<String>,
+    endpoint: Option<String>,
+) -> Result<Vec<String>, String> {
+    if matches!(provider.as_str(), "claude" | "codex" | "gemini") {
+        return Err(format!(
+            "Model listing is not supported for CLI provider {}",
+            provider
+        ));
+    }
+    let client = make_client(10)?;
+    let mut models = fetch_model_ids(&client, &provider, api_key, endpoint).await?;
+    models.sort();
+    models.dedup();
+    Ok(models)
+}
+
+/// Query a provider's models endpoint and return the raw model ids.
+async fn fetch_model_ids(
+    client: &reqwest::Client,
+    provider: &str,
+    api_key: Option<String>,
+    endpoint: Option<String>,
+) -> Result<Vec<String>, String> {
+    let resp = models_request(client, provider, api_key, endpoint)?
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    let resp = check_response(resp).await?;
+    let json: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    Ok(parse_model_ids(provider, &json))
+}
+
+/// Build the authenticated models-endpoint request for a provider.
+fn models_request(
+    client: &reqwest::Client,
+    provider: &str,
+    api_key: Option<String>,
+    endpoint: Option<String>,
+) -> Result<reqwest::RequestBuilder, String> {
+    let request = match provider {
+        "ollama-api" => {
+            let base = resolve_endpoint(endpoint, "http://localhost:11434");
+            client.get(format!("{}/api/tags", base))
+        }
+        "openai" => {
+            let key = require_key(api_key)?;
+            let base = resolve_endpoint(endpoint, "https://api.openai.com");
+            client
+                .get(format!("{}/v1/models", base))
+                .header("Authorization", format!("Bearer {}", key))
+        }
+        "google-ai" => {
+            let key = require_key(api_key)?;
+            client
+                .get("https://generativelanguage.googleapis.com/v1beta/models?pageSize=1000")
+                .header("x-goog-api-key", key)
+        }
+        "anthropic" => {
+            let key = require_key(api_key)?;
+            let base = resolve_endpoint(endpoint, "https://api.anthropic.com");
+            client
+                .get(format!("{}/v1/models?limit=1000", base))
+                .header("x-api-key", key)
+                .header("anthropic-version", "2023-06-01")
+        }
+        _ => return Err(format!("Unknown provider: {}", provider)),
+    };
+    Ok(request)
+}
+
+/// Extract model ids from a models-endpoint response.
+fn parse_model_ids(provider: &str, json: &serde_json::Value) -> Vec<String> {
+    let (list_key, id_key) = match provider {
+        "ollama-api" => ("models", "name"),
+        "google-ai" => ("models", "name"),
+        _ => ("data", "id"),
+    };
+    json.get(list_key)
+        .and_then(|m| m.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|m| m.get(id_key).and_then(|id| id.as_str()))
+                .map(|id| id.strip_prefix("models/").unwrap_or(id).to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 // ============================================================================
 // Model Validation
 // ============================================================================
@@ -495,6 +848,242 @@ pub async fn validate_model(
     }
 }
 
+// ============================================================================
+// Connection Warm-up
+// ============================================================================
+
+/// Timeout for warm-up requests — this must never make the UI wait long.
+const WARM_UP_TIMEOUT_SECS: u64 = 5;
+/// Timeout for the Ollama server health check.
+const OLLAMA_HEALTH_TIMEOUT_SECS: u64 = 2;
+
+/// Pre-establish a pooled connection to a REST provider.
+///
+/// Sends a cheap HEAD request through the shared client so the first prompt
+/// doesn't pay DNS + TLS handshake latency. Errors are ignored for cloud
+/// providers; for Ollama an unreachable server is reported since the user
+/// needs to start it. CLI providers are a no-op.
+#[command]
+pub async fn warm_provider(provider: String, endpoint: Option<String>) -> Result<(), String> {
+    let base = match provider.as_str() {
+        "anthropic" => resolve_endpoint(endpoint, "https://api.anthropic.com"),
+        "openai" => resolve_endpoint(endpoint, "https://api.openai.com"),
+        "google-ai" => "https://generativelanguage.googleapis.com".to_string(),
+        "ollama-api" => {
+            let base = resolve_endpoint(endpoint, "http://localhost:11434");
+            return http_client()
+                .get(format!("{}/api/tags", base))
+                .timeout(std::time::Duration::from_secs(WARM_UP_TIMEOUT_SECS))
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(|_| format!("Ollama is not reachable at {}", base));
+        }
+        _ => return Ok(()),
+    };
+
+    // Any response (even 404) means the connection is pooled
+    if let Err(_e) = http_client()
+        .head(&base)
+        .timeout(std::time::Duration::from_secs(WARM_UP_TIMEOUT_SECS))
+        .send()
+        .await
+    {
+        #[cfg(debug_assertions)]
+        eprintln!("[AI] Warm-up for {} failed: {}", provider, _e);
+    }
+    Ok(())
+}
+
+/// Whether an Ollama server answers `GET /api/tags` at `endpoint` (default
+/// `http://localhost:11434`). `detect_ai_providers` only finds the binary;
+/// this tells "installed but not running" apart from "ready". Errors only
+/// on an invalid endpoint.
+#[command]
+pub async fn check_ollama_server(endpoint: Option<String>) -> Result<bool, String> {
+    let base = resolve_endpoint(endpoint, "http://localhost:11434");
+    let url = reqwest::Url::parse(&format!("{}/api/tags", base.trim_end_matches('/')))
+        .map_err(|e| format!("Invalid Ollama endpoint {}: {}", base, e))?;
+    let resp = http_client()
+        .get(url)
+        .timeout(std::time::Duration::from_secs(OLLAMA_HEALTH_TIMEOUT_SECS))
+        .send()
+        .await;
+    Ok(resp.is_ok_and(|r| r.status().is_success()))
+}
+
+// ============================================================================
+// Concurrency Limit & Cancellation
+// ============================================================================
+
+/// Default maximum number of prompts running at once
+const DEFAULT_AI_CONCURRENCY: usize = 4;
+/// Upper bound accepted by `set_ai_concurrency`
+const MAX_AI_CONCURRENCY: usize = 32;
+/// Default limit on a whole REST request, including the streamed body
+const DEFAULT_REST_TIMEOUT_SECS: u64 = 120;
+/// Default limit on a CLI provider run before its process is killed
+const DEFAULT_CLI_TIMEOUT_SECS: u64 = 180;
+/// Azure OpenAI REST API version used when the caller doesn't set one
+const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
+/// Anthropic requires `max_tokens`; used when the caller doesn't set one
+const DEFAULT_ANTHROPIC_MAX_TOKENS: u32 = 4096;
+/// Characters per chunk streamed by the `echo` provider
+const ECHO_CHUNK_CHARS: usize = 8;
+/// Delay between `echo` provider chunks
+const ECHO_CHUNK_DELAY: Duration = Duration::from_millis(40);
+/// Prompt prefix that makes the `echo` provider fail with the rest as message
+const ECHO_ERROR_TRIGGER: &str = "__error__";
+
+/// Limits concurrent prompts. Replaced (not resized) on `set_ai_concurrency`;
+/// permits held on the old semaphore are simply released into it.
+static AI_SEMAPHORE: LazyLock<Mutex<Arc<Semaphore>>> =
+    LazyLock::new(|| Mutex::new(Arc::new(Semaphore::new(DEFAULT_AI_CONCURRENCY))));
+
+/// Whether prompts beyond the limit wait for a slot (true) or are rejected.
+static AI_QUEUE_WHEN_BUSY: AtomicBool = AtomicBool::new(true);
+
+/// Stop signals for queued and running prompts, keyed by request id.
+static ACTIVE_REQUESTS: LazyLock<Mutex<HashMap<String, watch::Sender<Option<StopReason>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Why a request was stopped before finishing on its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StopReason {
+    Cancelled,
+    /// CLI run exceeded its timeout (seconds)
+    TimedOut(u64),
+}
+
+/// Running CLI child processes, keyed by request id, so cancel can kill them.
+static CLI_CHILDREN: LazyLock<Mutex<HashMap<String, Arc<Mutex<Child>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn current_semaphore() -> Arc<Semaphore> {
+    AI_SEMAPHORE
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .clone()
+}
+
+/// Removes a request from the cancellation registry when dropped, so every
+/// exit path (done, error, cancel, panic) cleans up.
+struct RequestGuard {
+    request_id: String,
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        ACTIVE_REQUESTS
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .remove(&self.request_id);
+        CLI_CHILDREN
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .remove(&self.request_id);
+        ai_transcript::discard(&self.request_id);
+    }
+}
+
+/// Register a request so it can be stopped. Fails if a request with the
+/// same id is still in flight (e.g. a double-click fired it twice).
+fn register_request(
+    request_id: &str,
+) -> Result<(RequestGuard, watch::Receiver<Option<StopReason>>), String> {
+    let mut registry = ACTIVE_REQUESTS.lock().unwrap_or_else(|p| p.into_inner());
+    if registry.contains_key(request_id) {
+        return Err(format!("Duplicate request id: {}", request_id));
+    }
+    let (tx, rx) = watch::channel(None);
+    registry.insert(request_id.to_string(), tx);
+    Ok((
+        RequestGuard {
+            request_id: request_id.to_string(),
+        },
+        rx,
+    ))
+}
+
+/// Set the maximum number of AI prompts that may run concurrently.
+/// Requests beyond the limit wait in a queue, or are rejected with an error
+/// when `queue_when_busy` is false (it is left unchanged when omitted).
+#[command]
+pub fn set_ai_concurrency(limit: usize, queue_when_busy: Option<bool>) -> Result<(), String> {
+    if !(1..=MAX_AI_CONCURRENCY).contains(&limit) {
+        return Err(format!(
+            "Concurrency limit must be between 1 and {}",
+            MAX_AI_CONCURRENCY
+        ));
+    }
+    *AI_SEMAPHORE.lock().unwrap_or_else(|p| p.into_inner()) = Arc::new(Semaphore::new(limit));
+    if let Some(queue) = queue_when_busy {
+        AI_QUEUE_WHEN_BUSY.store(queue, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Kill a request's CLI child process, if one is running.
+fn kill_cli_child(request_id: &str) {
+    let child = CLI_CHILDREN
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .get(request_id)
+        .cloned();
+    if let Some(child) = child {
+        let _ = child.lock().unwrap_or_else(|p| p.into_inner()).kill();
+    }
+}
+
+/// Stop a request: signal its waiters and kill its CLI child, if any. Shared
+/// by explicit cancel and the CLI watchdog; the first reason recorded wins.
+fn stop_request(request_id: &str, reason: StopReason) -> bool {
+    let found = {
+        let registry = ACTIVE_REQUESTS.lock().unwrap_or_else(|p| p.into_inner());
+        match registry.get(request_id) {
+            Some(tx) => {
+                tx.send_if_modified(|current| {
+                    if current.is_some() {
+                        return false;
+                    }
+                    *current = Some(reason);
+                    true
+                });
+                true
+            }
+            None => false,
+        }
+    };
+    if found {
+        kill_cli_child(request_id);
+    }
+    found
+}
+
+/// Kills a CLI run that outlives its timeout. Dropping it disarms the timer.
+struct CliWatchdog {
+    _disarm: mpsc::Sender<()>,
+}
+
+fn arm_cli_watchdog(request_id: &str, timeout_secs: u64) -> CliWatchdog {
+    let (tx, rx) = mpsc::channel::<()>();
+    let request_id = request_id.to_string();
+    std::thread::spawn(move || {
+        if let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(Duration::from_secs(timeout_secs)) {
+            stop_request(&request_id, StopReason::TimedOut(timeout_secs));
+        }
+    });
+    CliWatchdog { _disarm: tx }
+}
+
+/// Cancel a queued or running prompt. CLI children are killed; REST requests
+/// are dropped mid-stream. Returns false if the request is unknown (already
+/// finished or never started).
+#[command]
+pub fn cancel_ai_prompt(request_id: String) -> bool {
+    stop_request(&request_id, StopReason::Cancelled)
+}
+
 // ============================================================================
 // Prompt Execution
 // ============================================================================
@@ -503,81 +1092,351 @@ pub async fn validate_model(
 ///
 /// For CLI providers: pipes prompt to stdin of the CLI tool.
 /// For REST providers: sends HTTP request via reqwest.
+/// Either `prompt` or `messages` (conversation history ending with a user
+/// turn) is required; CLI providers receive history flattened into one prompt.
+/// `temperature`, `max_tokens`, `top_p` and `stop` (stop sequences) apply to
+/// REST providers only. `images` go with the last user message and are
+/// only accepted by Anthropic and OpenAI (including Azure).
+/// For `azure-openai`, `endpoint` is the resource URL, `model` the deployment
+/// name, and `api_version` defaults to a GA API version. For
+/// `openai-compatible`, `endpoint` is required, `api_key` is optional, and
+/// `chat_path` overrides `/v1/chat/completions`. `proxy_url` routes REST
+/// requests through an explicit proxy instead of the environment's.
 /// `cli_path` is the resolved absolute path from detection (used on
 /// Windows where bare command names may not find `.cmd`/`.bat` shims).
+/// `stream` (default true) can be set to false for Ollama to receive the
+/// whole reply as a single chunk. `timeout_secs` bounds REST requests from
+/// connect until the last streamed byte (default 120) and CLI runs, whose
+/// process is killed when it expires (default 180). `system` sets the
+/// system prompt; CLI providers without a system prompt flag ignore it.
+/// `env` adds environment variables for CLI providers (e.g. `OLLAMA_HOST`),
+/// which a Dock-launched app may not have inherited; `PATH` already comes
+/// from the login shell.
+/// A `started` status is emitted once the request is accepted (after any
+/// queueing and after the parameters, provider, API key, endpoint and model
+/// are validated), before the provider is contacted. A request rejected
+/// before that always ends with an error chunk.
+/// The `echo` provider does no I/O: it streams the prompt back for frontend
+/// testing, or fails if the last message starts with `__error__`.
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn run_ai_prompt(
     window: WebviewWindow,
     request_id: String,
     provider: String,
-    prompt: String,
+    prompt: Option<String>,
     model: Option<String>,
     api_key: Option<String>,
     endpoint: Option<String>,
     cli_path: Option<String>,
+    stream: Option<bool>,
+    timeout_secs: Option<u64>,
+    system: Option<String>,
+    messages: Option<Vec<ChatMessage>>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    top_p: Option<f32>,
+    api_version: Option<String>,
+    chat_path: Option<String>,
+    proxy_url: Option<String>,
+    env: Option<HashMap<String, String>>,
+    stop: Option<Vec<String>>,
+    images: Option<Vec<ImageAttachment>>,
 ) -> Result<(), String> {
-    let path_ref = cli_path.as_deref();
-    match provider.as_str() {
-        // CLI providers
-        "claude" => run_cli_provider(&window, &request_id, "claude", &["--print", "--output-format", "text"], Some(&prompt), path_ref),
-        "codex" => run_cli_provider(&window, &request_id, "codex", &["exec", &prompt], None, path_ref),
-        "gemini" => run_cli_provider(&window, &request_id, "gemini", &["-p", &prompt], None, path_ref),
-
-        // REST providers
-        "anthropic" => {
-            let Some(key) = require_api_key(&window, &request_id, &api_key, "Anthropic") else {
-                return Ok(());
-            };
-            run_rest_anthropic(
-                &window,
-                &request_id,
-                &endpoint.unwrap_or_else(|| "https://api.anthropic.com".to_string()),
-                key,
-                &model.unwrap_or_else(|| "claude-sonnet-4-5-20250929".to_string()),
-                &prompt,
-            )
-            .await
+    let messages = conversation(prompt, messages)?;
+    let prompt = flatten_conversation(&messages);
+    let (_guard, mut stop_rx) = match register_request(&request_id) {
+        Ok(registered) => registered,
+        Err(e) => {
+            // The running request owns the transcript; only report the rejection
+            emit_error_chunk(&window, &request_id, &e);
+            return Ok(());
         }
-        "openai" => {
-            let Some(key) = require_api_key(&window, &request_id, &api_key, "OpenAI") else {
-                return Ok(());
-            };
-            run_rest_openai(
+    };
+    let model = model
+        .or_else(|| default_model(&provider).map(str::to_string))
+        .unwrap_or_default();
+    ai_transcript::begin(&request_id, &provider, &model, &prompt);
+
+    // Wait for a concurrency slot; the permit is released when dropped
+    let semaphore = current_semaphore();
+    let _permit = match semaphore.clone().try_acquire_owned() {
+        Ok(permit) => permit,
+        Err(_) if !AI_QUEUE_WHEN_BUSY.load(Ordering::SeqCst) => {
+            emit_error(
                 &window,
                 &request_id,
-                &endpoint.unwrap_or_else(|| "https://api.openai.com".to_string()),
-                key,
-                &model.unwrap_or_else(|| "gpt-4o".to_string()),
-                &prompt,
-            )
-            .await
+                "Too many AI requests in progress; try again when one finishes",
+            );
+            return Ok(());
         }
-        "google-ai" => {
-            let Some(key) = require_api_key(&window, &request_id, &api_key, "Google AI") else {
-                return Ok(());
-            };
-            run_rest_google(
+        Err(_) => {
+            emit_status(&window, &request_id, "queued");
+            tokio::select! {
+                permit = semaphore.acquire_owned() => match permit {
+                    Ok(permit) => permit,
+                    Err(e) => {
+                        emit_error(&window, &request_id, &format!("Concurrency limiter closed: {}", e));
+                        return Ok(());
+                    }
+                },
+                _ = stop_rx.wait_for(Option::is_some) => {
+                    emit_cancelled(&window, &request_id);
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    let is_cli = matches!(provider.as_str(), "claude" | "codex" | "gemini");
+    let timeout_secs = timeout_secs.unwrap_or(if is_cli {
+        DEFAULT_CLI_TIMEOUT_SECS
+    } else {
+        DEFAULT_REST_TIMEOUT_SECS
+    });
+    let params = GenerationParams {
+        temperature,
+        max_tokens,
+        top_p,
+        stop,
+    };
+    let images = images.unwrap_or_default();
+    if let Err(e) = validate_images(&provider, &images) {
+        emit_error(&window, &request_id, &e);
+        return Ok(());
+    }
+    if !is_cli {
+        if let Err(e) = params.validate(&provider) {
+            emit_error(&window, &request_id, &e);
+            return Ok(());
+        }
+    }
+    let system = system.as_deref().filter(|s| !s.trim().is_empty());
+    let path_ref = cli_path.as_deref();
+    let cli_env = env.unwrap_or_default();
+    let dispatch_stop = stop_rx.clone();
+    let _watchdog = is_cli.then(|| arm_cli_watchdog(&request_id, timeout_secs));
+    let mut claude_args = vec!["--print", "--output-format", "text"];
+    if let Some(system) = system {
+        claude_args.extend(["--append-system-prompt", system]);
+    }
+    let client = match client_for_proxy(proxy_url.as_deref()) {
+        Ok(client) => client,
+        Err(e) => {
+            emit_error(&window, &request_id, &e);
+            return Ok(());
+        }
+    };
+    let input = PromptInput {
+        model: &model,
+        messages: &messages,
+        system,
+        params: &params,
+        images: &images,
+    };
+
+    if let Err(e) =
+        check_provider_settings(&provider, api_key.as_deref(), endpoint.as_deref(), &model)
+    {
+        emit_error(&window, &request_id, &e);
+        return Ok(());
+    }
+
+    // Accepted: tell the UI before the first (possibly slow) spawn or request
+    emit_status(&window, &request_id, "started");
+    let dispatch = async {
+        match provider.as_str() {
+            // CLI providers
+            "claude" => run_cli_provider(
                 &window,
                 &request_id,
-                key,
-                &model.unwrap_or_else(|| "gemini-2.0-flash".to_string()),
-                &prompt,
-            )
-            .await
-        }
-        "ollama-api" => {
-            run_rest_ollama(
+                "claude",
+                &claude_args,
+                Some(&prompt),
+                path_ref,
+                &cli_env,
+                &dispatch_stop,
+            ),
+            "codex" => run_cli_provider(
                 &window,
                 &request_id,
-                &endpoint.unwrap_or_else(|| "http://localhost:11434".to_string()),
-                &model.unwrap_or_else(|| "llama3.2".to_string()),
-                &prompt,
-            )
-            .await
+                "codex",
+                &["exec", &prompt],
+                None,
+                path_ref,
+                &cli_env,
+                &dispatch_stop,
+            ),
+            "gemini" => run_cli_provider(
+                &window,
+                &request_id,
+                "gemini",
+                &["-p", &prompt],
+                None,
+                path_ref,
+                &cli_env,
+                &dispatch_stop,
+            ),
+
+            // REST providers
+            "anthropic" => {
+                let key = api_key.as_deref().unwrap_or_default();
+                let endpoint = endpoint.unwrap_or_else(|| "https://api.anthropic.com".to_string());
+                run_rest_anthropic(
+                    &window,
+                    &request_id,
+                    &client,
+                    &endpoint,
+                    key,
+                    &input,
+                    &dispatch_stop,
+                )
+                .await
+            }
+            "openai" => {
+                let key = api_key.as_deref().unwrap_or_default();
+                let endpoint = endpoint.unwrap_or_else(|| "https://api.openai.com".to_string());
+                run_rest_openai(
+                    &window,
+                    &request_id,
+                    &client,
+                    "OpenAI",
+                    &openai_chat_url(&endpoint, None),
+                    Some(key),
+                    &input,
+                    &dispatch_stop,
+                )
+                .await
+            }
+            "openai-compatible" => {
+                let endpoint = endpoint.as_deref().unwrap_or_default();
+                run_rest_openai(
+                    &window,
+                    &request_id,
+                    &client,
+                    "OpenAI-compatible",
+                    &openai_chat_url(endpoint, chat_path.as_deref()),
+                    api_key.as_deref().filter(|k| !k.is_empty()),
+                    &input,
+                    &dispatch_stop,
+                )
+                .await
+            }
+            "azure-openai" => {
+                let key = api_key.as_deref().unwrap_or_default();
+                let endpoint = endpoint.as_deref().unwrap_or_default();
+                let url = azure_chat_url(
+                    endpoint,
+                    input.model,
+                    api_version.as_deref().unwrap_or(DEFAULT_AZURE_API_VERSION),
+                );
+                run_rest_azure_openai(
+                    &window,
+                    &request_id,
+                    &client,
+                    &url,
+                    key,
+                    &input,
+                    &dispatch_stop,
+                )
+                .await
+            }
+            "google-ai" => {
+                let key = api_key.as_deref().unwrap_or_default();
+                run_rest_google(&window, &request_id, &client, key, &input, &dispatch_stop).await
+            }
+            "ollama-api" => {
+                let endpoint = endpoint.unwrap_or_else(|| "http://localhost:11434".to_string());
+                run_rest_ollama(
+                    &window,
+                    &request_id,
+                    &client,
+                    &endpoint,
+                    &input,
+                    stream.unwrap_or(true),
+                    &dispatch_stop,
+                )
+                .await
+            }
+
+            "echo" => {
+                run_echo_provider(&window, &request_id, &prompt, &messages).await;
+                Ok(())
+            }
+
+            _ => Err(format!("Unknown provider: {}", provider)),
         }
+    };
 
-        _ => Err(format!("Unknown provider: {}", provider)),
+    // CLI children are killed by `stop_request` and REST bodies stop reading
+    // (see `CancellableBody`); both report the stop themselves. This branch
+    // covers a request stopped while still connecting, which dropping the
+    // dispatch future aborts. `biased` lets a finished dispatch win.
+    let result = tokio::select! {
+        biased;
+        result = dispatch => result,
+        _ = stop_rx.wait_for(Option::is_some) => {
+            emit_cancelled(&window, &request_id);
+            Ok(())
+        }
+        _ = tokio::time::sleep(std::time::Duration::from_secs(timeout_secs)), if !is_cli => {
+            emit_error(&window, &request_id, &format!("Request timed out after {}s", timeout_secs));
+            Ok(())
+        }
+    };
+    if let Err(e) = &result {
+        ai_transcript::finish(&request_id, ai_transcript::Outcome::Failed(e.clone()));
     }
+    result
+}
+
+// ============================================================================
+// Echo Provider
+// ============================================================================
+
+/// Stream `prompt` back in small delayed chunks, then finish. A last message
+/// starting with `ECHO_ERROR_TRIGGER` emits an error instead.
+async fn run_echo_provider(
+    window: &WebviewWindow,
+    request_id: &str,
+    prompt: &str,
+    messages: &[ChatMessage],
+) {
+    let last = messages
+        .last()
+        .map(|m| m.content.as_str())
+        .unwrap_or_default();
+    if let Some(msg) = last.strip_prefix(ECHO_ERROR_TRIGGER) {
+        let msg = msg.trim();
+        emit_error(
+            window,
+            request_id,
+            if msg.is_empty() {
+                "Echo provider error"
+            } else {
+                msg
+            },
+        );
+        return;
+    }
+    for chunk in echo_chunks(prompt, ECHO_CHUNK_CHARS) {
+        tokio::time::sleep(ECHO_CHUNK_DELAY).await;
+        emit_chunk(window, request_id, chunk);
+    }
+    emit_done(window, request_id);
+}
+
+/// Split `text` into chunks of at most `size` characters.
+fn echo_chunks(text: &str, size: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = rest.char_indices().nth(size).map_or(rest.len(), |(i, _)| i);
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
 }
 
 // ============================================================================
@@ -612,7 +1471,9 @@ pub(crate) fn build_command(exe: &str, args: &[&str]) -> Command {
 ///
 /// `cli_path` is the resolved path from detection.  When available it
 /// is used instead of the bare command name so that Windows `.cmd`
-/// shims are handled correctly.
+/// shims are handled correctly. `env` is set on top of the inherited
+/// environment and the login-shell `PATH`, so it may override either.
+#[allow(clippy::too_many_arguments)]
 fn run_cli_provider(
     window: &WebviewWindow,
     request_id: &str,
@@ -620,61 +1481,892 @@ fn run_cli_provider(
     args: &[&str],
     stdin_prompt: Option<&str>,
     cli_path: Option<&str>,
+    env: &HashMap<String, String>,
+    stop: &watch::Receiver<Option<StopReason>>,
 ) -> Result<(), String> {
-    let stdin_cfg = if stdin_prompt.is_some() { Stdio::piped() } else { Stdio::null() };
+    let stdin_cfg = if stdin_prompt.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    };
     let effective_cmd = cli_path.unwrap_or(cmd);
 
     let mut child = build_command(effective_cmd, args)
         .env("PATH", login_shell_path())
+        .envs(env)
         .stdin(stdin_cfg)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
         .map_err(|e| format!("Failed to spawn {}: {}", cmd, e))?;
 
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    // Register the child so `stop_request` can kill it (the request guard
+    // removes it again). A stop that raced the spawn is applied here.
+    let child = Arc::new(Mutex::new(child));
+    CLI_CHILDREN
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .insert(request_id.to_string(), child.clone());
+    if stop.borrow().is_some() {
+        kill_cli_child(request_id);
+    }
+
     // Write prompt to stdin when the provider expects it
-    if let Some(prompt) = stdin_prompt {
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin
-                .write_all(prompt.as_bytes())
-                .map_err(|e| format!("Failed to write to stdin: {}", e))?;
-            // stdin is dropped here, closing it
-        }
-    }
-
-    // Stream stdout line by line
-    if let Some(stdout) = child.stdout.take() {
-        let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            match line {
-                Ok(text) => {
-                    emit_chunk(window, request_id, &(text + "\n"));
-                }
-                Err(e) => {
-                    emit_error(window, request_id, &format!("Read error: {}", e));
-                    let _ = child.kill();
-                    return Ok(());
-                }
+    if let (Some(prompt), Some(mut stdin)) = (stdin_prompt, stdin) {
+        if let Err(e) = stdin.write_all(prompt.as_bytes()) {
+            if stop.borrow().is_none() {
+                kill_cli_child(request_id);
+                return Err(format!("Failed to write to stdin: {}", e));
             }
         }
+        // stdin is dropped here, closing it
+    }
+
+    // Forward stdout exactly as written; ends early when the child is killed
+    if let Some(stdout) = stdout {
+        if let Err(e) = forward_output(stdout, |text| emit_chunk(window, request_id, text)) {
+            kill_cli_child(request_id);
+            if stop.borrow().is_none() {
+                emit_error(window, request_id, &format!("Read error: {}", e));
+                return Ok(());
+            }
+        }
+    }
+
+    let mut stderr_text = String::new();
+    if let Some(mut stderr) = stderr {
+        let _ = stderr.read_to_string(&mut stderr_text);
+    }
+    let status = wait_cli_child(&child).map_err(|e| format!("Wait failed: {}", e))?;
+
+    let stopped = *stop.borrow();
+    match stopped {
+        Some(StopReason::Cancelled) => {
+            emit_cancelled(window, request_id);
+            return Ok(());
+        }
+        Some(StopReason::TimedOut(secs)) => {
+            emit_error(
+                window,
+                request_id,
+                &format!("{} timed out after {}s", cmd, secs),
+            );
+            return Ok(());
+        }
+        None => {}
     }
 
     // Check exit status — include stderr in error message
-    let output = child.wait_with_output().map_err(|e| format!("Wait failed: {}", e))?;
-    if !output.status.success() {
-        let stderr_text = String::from_utf8_lossy(&output.stderr);
+    if !status.success() {
         let stderr_msg = stderr_text.trim();
         let msg = if stderr_msg.is_empty() {
-            format!("{} exited with status {}", cmd, output.status)
+            format!("{} exited with status {}", cmd, status)
         } else {
-            format!("{} exited with status {}: {}", cmd, output.status, stderr_msg)
+            format!("{} exited with status {}: {}", cmd, status, stderr_msg)
         };
         emit_error(window, request_id, &msg);
     } else {
         emit_done(window, request_id);
     }
 
-    Ok(())
+    Ok(())
+}
+
+/// How often a running CLI child is polled for exit
+const CLI_WAIT_POLL: Duration = Duration::from_millis(20);
+
+/// Wait for a registered CLI child. The lock is only held for each
+/// `try_wait`, so `kill_cli_child` (cancel, watchdog) never waits behind us.
+fn wait_cli_child(child: &Mutex<Child>) -> std::io::Result<ExitStatus> {
+    loop {
+        if let Some(status) = child.lock().unwrap_or_else(|p| p.into_inner()).try_wait()? {
+            return Ok(status);
+        }
+        std::thread::sleep(CLI_WAIT_POLL);
+    }
+}
+
+/// Bytes read from a CLI's stdout at a time
+const CLI_READ_BUFFER_BYTES: usize = 4096;
+
+/// Read `reader` to the end, passing each read to `emit` as text. Output is
+/// forwarded as-is: no line buffering, no added or normalized newlines.
+fn forward_output(mut reader: impl Read, mut emit: impl FnMut(&str)) -> std::io::Result<()> {
+    let mut buf = [0u8; CLI_READ_BUFFER_BYTES];
+    let mut decoder = Utf8Buffer::default();
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let text = decoder.push(&buf[..n]);
+        if !text.is_empty() {
+            emit(&text);
+        }
+    }
+    let rest = decoder.finish();
+    if !rest.is_empty() {
+        emit(&rest);
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Streaming Helpers
+// ============================================================================
+
+/// Decodes UTF-8 read in arbitrary pieces, holding back a multi-byte
+/// sequence split across reads until it is complete.
+#[derive(Default)]
+struct Utf8Buffer {
+    pending: Vec<u8>,
+}
+
+impl Utf8Buffer {
+    /// Add bytes and return the text they complete.
+    fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // Only an incomplete sequence at the end is worth waiting for
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        let rest = self.pending.split_off(complete);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = rest;
+        text
+    }
+
+    /// Whatever is left at the end of the stream.
+    fn finish(self) -> String {
+        String::from_utf8_lossy(&self.pending).into_owned()
+    }
+}
+
+/// Splits a byte stream into complete lines, buffering partial lines (and
+/// partial UTF-8 sequences) across network reads.
+#[derive(Default)]
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// Add bytes and return every line completed by them (without `\r\n`).
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(bytes);
+        let mut lines = Vec::new();
+        while let Some(pos) = self.pending.iter().position(|b| *b == b'\n') {
+            let mut line: Vec<u8> = self.pending.drain(..=pos).collect();
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            lines.push(String::from_utf8_lossy(&line).into_owned());
+        }
+        lines
+    }
+}
+
+/// Extracts top-level JSON objects from a byte stream, skipping whatever
+/// sits between them: SSE `data:` prefixes, or the `[`, `,` and `]` of a
+/// streamed JSON array. Partial objects are kept across network reads.
+#[derive(Default)]
+struct JsonObjectBuffer {
+    pending: Vec<u8>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl JsonObjectBuffer {
+    /// Add bytes and return every object completed by them.
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut objects = Vec::new();
+        for &b in bytes {
+            if self.depth == 0 {
+                if b == b'{' {
+                    self.depth = 1;
+                    self.pending.push(b);
+                }
+                continue;
+            }
+            self.pending.push(b);
+            if self.in_string {
+                match b {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match b {
+                b'"' => self.in_string = true,
+                b'{' => self.depth += 1,
+                b'}' => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        objects.push(String::from_utf8_lossy(&self.pending).into_owned());
+                        self.pending.clear();
+                    }
+                }
+                _ => {}
+            }
+        }
+        objects
+    }
+
+    /// Whether an object was started but not finished.
+    fn has_partial(&self) -> bool {
+        self.depth > 0
+    }
+}
+
+/// Payload of an SSE `data:` line, or None for other lines.
+fn sse_data(line: &str) -> Option<&str> {
+    let data = line.strip_prefix("data:")?;
+    Some(data.strip_prefix(' ').unwrap_or(data))
+}
+
+/// A parsed event from a provider's response stream.
+#[derive(Debug, PartialEq)]
+enum StreamEvent {
+    Text(String),
+    Done,
+    Error(String),
+    /// The provider declined to answer (safety block, content filter); the
+    /// reason is shown as "The model declined to answer (reason)"
+    Refused(String),
+    Ignore,
+}
+
+/// Google `finishReason`s meaning the candidate was withheld.
+const GOOGLE_BLOCKED_FINISH_REASONS: &[&str] = &[
+    "SAFETY",
+    "PROHIBITED_CONTENT",
+    "BLOCKLIST",
+    "SPII",
+    "RECITATION",
+];
+
+/// Parse one Anthropic Messages API SSE `data:` payload.
+fn parse_anthropic_event(data: &str) -> StreamEvent {
+    let json: serde_json::Value = match serde_json::from_str(data) {
+        Ok(json) => json,
+        Err(e) => return StreamEvent::Error(format!("Malformed Anthropic stream data: {}", e)),
+    };
+    match json.get("type").and_then(|t| t.as_str()) {
+        Some("content_block_delta") => json
+            .get("delta")
+            .and_then(|d| d.get("text"))
+            .and_then(|t| t.as_str())
+            .map(|t| StreamEvent::Text(t.to_string()))
+            .unwrap_or(StreamEvent::Ignore),
+        Some("message_delta")
+            if json
+                .get("delta")
+                .and_then(|d| d.get("stop_reason"))
+                .and_then(|r| r.as_str())
+                == Some("refusal") =>
+        {
+            StreamEvent::Refused("refusal".to_string())
+        }
+        Some("message_stop") => StreamEvent::Done,
+        Some("error") => StreamEvent::Error(format!(
+            "Anthropic stream error: {}",
+            json.get("error")
+                .and_then(|e| e.get("message"))
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error")
+        )),
+        _ => StreamEvent::Ignore,
+    }
+}
+
+/// Parse one OpenAI chat-completions SSE `data:` payload.
+fn parse_openai_event(data: &str) -> StreamEvent {
+    if data.trim() == "[DONE]" {
+        return StreamEvent::Done;
+    }
+    let json: serde_json::Value = match serde_json::from_str(data) {
+        Ok(json) => json,
+        Err(e) => return StreamEvent::Error(format!("Malformed OpenAI stream data: {}", e)),
+    };
+    if let Some(error) = json.get("error") {
+        return StreamEvent::Error(format!(
+            "OpenAI stream error: {}",
+            error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error")
+        ));
+    }
+    let choice = json
+        .get("choices")
+        .and_then(|c| c.as_array())
+        .and_then(|choices| choices.first());
+    if choice
+        .and_then(|c| c.get("finish_reason"))
+        .and_then(|r| r.as_str())
+        == Some("content_filter")
+    {
+        return StreamEvent::Refused("content filter".to_string());
+    }
+    choice
+        .and_then(|c| c.get("delta"))
+        .and_then(|d| d.get("content"))
+        .and_then(|t| t.as_str())
+        .filter(|t| !t.is_empty())
+        .map(|t| StreamEvent::Text(t.to_string()))
+        .unwrap_or(StreamEvent::Ignore)
+}
+
+/// Parse one Google AI `streamGenerateContent` object. The stream has no
+/// terminal event: it simply ends after the last candidate.
+fn parse_google_event(data: &str) -> StreamEvent {
+    let json: serde_json::Value = match serde_json::from_str(data) {
+        Ok(json) => json,
+        Err(e) => return StreamEvent::Error(format!("Malformed Google AI stream data: {}", e)),
+    };
+    if let Some(error) = json.get("error") {
+        return StreamEvent::Error(format!(
+            "Google AI error: {}",
+            error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error")
+        ));
+    }
+    if let Some(reason) = json
+        .get("promptFeedback")
+        .and_then(|f| f.get("blockReason"))
+        .and_then(|r| r.as_str())
+    {
+        return StreamEvent::Refused(format!("prompt blocked: {}", reason));
+    }
+    let candidate = json
+        .get("candidates")
+        .and_then(|c| c.as_array())
+        .and_then(|candidates| candidates.first());
+    if let Some(reason) = candidate
+        .and_then(|c| c.get("finishReason"))
+        .and_then(|r| r.as_str())
+        .filter(|r| GOOGLE_BLOCKED_FINISH_REASONS.contains(r))
+    {
+        return StreamEvent::Refused(format!("content filter: {}", reason));
+    }
+    let text: String = candidate
+        .and_then(|c| c.get("content"))
+        .and_then(|c| c.get("parts"))
+        .and_then(|p| p.as_array())
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    if text.is_empty() {
+        StreamEvent::Ignore
+    } else {
+        StreamEvent::Text(text)
+    }
+}
+
+/// Response text of an Ollama object: `response` from `/api/generate`,
+/// `message.content` from `/api/chat`.
+fn ollama_text(json: &serde_json::Value) -> Option<&str> {
+    json.get("response")
+        .or_else(|| json.get("message").and_then(|m| m.get("content")))
+        .and_then(|t| t.as_str())
+}
+
+/// Parse one Ollama NDJSON object from `/api/generate` or `/api/chat`.
+/// The final object (`"done": true`) carries no response text.
+fn parse_ollama_event(line: &str) -> StreamEvent {
+    let json: serde_json::Value = match serde_json::from_str(line) {
+        Ok(json) => json,
+        Err(e) => return StreamEvent::Error(format!("Malformed Ollama stream data: {}", e)),
+    };
+    if let Some(error) = json.get("error").and_then(|e| e.as_str()) {
+        return StreamEvent::Error(format!("Ollama error: {}", error));
+    }
+    if json.get("done").and_then(|d| d.as_bool()) == Some(true) {
+        return StreamEvent::Done;
+    }
+    ollama_text(&json)
+        .filter(|t| !t.is_empty())
+        .map(|t| StreamEvent::Text(t.to_string()))
+        .unwrap_or(StreamEvent::Ignore)
+}
+
+/// How events are framed in a response body.
+#[derive(Debug, Clone, Copy)]
+enum Framing {
+    /// Server-sent events: only `data:` lines carry payloads
+    Sse,
+    /// Newline-delimited JSON: every non-empty line is a payload
+    Ndjson,
+    /// Back-to-back JSON objects, either in SSE `data:` lines or in a
+    /// streamed JSON array. There is no terminal event; the body's end is
+    /// the end of the reply.
+    JsonObjects,
+}
+
+/// One read from a `CancellableBody`.
+enum BodyRead {
+    Chunk(Bytes),
+    End,
+    /// The request was stopped; nothing more will be read
+    Stopped,
+    Failed(reqwest::Error),
+}
+
+/// A REST response body read chunk by chunk that gives up as soon as the
+/// request is stopped, so cancel works mid-stream like killing a CLI child.
+/// Dropping it closes the connection.
+struct CancellableBody {
+    stream: Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>,
+    stop: watch::Receiver<Option<StopReason>>,
+}
+
+impl CancellableBody {
+    fn new(resp: reqwest::Response, stop: &watch::Receiver<Option<StopReason>>) -> Self {
+        Self {
+            stream: Box::pin(resp.bytes_stream()),
+            stop: stop.clone(),
+        }
+    }
+
+    async fn next(&mut self) -> BodyRead {
+        tokio::select! {
+            biased;
+            Ok(_) = self.stop.wait_for(Option::is_some) => BodyRead::Stopped,
+            chunk = self.stream.next() => match chunk {
+                Some(Ok(bytes)) => BodyRead::Chunk(bytes),
+                Some(Err(e)) => BodyRead::Failed(e),
+                None => BodyRead::End,
+            },
+        }
+    }
+
+    /// Read the whole body (for non-streaming replies). `None` if the
+    /// request was stopped first.
+    async fn collect(mut self) -> Result<Option<Vec<u8>>, String> {
+        let mut body = Vec::new();
+        loop {
+            match self.next().await {
+                BodyRead::Chunk(bytes) => body.extend_from_slice(&bytes),
+                BodyRead::End => return Ok(Some(body)),
+                BodyRead::Stopped => return Ok(None),
+                BodyRead::Failed(e) => return Err(format!("Failed to read response: {}", e)),
+            }
+        }
+    }
+}
+
+/// Read a whole JSON reply through a `CancellableBody`. Emits the cancelled
+/// chunk and returns `None` if the request was stopped first.
+async fn read_json_reply(
+    window: &WebviewWindow,
+    request_id: &str,
+    resp: reqwest::Response,
+    stop: &watch::Receiver<Option<StopReason>>,
+) -> Result<Option<serde_json::Value>, String> {
+    let Some(body) = CancellableBody::new(resp, stop).collect().await? else {
+        emit_cancelled(window, request_id);
+        return Ok(None);
+    };
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+/// Read a streaming response body, emitting text chunks as they arrive.
+///
+/// Ends with `emit_done` on the provider's terminal event, `emit_cancelled`
+/// when the request is stopped mid-stream, or `emit_error` on a stream
+/// error, malformed payload, or premature end of stream.
+async fn stream_response(
+    window: &WebviewWindow,
+    request_id: &str,
+    resp: reqwest::Response,
+    stop: &watch::Receiver<Option<StopReason>>,
+    provider_name: &str,
+    framing: Framing,
+    parse: fn(&str) -> StreamEvent,
+) -> Result<(), String> {
+    let mut body = CancellableBody::new(resp, stop);
+    let mut lines = LineBuffer::default();
+    let mut objects = JsonObjectBuffer::default();
+    loop {
+        let bytes = match body.next().await {
+            BodyRead::Chunk(bytes) => bytes,
+            BodyRead::End => break,
+            BodyRead::Stopped => {
+                emit_cancelled(window, request_id);
+                return Ok(());
+            }
+            BodyRead::Failed(e) => {
+                emit_error(
+                    window,
+                    request_id,
+                    &format!("{} stream error: {}", provider_name, e),
+                );
+                return Ok(());
+            }
+        };
+        let payloads = match framing {
+            Framing::JsonObjects => objects.push(&bytes),
+            Framing::Sse | Framing::Ndjson => lines.push(&bytes),
+        };
+        for line in payloads {
+            let payload = match framing {
+                Framing::Sse => sse_data(&line),
+                Framing::Ndjson => Some(line.as_str()).filter(|l| !l.trim().is_empty()),
+                Framing::JsonObjects => Some(line.as_str()),
+            };
+            let Some(payload) = payload else {
+                continue;
+            };
+            match parse(payload) {
+                StreamEvent::Text(text) => emit_chunk(window, request_id, &text),
+                StreamEvent::Done => {
+                    emit_done(window, request_id);
+                    return Ok(());
+                }
+                StreamEvent::Error(msg) => {
+                    emit_error(window, request_id, &msg);
+                    return Ok(());
+                }
+                StreamEvent::Refused(reason) => {
+                    emit_refused(window, request_id, &reason);
+                    return Ok(());
+                }
+                StreamEvent::Ignore => {}
+            }
+        }
+    }
+
+    if matches!(framing, Framing::JsonObjects) && !objects.has_partial() {
+        emit_done(window, request_id);
+        return Ok(());
+    }
+    emit_error(
+        window,
+        request_id,
+        &format!("{} stream ended unexpectedly", provider_name),
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Retry
+// ============================================================================
+
+/// Retries after the first attempt for transient REST failures
+const MAX_RETRIES: u32 = 3;
+/// First backoff delay; doubled on each further retry
+const RETRY_BASE_DELAY_MS: u64 = 500;
+/// Random extra delay so parallel prompts don't retry in lockstep
+const RETRY_JITTER_MS: u64 = 250;
+/// Cap on a server-requested `Retry-After` delay
+const MAX_RETRY_AFTER_SECS: u64 = 30;
+
+/// Statuses worth retrying: timeouts, rate limits, and gateway/server errors.
+fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 408 | 429 | 500 | 502 | 503 | 504)
+}
+
+/// Parse a `Retry-After` header value (delay in seconds or an HTTP date).
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Delay before retry number `retry` (0-based): the server's `Retry-After`
+/// when given, otherwise exponential backoff plus jitter.
+fn backoff_delay(retry: u32, retry_after: Option<Duration>, jitter_ms: u64) -> Duration {
+    match retry_after {
+        Some(delay) => delay.min(Duration::from_secs(MAX_RETRY_AFTER_SECS)),
+        None => Duration::from_millis((RETRY_BASE_DELAY_MS << retry) + jitter_ms),
+    }
+}
+
+fn jitter_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| u64::from(d.subsec_nanos()) % RETRY_JITTER_MS)
+        .unwrap_or(0)
+}
+
+/// Send a request, retrying connection errors and retryable statuses with
+/// backoff. Emits a "retrying" status before each retry. The final response
+/// is returned as-is, so callers still handle non-success statuses.
+async fn send_with_retry(
+    window: &WebviewWindow,
+    request_id: &str,
+    provider_name: &str,
+    request: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response, String> {
+    let mut retry = 0;
+    loop {
+        let retry_after = match request().send().await {
+            Ok(resp) if retry < MAX_RETRIES && is_retryable_status(resp.status().as_u16()) => resp
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| parse_retry_after(v, Utc::now())),
+            Ok(resp) => return Ok(resp),
+            Err(e) if retry < MAX_RETRIES && (e.is_connect() || e.is_timeout()) => None,
+            Err(e) => return Err(format!("{} request failed: {}", provider_name, e)),
+        };
+        emit_status(window, request_id, "retrying");
+        tokio::time::sleep(backoff_delay(retry, retry_after, jitter_ms())).await;
+        retry += 1;
+    }
+}
+
+// ============================================================================
+// Request Bodies
+// ============================================================================
+
+/// Model used when the caller doesn't choose one.
+fn default_model(provider: &str) -> Option<&'static str> {
+    match provider {
+        "anthropic" => Some("claude-sonnet-4-5-20250929"),
+        "openai" => Some("gpt-4o"),
+        "google-ai" => Some("gemini-2.0-flash"),
+        "ollama-api" => Some("llama3.2"),
+        _ => None,
+    }
+}
+
+/// Build the conversation from either `messages` or a single `prompt`.
+fn conversation(
+    prompt: Option<String>,
+    messages: Option<Vec<ChatMessage>>,
+) -> Result<Vec<ChatMessage>, String> {
+    let messages = match (messages, prompt) {
+        (Some(messages), _) if !messages.is_empty() => messages,
+        (_, Some(prompt)) => vec![ChatMessage {
+            role: "user".to_string(),
+            content: prompt,
+        }],
+        _ => return Err("Either prompt or messages is required".to_string()),
+    };
+    if let Some(m) = messages
+        .iter()
+        .find(|m| m.role != "user" && m.role != "assistant")
+    {
+        return Err(format!("Unsupported message role: {}", m.role));
+    }
+    if messages.last().map(|m| m.role.as_str()) != Some("user") {
+        return Err("The last message must be from the user".to_string());
+    }
+    Ok(messages)
+}
+
+/// Flatten a conversation into a single prompt (for CLI providers).
+fn flatten_conversation(messages: &[ChatMessage]) -> String {
+    if let [only] = messages {
+        return only.content.clone();
+    }
+    messages
+        .iter()
+        .map(|m| {
+            let speaker = if m.role == "assistant" {
+                "Assistant"
+            } else {
+                "User"
+            };
+            format!("{}: {}", speaker, m.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Set `body[key]` only when a value is given.
+fn set_if_some<T: Serialize>(body: &mut serde_json::Value, key: &str, value: Option<T>) {
+    if let Some(value) = value {
+        body[key] = serde_json::json!(value);
+    }
+}
+
+fn anthropic_body(input: &PromptInput<'_>) -> serde_json::Value {
+    let PromptInput {
+        model,
+        messages,
+        system,
+        params,
+        images,
+    } = *input;
+    let mut body = serde_json::json!({
+        "model": model,
+        "max_tokens": params.max_tokens.unwrap_or(DEFAULT_ANTHROPIC_MAX_TOKENS),
+        "stream": true,
+        "messages": messages
+    });
+    if !images.is_empty() {
+        let mut content: Vec<serde_json::Value> = images
+            .iter()
+            .map(|image| {
+                serde_json::json!({
+                    "type": "image",
+                    "source": {"type": "base64", "media_type": image.mime_type, "data": image.data}
+                })
+            })
+            .collect();
+        if let Some(last) = messages.last() {
+            content.push(serde_json::json!({"type": "text", "text": last.content}));
+        }
+        attach_to_last_message(&mut body["messages"], content);
+    }
+    set_if_some(&mut body, "system", system);
+    set_if_some(&mut body, "temperature", params.temperature);
+    set_if_some(&mut body, "top_p", params.top_p);
+    set_if_some(&mut body, "stop_sequences", params.stop_sequences());
+    body
+}
+
+/// OpenAI-style message array with an optional leading system message
+/// (also used by Ollama's `/api/chat`).
+fn messages_with_system(messages: &[ChatMessage], system: Option<&str>) -> Vec<serde_json::Value> {
+    let mut out = Vec::with_capacity(messages.len() + 1);
+    if let Some(system) = system {
+        out.push(serde_json::json!({"role": "system", "content": system}));
+    }
+    out.extend(
+        messages
+            .iter()
+            .map(|m| serde_json::json!({"role": m.role, "content": m.content})),
+    );
+    out
+}
+
+fn openai_body(input: &PromptInput<'_>) -> serde_json::Value {
+    let PromptInput {
+        model,
+        messages,
+        system,
+        params,
+        images,
+    } = *input;
+    let mut body = serde_json::json!({
+        "model": model,
+        "stream": true,
+        "messages": messages_with_system(messages, system)
+    });
+    if !images.is_empty() {
+        let mut content = Vec::with_capacity(images.len() + 1);
+        if let Some(last) = messages.last() {
+            content.push(serde_json::json!({"type": "text", "text": last.content}));
+        }
+        content.extend(images.iter().map(|image| {
+            serde_json::json!({
+                "type": "image_url",
+                "image_url": {"url": format!("data:{};base64,{}", image.mime_type, image.data)}
+            })
+        }));
+        attach_to_last_message(&mut body["messages"], content);
+    }
+    set_if_some(&mut body, "temperature", params.temperature);
+    set_if_some(&mut body, "max_tokens", params.max_tokens);
+    set_if_some(&mut body, "top_p", params.top_p);
+    set_if_some(&mut body, "stop", params.stop_sequences());
+    body
+}
+
+/// Replace the last message's text content with multi-part `content`.
+fn attach_to_last_message(messages: &mut serde_json::Value, content: Vec<serde_json::Value>) {
+    if let Some(last) = messages.as_array_mut().and_then(|m| m.last_mut()) {
+        last["content"] = serde_json::Value::Array(content);
+    }
+}
+
+fn google_body(input: &PromptInput<'_>) -> serde_json::Value {
+    let PromptInput {
+        messages,
+        system,
+        params,
+        ..
+    } = *input;
+    let contents: Vec<serde_json::Value> = messages
+        .iter()
+        .map(|m| {
+            let role = if m.role == "assistant" {
+                "model"
+            } else {
+                "user"
+            };
+            serde_json::json!({"role": role, "parts": [{"text": m.content}]})
+        })
+        .collect();
+    let mut body = serde_json::json!({ "contents": contents });
+    if let Some(system) = system {
+        body["systemInstruction"] = serde_json::json!({"parts": [{"text": system}]});
+    }
+    let mut config = serde_json::json!({});
+    set_if_some(&mut config, "temperature", params.temperature);
+    set_if_some(&mut config, "maxOutputTokens", params.max_tokens);
+    set_if_some(&mut config, "topP", params.top_p);
+    set_if_some(&mut config, "stopSequences", params.stop_sequences());
+    if config.as_object().is_some_and(|c| !c.is_empty()) {
+        body["generationConfig"] = config;
+    }
+    body
+}
+
+/// Ollama endpoint path and body: `/api/generate` for a single prompt,
+/// `/api/chat` when there is conversation history.
+fn ollama_request(input: &PromptInput<'_>, stream: bool) -> (&'static str, serde_json::Value) {
+    let PromptInput {
+        model,
+        messages,
+        system,
+        params,
+        ..
+    } = *input;
+    let (path, mut body) = if let [only] = messages {
+        let mut body = serde_json::json!({
+            "model": model,
+            "prompt": only.content,
+            "stream": stream
+        });
+        set_if_some(&mut body, "system", system);
+        ("/api/generate", body)
+    } else {
+        let body = serde_json::json!({
+            "model": model,
+            "messages": messages_with_system(messages, system),
+            "stream": stream
+        });
+        ("/api/chat", body)
+    };
+    let mut options = serde_json::json!({});
+    set_if_some(&mut options, "temperature", params.temperature);
+    set_if_some(&mut options, "num_predict", params.max_tokens);
+    set_if_some(&mut options, "top_p", params.top_p);
+    set_if_some(&mut options, "stop", params.stop_sequences());
+    if options.as_object().is_some_and(|o| !o.is_empty()) {
+        body["options"] = options;
+    }
+    (path, body)
 }
 
 // ============================================================================
@@ -684,203 +2376,260 @@ fn run_cli_provider(
 async fn run_rest_anthropic(
     window: &WebviewWindow,
     request_id: &str,
+    client: &reqwest::Client,
     endpoint: &str,
     api_key: &str,
-    model: &str,
-    prompt: &str,
+    input: &PromptInput<'_>,
+    stop: &watch::Receiver<Option<StopReason>>,
 ) -> Result<(), String> {
-    let client = reqwest::Client::new();
-    let body = serde_json::json!({
-        "model": model,
-        "max_tokens": 4096,
-        "messages": [{"role": "user", "content": prompt}]
-    });
-
-    let resp = client
-        .post(format!("{}/v1/messages", endpoint))
-        .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header("content-type", "application/json")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("Anthropic request failed: {}", e))?;
+    let body = anthropic_body(input);
+
+    let resp = send_with_retry(window, request_id, "Anthropic", || {
+        client
+            .post(format!("{}/v1/messages", endpoint))
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("content-type", "application/json")
+            .json(&body)
+    })
+    .await?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        emit_error(window, request_id, &format!("Anthropic API error {}: {}", status, text));
+        emit_error(
+            window,
+            request_id,
+            &format!("Anthropic API error {}: {}", status, text),
+        );
         return Ok(());
     }
 
-    let json: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    stream_response(
+        window,
+        request_id,
+        resp,
+        stop,
+        "Anthropic",
+        Framing::Sse,
+        parse_anthropic_event,
+    )
+    .await
+}
 
-    // Extract text from content blocks
-    if let Some(content) = json.get("content").and_then(|c| c.as_array()) {
-        for block in content {
-            if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
-                emit_chunk(window, request_id, text);
-            }
+/// Chat completions URL for OpenAI or a compatible server.
+fn openai_chat_url(endpoint: &str, path: Option<&str>) -> String {
+    let path = path
+        .filter(|p| !p.is_empty())
+        .unwrap_or("/v1/chat/completions");
+    format!(
+        "{}/{}",
+        endpoint.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// OpenAI chat completions, also used for OpenAI-compatible servers (which
+/// may not need an API key).
+#[allow(clippy::too_many_arguments)]
+async fn run_rest_openai(
+    window: &WebviewWindow,
+    request_id: &str,
+    client: &reqwest::Client,
+    provider_name: &str,
+    url: &str,
+    api_key: Option<&str>,
+    input: &PromptInput<'_>,
+    stop: &watch::Receiver<Option<StopReason>>,
+) -> Result<(), String> {
+    let body = openai_body(input);
+
+    let resp = send_with_retry(window, request_id, provider_name, || {
+        let request = client
+            .post(url)
+            .header("content-type", "application/json")
+            .json(&body);
+        match api_key {
+            Some(key) => request.header("Authorization", format!("Bearer {}", key)),
+            None => request,
         }
-    } else {
-        emit_error(window, request_id, "No content blocks in Anthropic response");
+    })
+    .await?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        emit_error(
+            window,
+            request_id,
+            &format!("{} API error {}: {}", provider_name, status, text),
+        );
         return Ok(());
     }
 
-    emit_done(window, request_id);
-    Ok(())
+    stream_response(
+        window,
+        request_id,
+        resp,
+        stop,
+        provider_name,
+        Framing::Sse,
+        parse_openai_event,
+    )
+    .await
 }
 
-async fn run_rest_openai(
+/// Chat completions URL for an Azure OpenAI deployment.
+fn azure_chat_url(endpoint: &str, deployment: &str, api_version: &str) -> String {
+    format!(
+        "{}/openai/deployments/{}/chat/completions?api-version={}",
+        endpoint.trim_end_matches('/'),
+        deployment,
+        api_version
+    )
+}
+
+/// Azure OpenAI: same body and SSE stream as OpenAI, with a per-deployment
+/// URL and an `api-key` header.
+async fn run_rest_azure_openai(
     window: &WebviewWindow,
     request_id: &str,
-    endpoint: &str,
+    client: &reqwest::Client,
+    url: &str,
     api_key: &str,
-    model: &str,
-    prompt: &str,
+    input: &PromptInput<'_>,
+    stop: &watch::Receiver<Option<StopReason>>,
 ) -> Result<(), String> {
-    let client = reqwest::Client::new();
-    let body = serde_json::json!({
-        "model": model,
-        "messages": [{"role": "user", "content": prompt}]
-    });
+    let body = openai_body(input);
 
-    let resp = client
-        .post(format!("{}/v1/chat/completions", endpoint))
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("content-type", "application/json")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("OpenAI request failed: {}", e))?;
+    let resp = send_with_retry(window, request_id, "Azure OpenAI", || {
+        client
+            .post(url)
+            .header("api-key", api_key)
+            .header("content-type", "application/json")
+            .json(&body)
+    })
+    .await?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        emit_error(window, request_id, &format!("OpenAI API error {}: {}", status, text));
-        return Ok(());
-    }
-
-    let json: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    if let Some(text) = json
-        .get("choices")
-        .and_then(|c| c.as_array())
-        .and_then(|choices| choices.first())
-        .and_then(|c| c.get("message"))
-        .and_then(|m| m.get("content"))
-        .and_then(|t| t.as_str())
-    {
-        emit_chunk(window, request_id, text);
-    } else {
-        emit_error(window, request_id, "No choices in OpenAI response");
+        emit_error(
+            window,
+            request_id,
+            &format!("Azure OpenAI API error {}: {}", status, text),
+        );
         return Ok(());
     }
 
-    emit_done(window, request_id);
-    Ok(())
+    stream_response(
+        window,
+        request_id,
+        resp,
+        stop,
+        "Azure OpenAI",
+        Framing::Sse,
+        parse_openai_event,
+    )
+    .await
 }
 
 async fn run_rest_google(
     window: &WebviewWindow,
     request_id: &str,
+    client: &reqwest::Client,
     api_key: &str,
-    model: &str,
-    prompt: &str,
+    input: &PromptInput<'_>,
+    stop: &watch::Receiver<Option<StopReason>>,
 ) -> Result<(), String> {
-    let client = reqwest::Client::new();
-    let body = serde_json::json!({
-        "contents": [{"parts": [{"text": prompt}]}]
-    });
+    let model = input.model;
+    let body = google_body(input);
 
     let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
+        "https://generativelanguage.googleapis.com/v1beta/models/{}:streamGenerateContent?alt=sse",
         model
     );
 
-    let resp = client
-        .post(&url)
-        .header("x-goog-api-key", api_key)
-        .header("content-type", "application/json")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("Google AI request failed: {}", e))?;
+    let resp = send_with_retry(window, request_id, "Google AI", || {
+        client
+            .post(&url)
+            .header("x-goog-api-key", api_key)
+            .header("content-type", "application/json")
+            .json(&body)
+    })
+    .await?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        emit_error(window, request_id, &format!("Google AI error {}: {}", status, text));
-        return Ok(());
-    }
-
-    let json: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    if let Some(text) = json
-        .get("candidates")
-        .and_then(|c| c.as_array())
-        .and_then(|candidates| candidates.first())
-        .and_then(|c| c.get("content"))
-        .and_then(|c| c.get("parts"))
-        .and_then(|p| p.as_array())
-        .and_then(|parts| parts.first())
-        .and_then(|p| p.get("text"))
-        .and_then(|t| t.as_str())
-    {
-        emit_chunk(window, request_id, text);
-    } else {
-        emit_error(window, request_id, "No candidates in Google AI response");
+        emit_error(
+            window,
+            request_id,
+            &format!("Google AI error {}: {}", status, text),
+        );
         return Ok(());
     }
 
-    emit_done(window, request_id);
-    Ok(())
+    stream_response(
+        window,
+        request_id,
+        resp,
+        stop,
+        "Google AI",
+        Framing::JsonObjects,
+        parse_google_event,
+    )
+    .await
 }
 
 async fn run_rest_ollama(
     window: &WebviewWindow,
     request_id: &str,
+    client: &reqwest::Client,
     endpoint: &str,
-    model: &str,
-    prompt: &str,
+    input: &PromptInput<'_>,
+    stream: bool,
+    stop: &watch::Receiver<Option<StopReason>>,
 ) -> Result<(), String> {
-    let client = reqwest::Client::new();
-    let body = serde_json::json!({
-        "model": model,
-        "prompt": prompt,
-        "stream": false
-    });
+    let (path, body) = ollama_request(input, stream);
 
-    let resp = client
-        .post(format!("{}/api/generate", endpoint))
-        .header("content-type", "application/json")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("Ollama request failed: {}", e))?;
+    let resp = send_with_retry(window, request_id, "Ollama", || {
+        client
+            .post(format!("{}{}", endpoint, path))
+            .header("content-type", "application/json")
+            .json(&body)
+    })
+    .await?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        emit_error(window, request_id, &format!("Ollama API error {}: {}", status, text));
+        emit_error(
+            window,
+            request_id,
+            &format!("Ollama API error {}: {}", status, text),
+        );
         return Ok(());
     }
 
-    let json: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    if stream {
+        return stream_response(
+            window,
+            request_id,
+            resp,
+            stop,
+            "Ollama",
+            Framing::Ndjson,
+            parse_ollama_event,
+        )
+        .await;
+    }
+
+    let Some(json) = read_json_reply(window, request_id, resp, stop).await? else {
+        return Ok(());
+    };
 
-    if let Some(text) = json.get("response").and_then(|r| r.as_str()) {
+    if let Some(text) = ollama_text(&json) {
         emit_chunk(window, request_id, text);
     } else {
         emit_error(window, request_id, "No response field in Ollama response");
@@ -895,25 +2644,51 @@ async fn run_rest_ollama(
 // Helpers
 // ============================================================================
 
-/// Validate that an API key is present and non-empty.
-///
-/// Returns `Some(key)` if valid, or emits an error event and returns `None`.
-fn require_api_key<'a>(
-    window: &WebviewWindow,
-    request_id: &str,
-    api_key: &'a Option<String>,
-    provider_name: &str,
-) -> Option<&'a str> {
-    match api_key.as_deref() {
-        Some(k) if !k.is_empty() => Some(k),
-        _ => {
-            emit_error(window, request_id, &format!("{} API key is required", provider_name));
-            None
+/// Check that `provider` is known and has the key, endpoint and model it
+/// needs, so a request is only reported as started once it can run.
+fn check_provider_settings(
+    provider: &str,
+    api_key: Option<&str>,
+    endpoint: Option<&str>,
+    model: &str,
+) -> Result<(), String> {
+    let has_key = api_key.is_some_and(|k| !k.is_empty());
+    let has_endpoint = endpoint.is_some_and(|e| !e.is_empty());
+    let require_key = |name: &str| {
+        if has_key {
+            Ok(())
+        } else {
+            Err(format!("{} API key is required", name))
+        }
+    };
+    match provider {
+        "claude" | "codex" | "gemini" | "ollama-api" | "echo" => Ok(()),
+        "anthropic" => require_key("Anthropic"),
+        "openai" => require_key("OpenAI"),
+        "google-ai" => require_key("Google AI"),
+        "openai-compatible" if !has_endpoint => {
+            Err("An endpoint is required for OpenAI-compatible servers".to_string())
+        }
+        "openai-compatible" if model.is_empty() => {
+            Err("A model is required for OpenAI-compatible servers".to_string())
         }
+        "openai-compatible" => Ok(()),
+        "azure-openai" => {
+            require_key("Azure OpenAI")?;
+            if !has_endpoint {
+                Err("Azure OpenAI endpoint is required".to_string())
+            } else if model.is_empty() {
+                Err("Azure OpenAI deployment name is required".to_string())
+            } else {
+                Ok(())
+            }
+        }
+        _ => Err(format!("Unknown provider: {}", provider)),
     }
 }
 
 fn emit_chunk(window: &WebviewWindow, request_id: &str, text: &str) {
+    ai_transcript::append(request_id, text);
     let _ = window.emit(
         "ai:response",
         AiResponseChunk {
@@ -921,11 +2696,13 @@ fn emit_chunk(window: &WebviewWindow, request_id: &str, text: &str) {
             chunk: text.to_string(),
             done: false,
             error: None,
+            status: None,
         },
     );
 }
 
 fn emit_done(window: &WebviewWindow, request_id: &str) {
+    ai_transcript::finish(request_id, ai_transcript::Outcome::Completed);
     let _ = window.emit(
         "ai:response",
         AiResponseChunk {
@@ -933,11 +2710,18 @@ fn emit_done(window: &WebviewWindow, request_id: &str) {
             chunk: String::new(),
             done: true,
             error: None,
+            status: None,
         },
     );
 }
 
 fn emit_error(window: &WebviewWindow, request_id: &str, msg: &str) {
+    ai_transcript::finish(request_id, ai_transcript::Outcome::Failed(msg.to_string()));
+    emit_error_chunk(window, request_id, msg);
+}
+
+/// Emit a final error chunk without touching the transcript.
+fn emit_error_chunk(window: &WebviewWindow, request_id: &str, msg: &str) {
     let _ = window.emit(
         "ai:response",
         AiResponseChunk {
@@ -945,6 +2729,816 @@ fn emit_error(window: &WebviewWindow, request_id: &str, msg: &str) {
             chunk: String::new(),
             done: true,
             error: Some(msg.to_string()),
+            status: None,
+        },
+    );
+}
+
+/// Emit the final chunk for a refused request: an error with status "refused",
+/// so the UI can tell it apart from a failure.
+fn emit_refused(window: &WebviewWindow, request_id: &str, reason: &str) {
+    let msg = format!("The model declined to answer ({})", reason);
+    ai_transcript::finish(request_id, ai_transcript::Outcome::Failed(msg.clone()));
+    let _ = window.emit(
+        "ai:response",
+        AiResponseChunk {
+            request_id: request_id.to_string(),
+            chunk: String::new(),
+            done: true,
+            error: Some(msg),
+            status: Some("refused".to_string()),
+        },
+    );
+}
+
+/// Emit the final chunk for a cancelled request (done, no error).
+fn emit_cancelled(window: &WebviewWindow, request_id: &str) {
+    ai_transcript::finish(request_id, ai_transcript::Outcome::Cancelled);
+    let _ = window.emit(
+        "ai:response",
+        AiResponseChunk {
+            request_id: request_id.to_string(),
+            chunk: String::new(),
+            done: true,
+            error: None,
+            status: Some("cancelled".to_string()),
+        },
+    );
+}
+
+/// Emit an informational status update (no text, not done).
+fn emit_status(window: &WebviewWindow, request_id: &str, status: &str) {
+    let _ = window.emit(
+        "ai:response",
+        AiResponseChunk {
+            request_id: request_id.to_string(),
+            chunk: String::new(),
+            done: false,
+            error: None,
+            status: Some(status.to_string()),
         },
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn ollama_health_check() {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 13\r\n\r\n{\"models\":[]}")
+                .await;
+        });
+        assert_eq!(check_ollama_server(Some(endpoint.clone())).await, Ok(true));
+
+        // Nothing listens there any more
+        assert_eq!(check_ollama_server(Some(endpoint)).await, Ok(false));
+        assert!(check_ollama_server(Some("not a url".to_string()))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn cancellable_body_stops_mid_stream() {
+        let (tx, rx) = watch::channel(None);
+        let chunks = futures_util::stream::iter([Ok(Bytes::from_static(b"data: 1\n"))]);
+        let mut body = CancellableBody {
+            stream: Box::pin(chunks.chain(futures_util::stream::pending())),
+            stop: rx,
+        };
+        assert!(matches!(body.next().await, BodyRead::Chunk(b) if b == "data: 1\n"));
+
+        // The server keeps the connection open; a stop still ends the read
+        tx.send_replace(Some(StopReason::Cancelled));
+        assert!(matches!(body.next().await, BodyRead::Stopped));
+    }
+
+    #[tokio::test]
+    async fn cancellable_body_collects_until_end() {
+        let (_tx, rx) = watch::channel(None);
+        let chunks = [
+            Ok(Bytes::from_static(b"{\"a\":")),
+            Ok(Bytes::from_static(b"1}")),
+        ];
+        let body = CancellableBody {
+            stream: Box::pin(futures_util::stream::iter(chunks)),
+            stop: rx,
+        };
+        assert_eq!(body.collect().await.unwrap(), Some(br#"{"a":1}"#.to_vec()));
+    }
+
+    #[test]
+    fn duplicate_request_id_is_rejected_while_in_flight() {
+        let (guard, _rx) = register_request("dup-test").unwrap();
+        assert!(register_request("dup-test").is_err());
+        assert!(register_request("dup-test-other").is_ok());
+
+        // Every exit path drops the guard, which frees the id
+        drop(guard);
+        assert!(register_request("dup-test").is_ok());
+    }
+
+    #[test]
+    fn echo_chunks_split_on_char_boundaries() {
+        assert_eq!(echo_chunks("hello world", 4), vec!["hell", "o wo", "rld"]);
+        assert_eq!(echo_chunks("héllo", 2), vec!["hé", "ll", "o"]);
+        assert!(echo_chunks("", 4).is_empty());
+    }
+
+    #[test]
+    fn merge_paths_keeps_inherited_entries() {
+        assert_eq!(
+            merge_paths("/opt/homebrew/bin:/usr/bin", "/usr/bin:/bin::/usr/bin"),
+            "/opt/homebrew/bin:/usr/bin:/bin"
+        );
+        assert_eq!(merge_paths("/usr/local/bin", ""), "/usr/local/bin");
+    }
+
+    #[test]
+    fn explicit_proxy_clients() {
+        assert!(client_for_proxy(None).is_ok());
+        assert!(client_for_proxy(Some("  ")).is_ok());
+        assert!(client_for_proxy(Some("http://proxy.example:3128")).is_ok());
+        assert!(client_for_proxy(Some("not a url")).is_err());
+    }
+
+    #[test]
+    fn key_validation_statuses() {
+        assert_eq!(key_accepted(200), Ok(true));
+        assert_eq!(key_accepted(401), Ok(false));
+        assert_eq!(key_accepted(403), Ok(false));
+        assert!(key_accepted(429).is_err());
+        assert!(key_accepted(500).is_err());
+    }
+
+    #[test]
+    fn model_ids_per_provider() {
+        let ollama =
+            serde_json::json!({"models": [{"name": "llama3.2:latest"}, {"name": "qwen2.5"}]});
+        assert_eq!(
+            parse_model_ids("ollama-api", &ollama),
+            vec!["llama3.2:latest", "qwen2.5"]
+        );
+
+        let google = serde_json::json!({"models": [{"name": "models/gemini-2.0-flash"}]});
+        assert_eq!(
+            parse_model_ids("google-ai", &google),
+            vec!["gemini-2.0-flash"]
+        );
+
+        let anthropic = serde_json::json!({"data": [{"id": "claude-opus-4-1", "type": "model"}]});
+        assert_eq!(
+            parse_model_ids("anthropic", &anthropic),
+            vec!["claude-opus-4-1"]
+        );
+
+        let openai = serde_json::json!({"object": "list", "data": [{"id": "gpt-4o"}]});
+        assert_eq!(parse_model_ids("openai", &openai), vec!["gpt-4o"]);
+        assert!(parse_model_ids("openai", &serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn custom_command_validation() {
+        assert_eq!(validate_custom_command("  claude-code "), Ok("claude-code"));
+        assert_eq!(
+            validate_custom_command("/opt/bin/my-ai"),
+            Ok("/opt/bin/my-ai")
+        );
+        assert!(validate_custom_command("   ").is_err());
+        assert!(validate_custom_command("--help").is_err());
+    }
+
+    #[test]
+    fn version_output_first_line() {
+        assert_eq!(
+            parse_version_output("\n1.0.51 (Claude Code)\nextra\n"),
+            Some("1.0.51 (Claude Code)".to_string())
+        );
+        assert_eq!(parse_version_output("  \n"), None);
+    }
+
+    #[test]
+    fn forward_output_keeps_output_verbatim() {
+        let forward = |input: &[u8]| {
+            let mut out = String::new();
+            forward_output(input, |text| out.push_str(text)).unwrap();
+            out
+        };
+        // No spurious trailing newline, and \r\n / bare \r progress are untouched
+        assert_eq!(forward(b"no newline at end"), "no newline at end");
+        assert_eq!(forward(b"a\r\nb\n"), "a\r\nb\n");
+        assert_eq!(forward(b"10%\r50%\r100%\n"), "10%\r50%\r100%\n");
+    }
+
+    #[test]
+    fn utf8_buffer_holds_split_sequences() {
+        let mut buf = Utf8Buffer::default();
+        let bytes = "aé".as_bytes();
+        assert_eq!(buf.push(&bytes[..2]), "a");
+        assert_eq!(buf.push(&bytes[2..]), "é");
+        assert_eq!(buf.push(&[0xe2, 0x82]), "");
+        assert_eq!(buf.finish(), "\u{fffd}");
+    }
+
+    #[test]
+    fn line_buffer_reassembles_split_lines() {
+        let mut buf = LineBuffer::default();
+        assert!(buf.push(b"data: {\"a\":").is_empty());
+        assert_eq!(buf.push(b"1}\r\n\ndata: x"), vec!["data: {\"a\":1}", ""]);
+        assert_eq!(buf.push(b"\n"), vec!["data: x"]);
+    }
+
+    #[test]
+    fn line_buffer_keeps_split_utf8_intact() {
+        let mut buf = LineBuffer::default();
+        let bytes = "é\n".as_bytes();
+        assert!(buf.push(&bytes[..1]).is_empty());
+        assert_eq!(buf.push(&bytes[1..]), vec!["é"]);
+    }
+
+    #[test]
+    fn json_object_buffer_handles_sse_and_array_framing() {
+        let mut buf = JsonObjectBuffer::default();
+        assert!(buf.push(b"data: {\"a\":{\"b\":").is_empty());
+        assert!(buf.has_partial());
+        assert_eq!(
+            buf.push(b"1}}\r\n\ndata: {\"c\":2}\r\n"),
+            vec![r#"{"a":{"b":1}}"#, r#"{"c":2}"#]
+        );
+        assert!(!buf.has_partial());
+
+        // Streamed array form, with braces and escaped quotes inside strings
+        let mut buf = JsonObjectBuffer::default();
+        assert_eq!(
+            buf.push(b"[{\"t\":\"}{\\\"\"}\n,\r\n{\"t\":2}]"),
+            vec![r#"{"t":"}{\""}"#, r#"{"t":2}"#]
+        );
+    }
+
+    #[test]
+    fn google_events() {
+        let chunk = r#"{"candidates":[{"content":{"parts":[{"text":"Hel"},{"text":"lo"}],"role":"model"}}]}"#;
+        assert_eq!(
+            parse_google_event(chunk),
+            StreamEvent::Text("Hello".to_string())
+        );
+        assert_eq!(
+            parse_google_event(r#"{"candidates":[{"finishReason":"STOP"}],"usageMetadata":{}}"#),
+            StreamEvent::Ignore
+        );
+        assert_eq!(
+            parse_google_event(r#"{"promptFeedback":{"blockReason":"SAFETY"}}"#),
+            StreamEvent::Refused("prompt blocked: SAFETY".to_string())
+        );
+        assert_eq!(
+            parse_google_event(
+                r#"{"candidates":[{"finishReason":"PROHIBITED_CONTENT","index":0}]}"#
+            ),
+            StreamEvent::Refused("content filter: PROHIBITED_CONTENT".to_string())
+        );
+        assert!(matches!(parse_google_event("{"), StreamEvent::Error(_)));
+    }
+
+    #[test]
+    fn sse_data_strips_prefix() {
+        assert_eq!(sse_data("data: {}"), Some("{}"));
+        assert_eq!(sse_data("data:{}"), Some("{}"));
+        assert_eq!(sse_data("event: ping"), None);
+    }
+
+    #[test]
+    fn anthropic_events() {
+        assert_eq!(
+            parse_anthropic_event(
+                r#"{"type":"content_block_delta","delta":{"type":"text_delta","text":"Hi"}}"#
+            ),
+            StreamEvent::Text("Hi".to_string())
+        );
+        assert_eq!(
+            parse_anthropic_event(r#"{"type":"message_stop"}"#),
+            StreamEvent::Done
+        );
+        assert_eq!(
+            parse_anthropic_event(r#"{"type":"ping"}"#),
+            StreamEvent::Ignore
+        );
+        assert_eq!(
+            parse_anthropic_event(
+                r#"{"type":"message_delta","delta":{"stop_reason":"refusal","stop_sequence":null},"usage":{"output_tokens":0}}"#
+            ),
+            StreamEvent::Refused("refusal".to_string())
+        );
+        assert_eq!(
+            parse_anthropic_event(r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"}}"#),
+            StreamEvent::Ignore
+        );
+        assert!(matches!(
+            parse_anthropic_event(r#"{"type":"error","error":{"message":"overloaded"}}"#),
+            StreamEvent::Error(msg) if msg.contains("overloaded")
+        ));
+        assert!(matches!(
+            parse_anthropic_event("{not json"),
+            StreamEvent::Error(_)
+        ));
+    }
+
+    #[test]
+    fn openai_events() {
+        assert_eq!(
+            parse_openai_event(r#"{"choices":[{"delta":{"content":"Hel"}}]}"#),
+            StreamEvent::Text("Hel".to_string())
+        );
+        assert_eq!(
+            parse_openai_event(r#"{"choices":[{"delta":{"role":"assistant"}}]}"#),
+            StreamEvent::Ignore
+        );
+        assert_eq!(parse_openai_event("[DONE]"), StreamEvent::Done);
+        assert_eq!(
+            parse_openai_event(
+                r#"{"choices":[{"index":0,"delta":{},"finish_reason":"content_filter"}]}"#
+            ),
+            StreamEvent::Refused("content filter".to_string())
+        );
+        assert_eq!(
+            parse_openai_event(r#"{"choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#),
+            StreamEvent::Ignore
+        );
+        assert!(matches!(
+            parse_openai_event(r#"{"error":{"message":"rate limited"}}"#),
+            StreamEvent::Error(msg) if msg.contains("rate limited")
+        ));
+        assert!(matches!(
+            parse_openai_event("{\"choices\": ["),
+            StreamEvent::Error(_)
+        ));
+    }
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage {
+            role: "user".to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    const NO_PARAMS: GenerationParams = GenerationParams {
+        temperature: None,
+        max_tokens: None,
+        top_p: None,
+        stop: None,
+    };
+
+    fn input<'a>(
+        model: &'a str,
+        messages: &'a [ChatMessage],
+        system: Option<&'a str>,
+        params: &'a GenerationParams,
+    ) -> PromptInput<'a> {
+        PromptInput {
+            model,
+            messages,
+            system,
+            params,
+            images: &[],
+        }
+    }
+
+    fn history() -> Vec<ChatMessage> {
+        vec![
+            user("Hi"),
+            ChatMessage {
+                role: "assistant".to_string(),
+                content: "Hello".to_string(),
+            },
+            user("Again"),
+        ]
+    }
+
+    #[test]
+    fn conversation_from_prompt_or_messages() {
+        assert_eq!(
+            conversation(Some("Hi".into()), None).unwrap(),
+            vec![user("Hi")]
+        );
+        assert_eq!(
+            conversation(Some("ignored".into()), Some(history()))
+                .unwrap()
+                .len(),
+            3
+        );
+        assert!(conversation(None, None).is_err());
+        assert!(conversation(None, Some(vec![])).is_err());
+
+        let mut bad_role = history();
+        bad_role[0].role = "tool".to_string();
+        assert!(conversation(None, Some(bad_role)).is_err());
+        assert!(conversation(None, Some(history()[..2].to_vec())).is_err());
+    }
+
+    #[test]
+    fn flatten_labels_turns() {
+        assert_eq!(flatten_conversation(&[user("Hi")]), "Hi");
+        assert_eq!(
+            flatten_conversation(&history()),
+            "User: Hi\n\nAssistant: Hello\n\nUser: Again"
+        );
+    }
+
+    #[test]
+    fn anthropic_body_uses_top_level_system() {
+        let body = anthropic_body(&input(
+            "claude",
+            &[user("Hi")],
+            Some("Be terse"),
+            &NO_PARAMS,
+        ));
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "model": "claude",
+                "max_tokens": 4096,
+                "stream": true,
+                "system": "Be terse",
+                "messages": [{"role": "user", "content": "Hi"}]
+            })
+        );
+        assert!(
+            anthropic_body(&input("claude", &[user("Hi")], None, &NO_PARAMS))
+                .get("system")
+                .is_none()
+        );
+        assert_eq!(
+            anthropic_body(&input("claude", &history(), None, &NO_PARAMS))["messages"][1]["role"],
+            "assistant"
+        );
+    }
+
+    #[test]
+    fn openai_body_prepends_system_message() {
+        let body = openai_body(&input(
+            "gpt-4o",
+            &[user("Hi")],
+            Some("Be terse"),
+            &NO_PARAMS,
+        ));
+        assert_eq!(
+            body["messages"],
+            serde_json::json!([
+                {"role": "system", "content": "Be terse"},
+                {"role": "user", "content": "Hi"}
+            ])
+        );
+        assert_eq!(
+            openai_body(&input("gpt-4o", &history(), None, &NO_PARAMS))["messages"]
+                .as_array()
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn google_body_renames_assistant_and_uses_system_instruction() {
+        let body = google_body(&input(
+            "gemini",
+            &history()[..2],
+            Some("Be terse"),
+            &NO_PARAMS,
+        ));
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "contents": [
+                    {"role": "user", "parts": [{"text": "Hi"}]},
+                    {"role": "model", "parts": [{"text": "Hello"}]}
+                ],
+                "systemInstruction": {"parts": [{"text": "Be terse"}]}
+            })
+        );
+    }
+
+    #[test]
+    fn ollama_generate_for_prompt_and_chat_for_history() {
+        let (path, body) = ollama_request(
+            &input("llama3.2", &[user("Hi")], Some("Be terse"), &NO_PARAMS),
+            true,
+        );
+        assert_eq!(path, "/api/generate");
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "model": "llama3.2",
+                "prompt": "Hi",
+                "system": "Be terse",
+                "stream": true
+            })
+        );
+
+        let (path, body) = ollama_request(
+            &input("llama3.2", &history(), Some("Be terse"), &NO_PARAMS),
+            false,
+        );
+        assert_eq!(path, "/api/chat");
+        assert_eq!(
+            body["messages"][0],
+            serde_json::json!({"role": "system", "content": "Be terse"})
+        );
+        assert_eq!(body["messages"].as_array().unwrap().len(), 4);
+        assert_eq!(body["stream"], false);
+    }
+
+    #[test]
+    fn generation_params_only_when_set() {
+        let params = GenerationParams {
+            temperature: Some(0.5),
+            max_tokens: Some(256),
+            top_p: None,
+            stop: Some(Vec::new()),
+        };
+        let body = anthropic_body(&input("claude", &[user("Hi")], None, &params));
+        assert_eq!(
+            (body["temperature"].as_f64(), body["max_tokens"].as_u64()),
+            (Some(0.5), Some(256))
+        );
+        assert!(body.get("top_p").is_none());
+
+        let body = openai_body(&input("gpt-4o", &[user("Hi")], None, &params));
+        assert_eq!(
+            (body["temperature"].as_f64(), body["max_tokens"].as_u64()),
+            (Some(0.5), Some(256))
+        );
+
+        let body = google_body(&input("gemini", &[user("Hi")], None, &params));
+        assert_eq!(
+            body["generationConfig"],
+            serde_json::json!({"temperature": 0.5, "maxOutputTokens": 256})
+        );
+        assert!(
+            google_body(&input("gemini", &[user("Hi")], None, &NO_PARAMS))
+                .get("generationConfig")
+                .is_none()
+        );
+
+        let (_, body) = ollama_request(&input("llama3.2", &[user("Hi")], None, &params), true);
+        assert_eq!(
+            body["options"],
+            serde_json::json!({"temperature": 0.5, "num_predict": 256})
+        );
+    }
+
+    #[test]
+    fn images_attach_to_last_user_message() {
+        let images = [ImageAttachment {
+            data: "iVBORw0KGgo=".to_string(),
+            mime_type: "image/png".to_string(),
+        }];
+        let messages = history();
+        let with_images = PromptInput {
+            images: &images,
+            ..input("m", &messages, Some("Be terse"), &NO_PARAMS)
+        };
+
+        let body = anthropic_body(&with_images);
+        let last = &body["messages"][2]["content"];
+        assert_eq!(
+            last[0]["source"],
+            serde_json::json!({"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="})
+        );
+        assert_eq!(
+            last[1],
+            serde_json::json!({"type": "text", "text": messages[2].content})
+        );
+        assert_eq!(body["messages"][0]["content"], messages[0].content.as_str());
+
+        let body = openai_body(&with_images);
+        let last = &body["messages"][3]["content"];
+        assert_eq!(last[0]["type"], "text");
+        assert_eq!(
+            last[1]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+    }
+
+    #[test]
+    fn image_validation() {
+        let image = |mime: &str, len| ImageAttachment {
+            data: "A".repeat(len),
+            mime_type: mime.to_string(),
+        };
+        assert!(validate_images("ollama-api", &[]).is_ok());
+        assert!(validate_images("openai", &[image("image/jpeg", 8)]).is_ok());
+        assert!(validate_images("google-ai", &[image("image/png", 8)]).is_err());
+        assert!(validate_images("claude", &[image("image/png", 8)]).is_err());
+        assert!(validate_images("anthropic", &[image("image/bmp", 8)]).is_err());
+        assert!(validate_images("anthropic", &[image("image/png", 0)]).is_err());
+        assert!(validate_images(
+            "anthropic",
+            &[image("image/png", MAX_IMAGE_BYTES / 3 * 4 + 8)]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn stop_sequences_per_provider() {
+        let params = GenerationParams {
+            stop: Some(vec!["###".to_string()]),
+            ..Default::default()
+        };
+        let stop = serde_json::json!(["###"]);
+        assert_eq!(
+            anthropic_body(&input("claude", &[user("Hi")], None, &params))["stop_sequences"],
+            stop
+        );
+        assert_eq!(
+            openai_body(&input("gpt-4o", &[user("Hi")], None, &params))["stop"],
+            stop
+        );
+        assert_eq!(
+            google_body(&input("gemini", &[user("Hi")], None, &params))["generationConfig"]
+                ["stopSequences"],
+            stop
+        );
+        let (_, body) = ollama_request(&input("llama3.2", &[user("Hi")], None, &params), true);
+        assert_eq!(body["options"]["stop"], stop);
+
+        let too_many = |n| GenerationParams {
+            stop: Some(vec!["x".to_string(); n]),
+            ..Default::default()
+        };
+        assert!(too_many(4).validate("openai").is_ok());
+        assert!(too_many(5).validate("openai").is_err());
+        assert!(too_many(5).validate("google-ai").is_ok());
+        assert!(too_many(6).validate("google-ai").is_err());
+        assert!(too_many(10).validate("ollama-api").is_ok());
+    }
+
+    #[test]
+    fn generation_params_validation() {
+        let temperature = |t| GenerationParams {
+            temperature: Some(t),
+            ..Default::default()
+        };
+        assert!(temperature(1.5).validate("openai").is_ok());
+        assert!(temperature(1.5).validate("anthropic").is_err());
+        assert!(temperature(-0.1).validate("ollama-api").is_err());
+        assert!(temperature(f32::NAN).validate("openai").is_err());
+
+        let top_p = GenerationParams {
+            top_p: Some(1.2),
+            ..Default::default()
+        };
+        assert!(top_p.validate("openai").is_err());
+        let max_tokens = GenerationParams {
+            max_tokens: Some(0),
+            ..Default::default()
+        };
+        assert!(max_tokens.validate("openai").is_err());
+        assert!(GenerationParams::default().validate("anthropic").is_ok());
+    }
+
+    #[test]
+    fn openai_url_with_optional_path() {
+        assert_eq!(
+            openai_chat_url("https://api.openai.com", None),
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(
+            openai_chat_url("http://localhost:1234/", None),
+            "http://localhost:1234/v1/chat/completions"
+        );
+        assert_eq!(
+            openai_chat_url("https://openrouter.ai/api", Some("v1/chat/completions")),
+            "https://openrouter.ai/api/v1/chat/completions"
+        );
+        assert_eq!(
+            openai_chat_url("http://host", Some("/chat")),
+            "http://host/chat"
+        );
+    }
+
+    #[test]
+    fn azure_url_shape() {
+        assert_eq!(
+            azure_chat_url("https://res.openai.azure.com/", "gpt4o-prod", "2024-10-21"),
+            "https://res.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-10-21"
+        );
+    }
+
+    #[test]
+    fn provider_settings_checked_before_start() {
+        assert!(check_provider_settings("claude", None, None, "").is_ok());
+        assert!(check_provider_settings("anthropic", Some("k"), None, "").is_ok());
+        assert_eq!(
+            check_provider_settings("anthropic", Some(""), None, "").unwrap_err(),
+            "Anthropic API key is required"
+        );
+        assert!(check_provider_settings("openai-compatible", None, Some("http://h"), "m").is_ok());
+        assert!(check_provider_settings("openai-compatible", None, Some("http://h"), "").is_err());
+        assert!(check_provider_settings("openai-compatible", None, None, "m").is_err());
+        assert!(check_provider_settings("azure-openai", Some("k"), Some(""), "d").is_err());
+        assert!(check_provider_settings("azure-openai", Some("k"), Some("https://r"), "d").is_ok());
+        assert_eq!(
+            check_provider_settings("nope", None, None, "").unwrap_err(),
+            "Unknown provider: nope"
+        );
+    }
+
+    #[test]
+    fn first_stop_reason_wins() {
+        let (guard, rx) = register_request("test-stop-reason").unwrap();
+        assert!(stop_request("test-stop-reason", StopReason::TimedOut(5)));
+        assert!(stop_request("test-stop-reason", StopReason::Cancelled));
+        assert_eq!(*rx.borrow(), Some(StopReason::TimedOut(5)));
+
+        drop(guard);
+        assert!(!stop_request("test-stop-reason", StopReason::Cancelled));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancel_kills_cli_child_being_waited_on() {
+        let (_guard, _rx) = register_request("test-cancel-wait").unwrap();
+        let child = Arc::new(Mutex::new(Command::new("sleep").arg("30").spawn().unwrap()));
+        CLI_CHILDREN
+            .lock()
+            .unwrap()
+            .insert("test-cancel-wait".to_string(), child.clone());
+
+        let started = std::time::Instant::now();
+        let waiter = std::thread::spawn(move || wait_cli_child(&child));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(cancel_ai_prompt("test-cancel-wait".to_string()));
+
+        let status = waiter.join().unwrap().unwrap();
+        assert!(!status.success());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn retryable_statuses() {
+        for status in [408, 429, 500, 502, 503, 504] {
+            assert!(is_retryable_status(status), "{}", status);
+        }
+        for status in [200, 400, 401, 403, 404] {
+            assert!(!is_retryable_status(status), "{}", status);
+        }
+    }
+
+    #[test]
+    fn retry_after_seconds_and_date() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_retry_after("7", now), Some(Duration::from_secs(7)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:05 GMT", now),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn backoff_doubles_and_honors_retry_after() {
+        assert_eq!(backoff_delay(0, None, 0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(1, None, 0), Duration::from_millis(1000));
+        assert_eq!(backoff_delay(2, None, 100), Duration::from_millis(2100));
+        assert_eq!(
+            backoff_delay(0, Some(Duration::from_secs(3)), 100),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            backoff_delay(0, Some(Duration::from_secs(600)), 0),
+            Duration::from_secs(MAX_RETRY_AFTER_SECS)
+        );
+    }
+
+    #[test]
+    fn ollama_events() {
+        assert_eq!(
+            parse_ollama_event(r#"{"model":"llama3.2","response":"Hi","done":false}"#),
+            StreamEvent::Text("Hi".to_string())
+        );
+        assert_eq!(
+            parse_ollama_event(r#"{"message":{"role":"assistant","content":"Yo"},"done":false}"#),
+            StreamEvent::Text("Yo".to_string())
+        );
+        assert_eq!(
+            parse_ollama_event(r#"{"response":"","done":true}"#),
+            StreamEvent::Done
+        );
+        assert!(matches!(
+            parse_ollama_event(r#"{"error":"model not found"}"#),
+            StreamEvent::Error(msg) if msg.contains("model not found")
+        ));
+    }
+}
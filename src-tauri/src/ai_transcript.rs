@@ -0,0 +1,216 @@
+//! AI Transcript - Opt-in logging of AI exchanges to daily Markdown files.
+//!
+//! When enabled, each completed prompt (including cancelled and failed ones,
+//! with a marker) is appended to `vmark-ai-YYYY-MM-DD.md` in the chosen
+//! directory. Streaming responses are accumulated in memory and written once.
+//! API keys are never passed to this module.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+use chrono::{DateTime, Local};
+
+/// Transcript directory when logging is enabled
+static TRANSCRIPT_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Exchanges in progress, keyed by request id
+static PENDING: LazyLock<Mutex<HashMap<String, Exchange>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Serializes appends from concurrent prompts
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone)]
+struct Exchange {
+    started_at: DateTime<Local>,
+    provider: String,
+    model: String,
+    prompt: String,
+    response: String,
+}
+
+/// How an exchange ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Completed,
+    Cancelled,
+    Failed(String),
+}
+
+fn transcript_dir() -> Option<PathBuf> {
+    TRANSCRIPT_DIR
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .clone()
+}
+
+fn transcript_file(dir: &Path, date: &DateTime<Local>) -> PathBuf {
+    dir.join(format!("vmark-ai-{}.md", date.format("%Y-%m-%d")))
+}
+
+fn format_entry(exchange: &Exchange, outcome: &Outcome) -> String {
+    let mut entry = format!(
+        "## {} — {} ({})\n\n### Prompt\n\n{}\n\n### Response\n\n{}\n",
+        exchange.started_at.format("%Y-%m-%d %H:%M:%S"),
+        exchange.provider,
+        exchange.model,
+        exchange.prompt.trim_end(),
+        exchange.response.trim_end(),
+    );
+    match outcome {
+        Outcome::Completed => {}
+        Outcome::Cancelled => entry.push_str("\n*[cancelled — partial response]*\n"),
+        Outcome::Failed(error) => entry.push_str(&format!("\n*[error: {}]*\n", error)),
+    }
+    entry.push_str("\n---\n\n");
+    entry
+}
+
+/// Append an entry with a single write so concurrent writers don't interleave.
+fn append_entry(path: &Path, entry: &str) -> Result<(), String> {
+    let _lock = WRITE_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open transcript {:?}: {}", path, e))?;
+    file.write_all(entry.as_bytes())
+        .map_err(|e| format!("Failed to write transcript: {}", e))?;
+    file.sync_data()
+        .map_err(|e| format!("Failed to sync transcript: {}", e))
+}
+
+/// Start recording an exchange (no-op when transcripts are disabled).
+/// `model` is the model the request actually uses; empty means the
+/// provider's own default (CLI providers).
+pub fn begin(request_id: &str, provider: &str, model: &str, prompt: &str) {
+    if transcript_dir().is_none() {
+        return;
+    }
+    PENDING.lock().unwrap_or_else(|p| p.into_inner()).insert(
+        request_id.to_string(),
+        Exchange {
+            started_at: Local::now(),
+            provider: provider.to_string(),
+            model: if model.is_empty() { "default" } else { model }.to_string(),
+            prompt: prompt.to_string(),
+            response: String::new(),
+        },
+    );
+}
+
+/// Accumulate streamed response text.
+pub fn append(request_id: &str, text: &str) {
+    if let Some(exchange) = PENDING
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .get_mut(request_id)
+    {
+        exchange.response.push_str(text);
+    }
+}
+
+/// Finish an exchange and write it to today's transcript file.
+pub fn finish(request_id: &str, outcome: Outcome) {
+    let Some(exchange) = PENDING
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .remove(request_id)
+    else {
+        return;
+    };
+    let Some(dir) = transcript_dir() else {
+        return;
+    };
+    let entry = format_entry(&exchange, &outcome);
+    if let Err(_e) = append_entry(&transcript_file(&dir, &Local::now()), &entry) {
+        #[cfg(debug_assertions)]
+        eprintln!("[AI Transcript] {}", _e);
+    }
+}
+
+/// Drop an exchange that ended without done/error (nothing is written).
+pub fn discard(request_id: &str) {
+    PENDING
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .remove(request_id);
+}
+
+/// Enable or disable transcript logging.
+#[tauri::command]
+pub fn set_ai_transcript(enabled: bool, dir: Option<String>) -> Result<(), String> {
+    let mut guard = TRANSCRIPT_DIR.lock().unwrap_or_else(|p| p.into_inner());
+    if !enabled {
+        *guard = None;
+        return Ok(());
+    }
+    let dir = dir
+        .filter(|d| !d.is_empty())
+        .ok_or("A transcript directory is required")?;
+    let dir = PathBuf::from(dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create transcript dir: {}", e))?;
+    *guard = Some(dir);
+    Ok(())
+}
+
+/// Path of today's transcript file, or None when logging is disabled.
+#[tauri::command]
+pub fn get_ai_transcript_path() -> Option<String> {
+    transcript_dir().map(|dir| {
+        transcript_file(&dir, &Local::now())
+            .to_string_lossy()
+            .to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    fn exchange() -> Exchange {
+        Exchange {
+            started_at: Local::now(),
+            provider: "anthropic".to_string(),
+            model: "claude".to_string(),
+            prompt: "Hello".to_string(),
+            response: "Hi there\n".to_string(),
+        }
+    }
+
+    #[test]
+    fn entry_contains_prompt_and_response() {
+        let entry = format_entry(&exchange(), &Outcome::Completed);
+        assert!(entry.contains("anthropic (claude)"));
+        assert!(entry.contains("### Prompt\n\nHello\n"));
+        assert!(entry.contains("### Response\n\nHi there\n"));
+        assert!(!entry.contains("cancelled"));
+    }
+
+    #[test]
+    fn entry_marks_cancelled_and_failed() {
+        assert!(format_entry(&exchange(), &Outcome::Cancelled).contains("[cancelled"));
+        assert!(format_entry(&exchange(), &Outcome::Failed("boom".into())).contains("[error: boom]"));
+    }
+
+    #[test]
+    fn append_entry_accumulates() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("t.md");
+        append_entry(&path, "one\n").unwrap();
+        append_entry(&path, "two\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+    }
+
+    #[test]
+    fn transcript_file_is_daily() {
+        let date = Local.with_ymd_and_hms(2026, 3, 4, 10, 0, 0).unwrap();
+        let name = transcript_file(Path::new("/tmp"), &date);
+        assert_eq!(name, Path::new("/tmp/vmark-ai-2026-03-04.md"));
+    }
+}
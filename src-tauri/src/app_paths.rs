@@ -2,13 +2,19 @@
 //!
 //! Provides:
 //! - Bootstrap file writing for MCP sidecar discovery
-//! - Migration from legacy ~/.vmark/ to standard app data directory
+//! - Migration from legacy ~/.vmark/ to standard app data directory, reported
+//!   to the frontend as `migration:progress` events
 //! - Atomic file operations to prevent race conditions
+//! - Startup lock file so concurrent instances don't race migrations
+//! - `VMARK_DATA_DIR` override of the app data directory (portable installs, tests)
 
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
 use std::fs::{self, File, OpenOptions};
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 
 // ============================================================================
 // Constants
@@ -17,19 +23,86 @@ use tauri::Manager;
 /// Bootstrap file name - contains path to app data directory
 const BOOTSTRAP_FILE: &str = "app-data-path";
 
+/// Contents of the bootstrap file. `sha256` is the hex digest of `path`, so a
+/// corrupted or hand-edited file is detected.
+#[derive(Debug, Serialize, Deserialize)]
+struct BootstrapFile {
+    path: String,
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+/// App data path read back from a bootstrap file.
+#[derive(Debug, PartialEq)]
+struct BootstrapPath {
+    path: PathBuf,
+    /// False for files without a checksum (plain-path files from older versions)
+    verified: bool,
+}
+
+/// Environment variable that overrides the app data directory
+pub const DATA_DIR_ENV: &str = "VMARK_DATA_DIR";
+
+/// Startup lock file name - contains the PID of the holder
+const LOCK_FILE: &str = "vmark.lock";
+
+/// A lock file without a readable PID is treated as stale after this long
+/// (the holder crashed between creating and writing it).
+const UNREADABLE_LOCK_GRACE: std::time::Duration = std::time::Duration::from_secs(10);
+
 /// Migration marker file name
 const MIGRATION_MARKER: &str = ".migrated-from-legacy";
 
+/// Workspaces directory kept in the legacy ~/.vmark/ by early versions
+const LEGACY_WORKSPACES_DIR: &str = "workspaces";
+
 /// MCP settings file name
 pub const MCP_SETTINGS_FILE: &str = "mcp-settings.json";
 
 /// MCP port file name
 pub const MCP_PORT_FILE: &str = "mcp-port";
 
+/// MCP bridge auth token file name
+pub const MCP_TOKEN_FILE: &str = "mcp-token";
+
+/// Self-signed TLS certificate for the MCP bridge
+pub const MCP_TLS_CERT_FILE: &str = "mcp-tls-cert.pem";
+
+/// Private key for the self-signed MCP bridge certificate
+pub const MCP_TLS_KEY_FILE: &str = "mcp-tls-key.pem";
+
+/// Certificate the MCP bridge currently serves, for the sidecar to trust
+pub const MCP_TLS_CA_FILE: &str = "mcp-tls-ca.pem";
+
+/// Event emitted at each stage of a legacy migration
+pub const MIGRATION_PROGRESS_EVENT: &str = "migration:progress";
+
+/// Payload for `migration:progress`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationProgress {
+    /// 1-based stage number
+    pub step: u32,
+    pub total: u32,
+    pub message: String,
+}
+
+/// Progress callback for the Tauri-free migration functions.
+pub type ProgressFn<'a> = Option<&'a dyn Fn(MigrationProgress)>;
+
 // ============================================================================
 // Public API (Tauri-dependent)
 // ============================================================================
 
+/// Resolve the app data directory: `VMARK_DATA_DIR` when set, otherwise
+/// Tauri's platform default. All app data paths should go through this.
+pub fn resolve_app_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    match data_dir_override(std::env::var_os(DATA_DIR_ENV)) {
+        Some(dir) => Ok(dir),
+        None => app.path().app_data_dir().map_err(|e| e.to_string()),
+    }
+}
+
 /// Get the legacy directory path (~/.vmark/).
 /// This is being phased out - only used for bootstrap file and migration.
 pub fn get_legacy_dir() -> Option<PathBuf> {
@@ -41,12 +114,15 @@ pub fn get_legacy_dir() -> Option<PathBuf> {
 /// The MCP sidecar is a separate Node.js process without access to Tauri's AppHandle.
 /// Instead of duplicating platform-specific path logic in Node.js, we write a bootstrap file:
 /// - File: ~/.vmark/app-data-path
-/// - Contents: Absolute path to app data directory
+/// - Contents: `{ "path": "<app data dir>", "sha256": "<hex digest of path>" }`
 /// - Read by: Node.js sidecar to locate mcp-port and mcp-settings.json
 ///
+/// The bootstrap file stays in ~/.vmark even when `VMARK_DATA_DIR` is set,
+/// so the sidecar can still discover the overridden directory.
+///
 /// Uses atomic write (temp file + rename) to prevent partial reads.
 pub fn write_app_data_path_bootstrap(app: &tauri::AppHandle) -> Result<(), String> {
-    let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let app_data = resolve_app_data_dir(app)?;
     let legacy_dir = get_legacy_dir().ok_or("Cannot determine home directory")?;
 
     write_bootstrap_file_impl(&legacy_dir, &app_data)
@@ -55,25 +131,73 @@ pub fn write_app_data_path_bootstrap(app: &tauri::AppHandle) -> Result<(), Strin
 /// Migrate legacy files from ~/.vmark/ to the app data directory.
 ///
 /// This is a one-time migration that runs on startup:
-/// - Copies mcp-settings.json if it exists in legacy location but not in app data
+/// - Copies mcp-settings.json, mcp-port and workspaces/ where they exist in the legacy
+///   location but not in app data
 /// - Creates a marker file atomically to prevent re-running (even with concurrent instances)
 /// - Only writes marker on successful migration or when nothing to migrate
 pub fn migrate_legacy_files(app: &tauri::AppHandle) -> Result<(), String> {
     let legacy_dir = get_legacy_dir().ok_or("Cannot determine home directory")?;
-    let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let app_data = resolve_app_data_dir(app)?;
 
-    migrate_legacy_files_impl(&legacy_dir, &app_data)
+    migrate_legacy_files_impl(
+        &legacy_dir,
+        &app_data,
+        Some(&|p| emit_migration_progress(app, p)),
+    )
+}
+
+/// Forward a migration stage to the frontend as `migration:progress`.
+pub fn emit_migration_progress(app: &tauri::AppHandle, progress: MigrationProgress) {
+    #[cfg(debug_assertions)]
+    eprintln!(
+        "[App Paths] Migration {}/{}: {}",
+        progress.step, progress.total, progress.message
+    );
+    let _ = app.emit(MIGRATION_PROGRESS_EVENT, progress);
+}
+
+/// Take the startup lock in the app data directory.
+///
+/// Fails if another live instance holds it; a lock left behind by a dead
+/// process is reclaimed. The lock is released when the guard is dropped.
+pub fn acquire_app_lock(app: &tauri::AppHandle) -> Result<LockGuard, String> {
+    acquire_lock_impl(&resolve_app_data_dir(app)?, is_process_alive)
 }
 
 /// Get the path to the port file in the app data directory.
 pub fn get_port_file_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let app_data = resolve_app_data_dir(app)?;
     Ok(app_data.join(MCP_PORT_FILE))
 }
 
+/// Get the path to the bridge auth token file in the app data directory.
+pub fn get_token_file_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = resolve_app_data_dir(app)?;
+    Ok(app_data.join(MCP_TOKEN_FILE))
+}
+
+/// Get the path to the bridge's self-signed TLS certificate.
+pub fn get_tls_cert_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = resolve_app_data_dir(app)?;
+    Ok(app_data.join(MCP_TLS_CERT_FILE))
+}
+
+/// Get the path to the bridge's self-signed TLS private key.
+pub fn get_tls_key_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = resolve_app_data_dir(app)?;
+    Ok(app_data.join(MCP_TLS_KEY_FILE))
+}
+
+/// Get the path to the certificate published for the sidecar while the
+/// bridge serves `wss://`.
+pub fn get_tls_ca_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = resolve_app_data_dir(app)?;
+    Ok(app_data.join(MCP_TLS_CA_FILE))
+}
+
 /// Get the path to the MCP settings file in the app data directory.
 pub fn get_mcp_settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let app_data = resolve_app_data_dir(app)?;
     Ok(app_data.join(MCP_SETTINGS_FILE))
 }
 
@@ -81,33 +205,99 @@ pub fn get_mcp_settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String>
 // Core Implementation (Tauri-independent, testable)
 // ============================================================================
 
+/// Interpret the `VMARK_DATA_DIR` value. Empty means unset; relative paths
+/// are resolved against the current directory so the bootstrap file is absolute.
+fn data_dir_override(value: Option<OsString>) -> Option<PathBuf> {
+    let dir = PathBuf::from(value.filter(|v| !v.is_empty())?);
+    if dir.is_absolute() {
+        return Some(dir);
+    }
+    Some(
+        std::env::current_dir()
+            .map(|cwd| cwd.join(&dir))
+            .unwrap_or(dir),
+    )
+}
+
+/// Hex-encoded SHA-256 of a bootstrap path string.
+fn bootstrap_checksum(path: &str) -> String {
+    ring::digest::digest(&ring::digest::SHA256, path.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Parse bootstrap file contents: the JSON form (checksum verified) or a
+/// plain path written by older versions (accepted, but unverified).
+fn parse_bootstrap_contents(contents: &str) -> Result<BootstrapPath, String> {
+    let contents = contents.trim();
+    if !contents.starts_with('{') {
+        if contents.is_empty() {
+            return Err("Bootstrap file is empty".to_string());
+        }
+        return Ok(BootstrapPath {
+            path: PathBuf::from(contents),
+            verified: false,
+        });
+    }
+
+    let file: BootstrapFile = serde_json::from_str(contents)
+        .map_err(|e| format!("Failed to parse bootstrap file: {}", e))?;
+    let verified = match &file.sha256 {
+        Some(hash) if hash.eq_ignore_ascii_case(&bootstrap_checksum(&file.path)) => true,
+        Some(_) => return Err("Bootstrap file checksum mismatch".to_string()),
+        None => false,
+    };
+    Ok(BootstrapPath {
+        path: PathBuf::from(file.path),
+        verified,
+    })
+}
+
+/// Read the bootstrap file from `legacy_dir`. Returns `Ok(None)` if it doesn't
+/// exist and an error if it is unreadable or fails its checksum.
+fn read_bootstrap_file_impl(legacy_dir: &Path) -> Result<Option<BootstrapPath>, String> {
+    let bootstrap_path = legacy_dir.join(BOOTSTRAP_FILE);
+    match fs::read_to_string(&bootstrap_path) {
+        Ok(contents) => parse_bootstrap_contents(&contents).map(Some),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {:?}: {}", bootstrap_path, e)),
+    }
+}
+
 /// Write bootstrap file atomically.
 /// This is the core implementation that can be tested without Tauri.
 fn write_bootstrap_file_impl(legacy_dir: &Path, app_data: &Path) -> Result<(), String> {
     // Ensure directories exist
-    fs::create_dir_all(app_data).map_err(|e| {
-        format!(
-            "Failed to create app data directory {:?}: {}",
-            app_data, e
-        )
-    })?;
+    fs::create_dir_all(app_data)
+        .map_err(|e| format!("Failed to create app data directory {:?}: {}", app_data, e))?;
 
-    fs::create_dir_all(legacy_dir).map_err(|e| {
+    fs::create_dir_all(legacy_dir)
+        .map_err(|e| format!("Failed to create legacy directory {:?}: {}", legacy_dir, e))?;
+
+    // Convert app_data path to UTF-8 string, failing explicitly on non-UTF-8 paths
+    let app_data_str = app_data.to_str().ok_or_else(|| {
         format!(
-            "Failed to create legacy directory {:?}: {}",
-            legacy_dir, e
+            "App data path contains non-UTF-8 characters: {:?}",
+            app_data
         )
     })?;
 
-    // Convert app_data path to UTF-8 string, failing explicitly on non-UTF-8 paths
-    let app_data_str = app_data
-        .to_str()
-        .ok_or_else(|| format!("App data path contains non-UTF-8 characters: {:?}", app_data))?;
+    // Leave an intact file pointing at the same directory alone
+    let current = read_bootstrap_file_impl(legacy_dir).ok().flatten();
+    if current.is_some_and(|c| c.verified && c.path == app_data) {
+        return Ok(());
+    }
 
     let bootstrap_path = legacy_dir.join(BOOTSTRAP_FILE);
+    let file = BootstrapFile {
+        path: app_data_str.to_string(),
+        sha256: Some(bootstrap_checksum(app_data_str)),
+    };
 
     // Atomic write: temp file -> sync -> rename
-    atomic_write_file(&bootstrap_path, app_data_str.as_bytes())?;
+    write_json_atomic(&bootstrap_path, &file)?;
 
     #[cfg(debug_assertions)]
     eprintln!(
@@ -118,15 +308,26 @@ fn write_bootstrap_file_impl(legacy_dir: &Path, app_data: &Path) -> Result<(), S
     Ok(())
 }
 
+/// Report a migration stage through `progress`, if given.
+pub fn report_migration_progress(progress: ProgressFn, step: u32, total: u32, message: &str) {
+    if let Some(report) = progress {
+        report(MigrationProgress {
+            step,
+            total,
+            message: message.to_string(),
+        });
+    }
+}
+
 /// Migrate legacy files - core implementation.
-fn migrate_legacy_files_impl(legacy_dir: &Path, app_data: &Path) -> Result<(), String> {
+fn migrate_legacy_files_impl(
+    legacy_dir: &Path,
+    app_data: &Path,
+    progress: ProgressFn,
+) -> Result<(), String> {
     // Ensure app data directory exists
-    fs::create_dir_all(app_data).map_err(|e| {
-        format!(
-            "Failed to create app data directory {:?}: {}",
-            app_data, e
-        )
-    })?;
+    fs::create_dir_all(app_data)
+        .map_err(|e| format!("Failed to create app data directory {:?}: {}", app_data, e))?;
 
     let marker_path = app_data.join(MIGRATION_MARKER);
 
@@ -150,7 +351,7 @@ fn migrate_legacy_files_impl(legacy_dir: &Path, app_data: &Path) -> Result<(), S
     }
 
     // Perform migration
-    let migration_result = perform_migration(legacy_dir, app_data);
+    let migration_result = perform_migration(legacy_dir, app_data, progress);
 
     // Only write marker if migration succeeded or there was nothing to migrate
     match &migration_result {
@@ -169,13 +370,110 @@ fn migrate_legacy_files_impl(legacy_dir: &Path, app_data: &Path) -> Result<(), S
         }
         Err(e) => {
             // Migration failed - don't write marker so we can retry
-            eprintln!("[App Paths] Migration failed, will retry on next launch: {}", e);
+            eprintln!(
+                "[App Paths] Migration failed, will retry on next launch: {}",
+                e
+            );
         }
     }
 
     migration_result
 }
 
+/// Holds the startup lock; removes the lock file on drop.
+#[derive(Debug)]
+pub struct LockGuard {
+    path: PathBuf,
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Acquire the lock file - core implementation.
+fn acquire_lock_impl(app_data: &Path, is_alive: impl Fn(u32) -> bool) -> Result<LockGuard, String> {
+    fs::create_dir_all(app_data)
+        .map_err(|e| format!("Failed to create app data directory {:?}: {}", app_data, e))?;
+
+    let path = app_data.join(LOCK_FILE);
+
+    // Second attempt only happens after reclaiming a stale lock
+    for _ in 0..2 {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                let pid = std::process::id().to_string();
+                if let Err(e) = file.write_all(pid.as_bytes()) {
+                    let _ = fs::remove_file(&path);
+                    return Err(format!("Failed to write lock file {:?}: {}", path, e));
+                }
+                return Ok(LockGuard { path });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let holder = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|s| s.trim().parse::<u32>().ok());
+
+                let stale = match holder {
+                    Some(pid) => !is_alive(pid),
+                    None => fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|t| t.elapsed().ok())
+                        .is_some_and(|age| age > UNREADABLE_LOCK_GRACE),
+                };
+                if !stale {
+                    return Err(match holder {
+                        Some(pid) => {
+                            format!("App data is locked by another instance (pid {})", pid)
+                        }
+                        None => "App data is locked by another instance".to_string(),
+                    });
+                }
+
+                #[cfg(debug_assertions)]
+                eprintln!(
+                    "[App Paths] Reclaiming stale lock {:?} (pid {:?})",
+                    path, holder
+                );
+
+                remove_file_if_exists(&path)?;
+            }
+            Err(e) => {
+                return Err(format!("Failed to create lock file {:?}: {}", path, e));
+            }
+        }
+    }
+
+    Err(format!("Failed to acquire lock file {:?}", path))
+}
+
+/// Whether a process with this PID is running.
+/// Only ESRCH counts as dead; EPERM means the process exists under another user.
+#[cfg(unix)]
+pub(crate) fn is_process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return true;
+    };
+    // SAFETY: signal 0 performs only the existence/permission check.
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+/// Whether a process with this PID is running.
+/// Errs on the side of "alive" when the check itself fails.
+#[cfg(windows)]
+pub(crate) fn is_process_alive(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).contains(&format!("\"{}\"", pid)))
+        .unwrap_or(true)
+}
+
 /// Result of trying to create the migration marker.
 enum MarkerResult {
     /// Marker already exists (migration was done)
@@ -196,127 +494,184 @@ fn try_create_marker(path: &Path) -> MarkerResult {
 }
 
 /// Perform the actual file migration.
-fn perform_migration(legacy_dir: &Path, app_data: &Path) -> Result<(), String> {
-    let legacy_settings = legacy_dir.join(MCP_SETTINGS_FILE);
-    let new_settings = app_data.join(MCP_SETTINGS_FILE);
+///
+/// Copies `mcp-settings.json`, `mcp-port` and the `workspaces/` tree. Any
+/// failure aborts the migration so the marker isn't written and it retries.
+/// Each of the three stages is reported through `progress` before it runs.
+fn perform_migration(
+    legacy_dir: &Path,
+    app_data: &Path,
+    progress: ProgressFn,
+) -> Result<(), String> {
+    const TOTAL: u32 = 3;
+    report_migration_progress(progress, 1, TOTAL, "Migrating MCP settings");
+    migrate_file(
+        &legacy_dir.join(MCP_SETTINGS_FILE),
+        &app_data.join(MCP_SETTINGS_FILE),
+    )?;
+    report_migration_progress(progress, 2, TOTAL, "Migrating MCP port file");
+    migrate_file(
+        &legacy_dir.join(MCP_PORT_FILE),
+        &app_data.join(MCP_PORT_FILE),
+    )?;
+    report_migration_progress(progress, 3, TOTAL, "Migrating workspaces");
+    migrate_dir(
+        &legacy_dir.join(LEGACY_WORKSPACES_DIR),
+        &app_data.join(LEGACY_WORKSPACES_DIR),
+    )
+}
 
+/// Copy every file under `legacy` into `dest`, recursively, never
+/// overwriting what is already there.
+fn migrate_dir(legacy: &Path, dest: &Path) -> Result<(), String> {
+    if !legacy.is_dir() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(dest)
+        .map_err(|e| format!("Failed to create directory {:?}: {}", dest, e))?;
+    let entries = fs::read_dir(legacy)
+        .map_err(|e| format!("Failed to read legacy directory {:?}: {}", legacy, e))?;
+
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| format!("Failed to read legacy directory {:?}: {}", legacy, e))?;
+        let source = entry.path();
+        let target = dest.join(entry.file_name());
+        if source.is_dir() {
+            migrate_dir(&source, &target)?;
+        } else {
+            migrate_file(&source, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy one legacy file. The destination is created with `create_new`, so
+/// an existing file (user data or another instance's copy) is kept.
+fn migrate_file(legacy: &Path, dest: &Path) -> Result<(), String> {
     // Only migrate if source exists
-    if !legacy_settings.exists() {
-        #[cfg(debug_assertions)]
-        eprintln!("[App Paths] No legacy settings to migrate");
+    if !legacy.exists() {
         return Ok(());
     }
 
     // Try to create destination atomically to avoid TOCTOU
-    match OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(&new_settings)
-    {
+    match OpenOptions::new().write(true).create_new(true).open(dest) {
         Ok(mut dest_file) => {
             // We own the destination - copy contents
-            let contents = fs::read(&legacy_settings).map_err(|e| {
-                format!(
-                    "Failed to read legacy settings {:?}: {}",
-                    legacy_settings, e
-                )
-            })?;
-
-            dest_file.write_all(&contents).map_err(|e| {
-                format!(
-                    "Failed to write settings to {:?}: {}",
-                    new_settings, e
-                )
-            })?;
-
-            dest_file.sync_all().map_err(|e| {
-                format!("Failed to sync settings file {:?}: {}", new_settings, e)
-            })?;
+            let result = fs::read(legacy)
+                .map_err(|e| format!("Failed to read legacy file {:?}: {}", legacy, e))
+                .and_then(|contents| {
+                    dest_file
+                        .write_all(&contents)
+                        .and_then(|_| dest_file.sync_all())
+                        .map_err(|e| format!("Failed to write {:?}: {}", dest, e))
+                });
+
+            if result.is_err() {
+                // Don't leave a partial copy that would block the retry
+                let _ = fs::remove_file(dest);
+            }
+            result?;
 
             #[cfg(debug_assertions)]
-            eprintln!(
-                "[App Paths] Migrated {} from {:?} to {:?}",
-                MCP_SETTINGS_FILE, legacy_settings, new_settings
-            );
+            eprintln!("[App Paths] Migrated {:?} to {:?}", legacy, dest);
 
             Ok(())
         }
         Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
-            // Destination already exists - another instance migrated or user has settings
+            // Destination already exists - another instance migrated or user has data
             #[cfg(debug_assertions)]
-            eprintln!(
-                "[App Paths] Settings already exist at {:?}, skipping migration",
-                new_settings
-            );
+            eprintln!("[App Paths] {:?} already exists, skipping migration", dest);
             Ok(())
         }
-        Err(e) => Err(format!(
-            "Failed to create settings file {:?}: {}",
-            new_settings, e
-        )),
+        Err(e) => Err(format!("Failed to create {:?}: {}", dest, e)),
+    }
+}
+
+/// Serialize `value` as pretty-printed JSON and write it with `atomic_write_file`.
+pub fn write_json_atomic<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|e| format!("Failed to serialize {:?}: {}", path, e))?;
+    atomic_write_file(path, &json)
+}
+
+/// Why `read_json` failed.
+#[derive(Debug)]
+pub enum ReadJsonError {
+    /// Reading failed (permissions, I/O); the file itself may be fine
+    Io(String),
+    /// The contents don't parse (truncated, not UTF-8, wrong shape)
+    Parse(String),
+}
+
+impl From<ReadJsonError> for String {
+    fn from(error: ReadJsonError) -> Self {
+        match error {
+            ReadJsonError::Io(e) | ReadJsonError::Parse(e) => e,
+        }
     }
 }
 
+/// Read and parse a JSON file. A missing file is `Ok(None)`.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, ReadJsonError> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(ReadJsonError::Io(format!(
+                "Failed to read {:?}: {}",
+                path, e
+            )))
+        }
+    };
+    serde_json::from_slice(&contents)
+        .map(Some)
+        .map_err(|e| ReadJsonError::Parse(format!("Failed to parse {:?}: {}", path, e)))
+}
+
 /// Write a file atomically using temp file + sync + rename pattern.
 /// This prevents partial reads by other processes.
+///
+/// Each call gets its own uniquely named temp file, so concurrent writers of
+/// the same path never share (and truncate) one; the last rename wins.
 pub fn atomic_write_file(path: &Path, contents: &[u8]) -> Result<(), String> {
-    let parent = path.parent().ok_or_else(|| {
-        format!("Cannot determine parent directory of {:?}", path)
-    })?;
+    let parent = path
+        .parent()
+        .ok_or_else(|| format!("Cannot determine parent directory of {:?}", path))?;
 
-    // Create temp file in same directory (for same-filesystem rename)
-    let temp_path = parent.join(format!(
-        ".{}.tmp.{}",
-        path.file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("file"),
-        std::process::id()
-    ));
-
-    // Write to temp file
-    let mut temp_file = File::create(&temp_path).map_err(|e| {
-        format!("Failed to create temp file {:?}: {}", temp_path, e)
-    })?;
+    // Create temp file in same directory (for same-filesystem rename).
+    // It is removed on drop unless persisted.
+    let mut temp_file = tempfile::NamedTempFile::new_in(parent)
+        .map_err(|e| format!("Failed to create temp file in {:?}: {}", parent, e))?;
 
-    temp_file.write_all(contents).map_err(|e| {
-        let _ = fs::remove_file(&temp_path);
-        format!("Failed to write temp file {:?}: {}", temp_path, e)
-    })?;
+    temp_file
+        .write_all(contents)
+        .map_err(|e| format!("Failed to write temp file {:?}: {}", temp_file.path(), e))?;
 
     // Sync to disk before rename
-    temp_file.sync_all().map_err(|e| {
-        let _ = fs::remove_file(&temp_path);
-        format!("Failed to sync temp file {:?}: {}", temp_path, e)
-    })?;
+    temp_file
+        .as_file()
+        .sync_all()
+        .map_err(|e| format!("Failed to sync temp file {:?}: {}", temp_file.path(), e))?;
 
     // Atomic rename (on Unix) or replace (on Windows)
+    temp_file.persist(path).map_err(|e| {
+        format!(
+            "Failed to rename {:?} to {:?}: {}",
+            e.file.path(),
+            path,
+            e.error
+        )
+    })?;
+
+    // Persist the rename itself: the new directory entry isn't durable
+    // until the parent directory is synced. The file is already in place,
+    // so a failure here (some filesystems refuse directory fsync) is not fatal.
     #[cfg(unix)]
-    {
-        fs::rename(&temp_path, path).map_err(|e| {
-            let _ = fs::remove_file(&temp_path);
-            format!(
-                "Failed to rename {:?} to {:?}: {}",
-                temp_path, path, e
-            )
-        })?;
-    }
-
-    #[cfg(windows)]
-    {
-        // Windows rename fails if target exists, use replace
-        if path.exists() {
-            fs::remove_file(path).map_err(|e| {
-                let _ = fs::remove_file(&temp_path);
-                format!("Failed to remove existing {:?}: {}", path, e)
-            })?;
-        }
-        fs::rename(&temp_path, path).map_err(|e| {
-            let _ = fs::remove_file(&temp_path);
-            format!(
-                "Failed to rename {:?} to {:?}: {}",
-                temp_path, path, e
-            )
-        })?;
+    if let Err(_e) = File::open(parent).and_then(|dir| dir.sync_all()) {
+        #[cfg(debug_assertions)]
+        eprintln!("[App Paths] Failed to sync directory {:?}: {}", parent, _e);
     }
 
     Ok(())
@@ -324,7 +679,6 @@ pub fn atomic_write_file(path: &Path, contents: &[u8]) -> Result<(), String> {
 
 /// Remove a file, returning Ok for NotFound (idempotent delete).
 /// Returns error for other failures (permission denied, etc.)
-#[allow(dead_code)]
 pub fn remove_file_if_exists(path: &Path) -> Result<(), String> {
     match fs::remove_file(path) {
         Ok(()) => Ok(()),
@@ -345,6 +699,82 @@ mod tests {
     use std::thread;
     use tempfile::tempdir;
 
+    // ------------------------------------------------------------------------
+    // acquire_lock_impl tests
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_lock_excludes_live_holder() {
+        let dir = tempdir().unwrap();
+
+        let guard = acquire_lock_impl(dir.path(), |_| true).unwrap();
+        let contents = fs::read_to_string(dir.path().join(LOCK_FILE)).unwrap();
+        assert_eq!(contents, std::process::id().to_string());
+
+        let err = acquire_lock_impl(dir.path(), |_| true).unwrap_err();
+        assert!(err.contains("locked by another instance"));
+
+        drop(guard);
+        assert!(!dir.path().join(LOCK_FILE).exists());
+        assert!(acquire_lock_impl(dir.path(), |_| true).is_ok());
+    }
+
+    #[test]
+    fn test_lock_reclaims_stale_holder() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(LOCK_FILE), "999999").unwrap();
+
+        let _guard = acquire_lock_impl(dir.path(), |pid| pid != 999999).unwrap();
+        let contents = fs::read_to_string(dir.path().join(LOCK_FILE)).unwrap();
+        assert_eq!(contents, std::process::id().to_string());
+    }
+
+    #[test]
+    fn test_fresh_unreadable_lock_is_held() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(LOCK_FILE), "").unwrap();
+
+        assert!(acquire_lock_impl(dir.path(), |_| false).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_current_process_is_alive() {
+        assert!(is_process_alive(std::process::id()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_liveness_uses_errno() {
+        // PID 1 belongs to another user in most environments (EPERM) but is alive
+        assert!(is_process_alive(1));
+
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        assert!(!is_process_alive(pid));
+    }
+
+    // ------------------------------------------------------------------------
+    // data_dir_override tests
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_data_dir_override() {
+        assert_eq!(data_dir_override(None), None);
+        assert_eq!(data_dir_override(Some(OsString::new())), None);
+
+        let dir = tempdir().unwrap();
+        assert_eq!(
+            data_dir_override(Some(dir.path().as_os_str().to_owned())),
+            Some(dir.path().to_path_buf())
+        );
+
+        let relative = data_dir_override(Some("portable-data".into())).unwrap();
+        assert!(relative.is_absolute());
+        assert!(relative.ends_with("portable-data"));
+    }
+
     // ------------------------------------------------------------------------
     // atomic_write_file tests
     // ------------------------------------------------------------------------
@@ -412,6 +842,39 @@ mod tests {
         reader.join().unwrap();
     }
 
+    #[test]
+    fn test_atomic_write_concurrent_writers() {
+        // Writers of the same path must not share a temp file
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("session.json");
+
+        let barrier = Arc::new(Barrier::new(2));
+        let writers: Vec<_> = [b"AAAA".as_slice(), b"BBBBBBBB".as_slice()]
+            .into_iter()
+            .map(|contents| {
+                let path = path.clone();
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..100 {
+                        atomic_write_file(&path, contents).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(
+            contents == "AAAA" || contents == "BBBBBBBB",
+            "Got {:?}",
+            contents
+        );
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
     #[test]
     fn test_atomic_write_cleans_up_temp_on_failure() {
         let dir = tempdir().unwrap();
@@ -427,6 +890,29 @@ mod tests {
         assert_eq!(entries.len(), 1); // Only the subdir
     }
 
+    // ------------------------------------------------------------------------
+    // read_json / write_json_atomic tests
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_json_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.json");
+
+        assert_eq!(read_json::<Vec<u32>>(&path).unwrap(), None);
+
+        write_json_atomic(&path, &vec![1u32, 2, 3]).unwrap();
+        assert_eq!(read_json::<Vec<u32>>(&path).unwrap(), Some(vec![1, 2, 3]));
+
+        fs::write(&path, "{ truncated").unwrap();
+        let err = read_json::<Vec<u32>>(&path).unwrap_err();
+        assert!(matches!(&err, ReadJsonError::Parse(e) if e.contains("Failed to parse")));
+
+        // A directory can't be read, which is not a parse error
+        let err = read_json::<Vec<u32>>(dir.path()).unwrap_err();
+        assert!(matches!(err, ReadJsonError::Io(_)));
+    }
+
     // ------------------------------------------------------------------------
     // remove_file_if_exists tests
     // ------------------------------------------------------------------------
@@ -464,8 +950,52 @@ mod tests {
         let bootstrap_path = legacy_dir.path().join(BOOTSTRAP_FILE);
         assert!(bootstrap_path.exists());
 
-        let contents = fs::read_to_string(&bootstrap_path).unwrap();
-        assert_eq!(contents, app_data.path().to_str().unwrap());
+        let read = read_bootstrap_file_impl(legacy_dir.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.path, app_data.path());
+        assert!(read.verified);
+    }
+
+    #[test]
+    fn test_bootstrap_file_plain_path_is_unverified() {
+        let legacy_dir = tempdir().unwrap();
+        assert!(read_bootstrap_file_impl(legacy_dir.path())
+            .unwrap()
+            .is_none());
+
+        fs::write(legacy_dir.path().join(BOOTSTRAP_FILE), "/data/vmark\n").unwrap();
+        let read = read_bootstrap_file_impl(legacy_dir.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.path, PathBuf::from("/data/vmark"));
+        assert!(!read.verified);
+    }
+
+    #[test]
+    fn test_bootstrap_file_tampered_hash_is_rejected() {
+        let legacy_dir = tempdir().unwrap();
+        let app_data = tempdir().unwrap();
+        write_bootstrap_file_impl(legacy_dir.path(), app_data.path()).unwrap();
+
+        // Point the file elsewhere without updating the checksum
+        let bootstrap_path = legacy_dir.path().join(BOOTSTRAP_FILE);
+        let mut file: BootstrapFile =
+            serde_json::from_str(&fs::read_to_string(&bootstrap_path).unwrap()).unwrap();
+        file.path = "/tmp/elsewhere".to_string();
+        fs::write(&bootstrap_path, serde_json::to_string(&file).unwrap()).unwrap();
+
+        let err = read_bootstrap_file_impl(legacy_dir.path()).unwrap_err();
+        assert!(err.contains("checksum mismatch"));
+
+        // Rewriting repairs it
+        write_bootstrap_file_impl(legacy_dir.path(), app_data.path()).unwrap();
+        assert!(
+            read_bootstrap_file_impl(legacy_dir.path())
+                .unwrap()
+                .unwrap()
+                .verified
+        );
     }
 
     #[test]
@@ -490,8 +1020,10 @@ mod tests {
         write_bootstrap_file_impl(legacy_dir.path(), app_data1.path()).unwrap();
         write_bootstrap_file_impl(legacy_dir.path(), app_data2.path()).unwrap();
 
-        let contents = fs::read_to_string(legacy_dir.path().join(BOOTSTRAP_FILE)).unwrap();
-        assert_eq!(contents, app_data2.path().to_str().unwrap());
+        let read = read_bootstrap_file_impl(legacy_dir.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.path, app_data2.path());
     }
 
     // ------------------------------------------------------------------------
@@ -507,7 +1039,7 @@ mod tests {
         let legacy_settings = legacy_dir.path().join(MCP_SETTINGS_FILE);
         fs::write(&legacy_settings, r#"{"toolMode":"full"}"#).unwrap();
 
-        migrate_legacy_files_impl(legacy_dir.path(), app_data.path()).unwrap();
+        migrate_legacy_files_impl(legacy_dir.path(), app_data.path(), None).unwrap();
 
         // Check settings were copied
         let new_settings = app_data.path().join(MCP_SETTINGS_FILE);
@@ -520,6 +1052,98 @@ mod tests {
         assert!(marker.exists());
     }
 
+    #[test]
+    fn test_migration_reports_progress() {
+        let legacy_dir = tempdir().unwrap();
+        let app_data = tempdir().unwrap();
+        fs::write(legacy_dir.path().join(MCP_PORT_FILE), "9223").unwrap();
+
+        let events = std::cell::RefCell::new(Vec::new());
+        let record = |p: MigrationProgress| events.borrow_mut().push(p);
+        migrate_legacy_files_impl(legacy_dir.path(), app_data.path(), Some(&record)).unwrap();
+
+        let events = events.into_inner();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events.iter().map(|p| p.step).collect::<Vec<_>>(), [1, 2, 3]);
+        assert!(events.iter().all(|p| p.total == 3));
+        assert_eq!(events[1].message, "Migrating MCP port file");
+
+        let json = serde_json::to_value(&events[0]).unwrap();
+        assert_eq!(json["step"], 1);
+        assert_eq!(json["total"], 3);
+    }
+
+    #[test]
+    fn test_migration_copies_mixed_legacy_contents() {
+        let legacy_dir = tempdir().unwrap();
+        let app_data = tempdir().unwrap();
+
+        fs::write(legacy_dir.path().join(MCP_SETTINGS_FILE), "{}").unwrap();
+        fs::write(legacy_dir.path().join(MCP_PORT_FILE), "9223").unwrap();
+        fs::write(legacy_dir.path().join("unrelated.txt"), "ignored").unwrap();
+        let workspaces = legacy_dir.path().join(LEGACY_WORKSPACES_DIR);
+        fs::create_dir_all(workspaces.join("nested")).unwrap();
+        fs::write(workspaces.join("a.json"), "a").unwrap();
+        fs::write(workspaces.join("nested").join("b.json"), "b").unwrap();
+
+        // An existing workspace file is kept
+        let new_workspaces = app_data.path().join(LEGACY_WORKSPACES_DIR);
+        fs::create_dir_all(&new_workspaces).unwrap();
+        fs::write(new_workspaces.join("a.json"), "mine").unwrap();
+
+        migrate_legacy_files_impl(legacy_dir.path(), app_data.path(), None).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(app_data.path().join(MCP_PORT_FILE)).unwrap(),
+            "9223"
+        );
+        assert_eq!(
+            fs::read_to_string(new_workspaces.join("a.json")).unwrap(),
+            "mine"
+        );
+        assert_eq!(
+            fs::read_to_string(new_workspaces.join("nested").join("b.json")).unwrap(),
+            "b"
+        );
+        assert!(!app_data.path().join("unrelated.txt").exists());
+        assert!(app_data.path().join(MIGRATION_MARKER).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_migration_failure_leaves_marker_unwritten() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let legacy_dir = tempdir().unwrap();
+        let app_data = tempdir().unwrap();
+
+        fs::write(legacy_dir.path().join(MCP_SETTINGS_FILE), "{}").unwrap();
+        let workspaces = legacy_dir.path().join(LEGACY_WORKSPACES_DIR);
+        fs::create_dir_all(&workspaces).unwrap();
+        let unreadable = workspaces.join("locked.json");
+        fs::write(&unreadable, "x").unwrap();
+        fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Root can read anything; nothing to test then
+        if fs::read(&unreadable).is_ok() {
+            return;
+        }
+
+        assert!(migrate_legacy_files_impl(legacy_dir.path(), app_data.path(), None).is_err());
+        assert!(!app_data.path().join(MIGRATION_MARKER).exists());
+        // The failed copy was cleaned up so the retry can create it
+        assert!(!app_data
+            .path()
+            .join(LEGACY_WORKSPACES_DIR)
+            .join("locked.json")
+            .exists());
+
+        // Once readable, the retry completes
+        fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o644)).unwrap();
+        migrate_legacy_files_impl(legacy_dir.path(), app_data.path(), None).unwrap();
+        assert!(app_data.path().join(MIGRATION_MARKER).exists());
+    }
+
     #[test]
     fn test_migration_skips_if_marker_exists() {
         let legacy_dir = tempdir().unwrap();
@@ -536,7 +1160,7 @@ mod tests {
         )
         .unwrap();
 
-        migrate_legacy_files_impl(legacy_dir.path(), app_data.path()).unwrap();
+        migrate_legacy_files_impl(legacy_dir.path(), app_data.path(), None).unwrap();
 
         // Settings should NOT be copied (marker was present)
         assert!(!app_data.path().join(MCP_SETTINGS_FILE).exists());
@@ -561,7 +1185,7 @@ mod tests {
         )
         .unwrap();
 
-        migrate_legacy_files_impl(legacy_dir.path(), app_data.path()).unwrap();
+        migrate_legacy_files_impl(legacy_dir.path(), app_data.path(), None).unwrap();
 
         // Original new settings should be preserved
         let contents = fs::read_to_string(app_data.path().join(MCP_SETTINGS_FILE)).unwrap();
@@ -574,7 +1198,7 @@ mod tests {
         let app_data = tempdir().unwrap();
 
         // No legacy settings exist
-        migrate_legacy_files_impl(legacy_dir.path(), app_data.path()).unwrap();
+        migrate_legacy_files_impl(legacy_dir.path(), app_data.path(), None).unwrap();
 
         // Marker should still be created
         assert!(app_data.path().join(MIGRATION_MARKER).exists());
@@ -593,8 +1217,8 @@ mod tests {
         .unwrap();
 
         // Run migration twice
-        migrate_legacy_files_impl(legacy_dir.path(), app_data.path()).unwrap();
-        migrate_legacy_files_impl(legacy_dir.path(), app_data.path()).unwrap();
+        migrate_legacy_files_impl(legacy_dir.path(), app_data.path(), None).unwrap();
+        migrate_legacy_files_impl(legacy_dir.path(), app_data.path(), None).unwrap();
 
         // Should succeed without error
         let contents = fs::read_to_string(app_data.path().join(MCP_SETTINGS_FILE)).unwrap();
@@ -625,7 +1249,7 @@ mod tests {
                 let a = app_path.clone();
                 thread::spawn(move || {
                     b.wait();
-                    migrate_legacy_files_impl(&l, &a)
+                    migrate_legacy_files_impl(&l, &a, None)
                 })
             })
             .collect();
@@ -1,15 +1,23 @@
-//! macOS Dock recent documents integration.
+//! Dock / taskbar recent documents integration.
 //!
-//! Registers opened files with NSDocumentController so they appear
-//! in the "Recent Documents" submenu when right-clicking the Dock icon.
+//! - macOS: registers opened files with NSDocumentController so they appear
+//!   in the "Recent Documents" submenu when right-clicking the Dock icon.
+//! - Windows: adds them to the shell's recent documents, which feeds the
+//!   taskbar Jump List.
+//! - Linux: records them in the freedesktop recently-used list
+//!   (`recently-used.xbel`), which GTK file choosers and desktops read.
 
+#[cfg(target_os = "macos")]
 use objc2::MainThreadMarker;
+#[cfg(target_os = "macos")]
 use objc2_app_kit::NSDocumentController;
+#[cfg(target_os = "macos")]
 use objc2_foundation::{NSString, NSURL};
 use std::path::Path;
 
 /// Register a file path with macOS Recent Documents.
 /// This makes the file appear in the Dock right-click menu.
+#[cfg(target_os = "macos")]
 pub fn register_recent_document(path: &str) {
     // Validate path exists
     if !Path::new(path).exists() {
@@ -33,3 +41,230 @@ pub fn register_recent_document(path: &str) {
     #[cfg(debug_assertions)]
     eprintln!("[dock_recent] Registered: {}", path);
 }
+
+/// Register a file path with the freedesktop recently-used list.
+/// The list is created if absent and rewritten if it can't be parsed.
+#[cfg(target_os = "linux")]
+pub fn register_recent_document(path: &str) {
+    // Validate path exists
+    if !Path::new(path).exists() {
+        #[cfg(debug_assertions)]
+        eprintln!("[dock_recent] Path does not exist: {}", path);
+        return;
+    }
+
+    if let Err(_e) = xbel::register(path) {
+        #[cfg(debug_assertions)]
+        eprintln!("[dock_recent] Failed to update recently-used list: {}", _e);
+        return;
+    }
+
+    #[cfg(debug_assertions)]
+    eprintln!("[dock_recent] Registered: {}", path);
+}
+
+/// Register a file path with Windows recent documents.
+/// This makes the file appear in the taskbar Jump List.
+#[cfg(target_os = "windows")]
+pub fn register_recent_document(path: &str) {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::UI::Shell::{SHAddToRecentDocs, SHARD_PATHW};
+
+    // Validate path exists
+    if !Path::new(path).exists() {
+        #[cfg(debug_assertions)]
+        eprintln!("[dock_recent] Path does not exist: {}", path);
+        return;
+    }
+
+    // SHARD_PATHW takes a NUL-terminated UTF-16 path
+    let wide: Vec<u16> = Path::new(path)
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    // SAFETY: `wide` is NUL-terminated and outlives the call
+    unsafe {
+        SHAddToRecentDocs(SHARD_PATHW.0 as u32, Some(wide.as_ptr().cast()));
+    }
+
+    #[cfg(debug_assertions)]
+    eprintln!("[dock_recent] Registered: {}", path);
+}
+
+/// Minimal reader/writer for the freedesktop `recently-used.xbel` list.
+///
+/// Only our own entry is touched; other bookmarks are kept verbatim.
+#[cfg(target_os = "linux")]
+mod xbel {
+    use std::path::{Path, PathBuf};
+
+    const HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0"
+      xmlns:bookmark="http://www.freedesktop.org/standards/desktop-bookmarks"
+      xmlns:mime="http://www.freedesktop.org/standards/shared-mime-info"
+>
+"#;
+    const FOOTER: &str = "</xbel>\n";
+    const APP_NAME: &str = "VMark";
+
+    /// `$XDG_DATA_HOME/recently-used.xbel` (usually `~/.local/share`)
+    fn list_path() -> Result<PathBuf, String> {
+        dirs::data_dir()
+            .map(|dir| dir.join("recently-used.xbel"))
+            .ok_or_else(|| "Cannot determine XDG data directory".to_string())
+    }
+
+    pub fn register(path: &str) -> Result<(), String> {
+        let list = list_path()?;
+        let existing = std::fs::read_to_string(&list).ok();
+
+        let exec = std::env::current_exe()
+            .map(|exe| format!("'{} %u'", exe.display()))
+            .unwrap_or_else(|_| "'vmark %u'".to_string());
+        let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string();
+        let updated = upsert(existing.as_deref(), &file_uri(path), mime_type(path), &exec, &now);
+
+        if let Some(parent) = list.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        crate::app_paths::atomic_write_file(&list, updated.as_bytes())
+    }
+
+    /// Insert or refresh the bookmark for `href`, returning the new file.
+    /// A missing or malformed list is replaced by one holding just this entry.
+    pub(super) fn upsert(existing: Option<&str>, href: &str, mime: &str, exec: &str, now: &str) -> String {
+        let mut bookmarks = existing.and_then(body).unwrap_or_default();
+
+        // Drop our previous entry, keeping when it was first added
+        let mut added = now.to_string();
+        let start_tag = format!("<bookmark href=\"{}\"", href);
+        if let Some(start) = bookmarks.find(&start_tag) {
+            if let Some(len) = bookmarks[start..].find("</bookmark>") {
+                let end = start + len + "</bookmark>".len();
+                if let Some(prev) = attr(&bookmarks[start..end], "added") {
+                    added = prev.to_string();
+                }
+                let end = end + bookmarks[end..].len() - bookmarks[end..].trim_start().len();
+                bookmarks.replace_range(start..end, "");
+            }
+        }
+
+        let entry = format!(
+            r#"  <bookmark href="{href}" added="{added}" modified="{now}" visited="{now}">
+    <info>
+      <metadata owner="http://freedesktop.org">
+        <mime:mime-type type="{mime}"/>
+        <bookmark:applications>
+          <bookmark:application name="{app}" exec="{exec}" modified="{now}" count="1"/>
+        </bookmark:applications>
+      </metadata>
+    </info>
+  </bookmark>
+"#,
+            app = APP_NAME,
+            exec = escape(exec),
+        );
+
+        format!("{}{}{}{}", HEADER, bookmarks, entry, FOOTER)
+    }
+
+    /// The bookmarks between the `<xbel>` root tags, or None if malformed.
+    fn body(xbel: &str) -> Option<String> {
+        let open = xbel.find("<xbel")?;
+        let start = open + xbel[open..].find('>')? + 1;
+        let end = xbel.rfind("</xbel>")?;
+        if end < start {
+            return None;
+        }
+        // Every bookmark must be closed, or we'd write back a broken file
+        let inner = &xbel[start..end];
+        if inner.matches("<bookmark ").count() != inner.matches("</bookmark>").count() {
+            return None;
+        }
+        let inner = inner.trim_start_matches(['\r', '\n']);
+        Some(inner.to_string())
+    }
+
+    fn attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+        let key = format!(" {}=\"", name);
+        let start = tag.find(&key)? + key.len();
+        let len = tag[start..].find('"')?;
+        Some(&tag[start..start + len])
+    }
+
+    /// `file://` URI with each path segment percent-encoded.
+    pub(super) fn file_uri(path: &str) -> String {
+        let encoded: Vec<String> = path
+            .split('/')
+            .map(|segment| urlencoding::encode(segment).into_owned())
+            .collect();
+        format!("file://{}", encoded.join("/"))
+    }
+
+    fn mime_type(path: &str) -> &'static str {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown") => {
+                "text/markdown"
+            }
+            _ => "text/plain",
+        }
+    }
+
+    fn escape(value: &str) -> String {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\'', "&apos;")
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::xbel::{file_uri, upsert};
+
+    const EXEC: &str = "'vmark %u'";
+
+    #[test]
+    fn test_file_uri_encodes_segments() {
+        assert_eq!(file_uri("/home/me/My Notes/a#1.md"), "file:///home/me/My%20Notes/a%231.md");
+    }
+
+    #[test]
+    fn test_upsert_creates_list() {
+        let xbel = upsert(None, "file:///a.md", "text/markdown", EXEC, "T1");
+        assert!(xbel.starts_with("<?xml"));
+        assert!(xbel.trim_end().ends_with("</xbel>"));
+        assert_eq!(xbel.matches("<bookmark ").count(), 1);
+        assert!(xbel.contains("exec=\"&apos;vmark %u&apos;\""));
+    }
+
+    #[test]
+    fn test_upsert_keeps_others_and_refreshes_own_entry() {
+        let first = upsert(None, "file:///a.md", "text/markdown", EXEC, "T1");
+        let second = upsert(Some(&first), "file:///b.md", "text/markdown", EXEC, "T2");
+        let third = upsert(Some(&second), "file:///a.md", "text/markdown", EXEC, "T3");
+
+        assert_eq!(third.matches("<bookmark ").count(), 2);
+        assert_eq!(third.matches("href=\"file:///a.md\"").count(), 1);
+        // Re-registering keeps the original added time
+        assert!(third.contains(r#"href="file:///a.md" added="T1" modified="T3""#));
+        // Most recent entry goes last
+        assert!(third.find("file:///b.md").unwrap() < third.find("file:///a.md").unwrap());
+    }
+
+    #[test]
+    fn test_upsert_rewrites_malformed_list() {
+        let broken = "<xbel version=\"1.0\"><bookmark href=\"file:///x\">";
+        let xbel = upsert(Some(broken), "file:///a.md", "text/markdown", EXEC, "T1");
+        assert_eq!(xbel.matches("<bookmark ").count(), 1);
+        assert!(!xbel.contains("file:///x"));
+
+        let garbage = upsert(Some("not xml"), "file:///a.md", "text/markdown", EXEC, "T1");
+        assert_eq!(garbage.matches("<bookmark ").count(), 1);
+    }
+}
@@ -0,0 +1,243 @@
+//! Drafts - Auto-saved content of untitled documents.
+//!
+//! Each untitled document is persisted to `<app_data>/drafts/<draft_id>.md`
+//! independently of the hot-exit session file. The frontend deletes a draft
+//! once its document is saved to a real path. Total storage is capped and the
+//! oldest drafts are evicted first.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::app_paths::{atomic_write_file, remove_file_if_exists, resolve_app_data_dir};
+
+/// Drafts directory name (inside app data)
+const DRAFTS_DIR: &str = "drafts";
+
+/// Draft file extension
+const DRAFT_EXT: &str = "md";
+
+/// Maximum total size of all drafts
+const MAX_DRAFTS_TOTAL_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Maximum length of a draft title
+const TITLE_MAX_CHARS: usize = 80;
+
+/// Summary of a stored draft.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DraftInfo {
+    pub draft_id: String,
+    /// First non-empty line of the draft, truncated
+    pub title: String,
+    pub size_bytes: u64,
+    /// Last modification time (Unix milliseconds)
+    pub modified_at: u64,
+}
+
+fn get_drafts_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data = resolve_app_data_dir(app)?;
+    Ok(app_data.join(DRAFTS_DIR))
+}
+
+/// Draft IDs come from the frontend; only allow safe file-name characters.
+fn validate_draft_id(draft_id: &str) -> Result<(), String> {
+    let valid = !draft_id.is_empty()
+        && draft_id.len() <= 128
+        && draft_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid draft id: {}", draft_id))
+    }
+}
+
+fn draft_path(dir: &Path, draft_id: &str) -> Result<PathBuf, String> {
+    validate_draft_id(draft_id)?;
+    Ok(dir.join(format!("{}.{}", draft_id, DRAFT_EXT)))
+}
+
+fn draft_title(content: &str) -> String {
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(|line| line.trim_start_matches('#').trim())
+        .unwrap_or("")
+        .chars()
+        .take(TITLE_MAX_CHARS)
+        .collect()
+}
+
+fn save_draft_impl(dir: &Path, draft_id: &str, content: &str) -> Result<(), String> {
+    let path = draft_path(dir, draft_id)?;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create drafts dir: {}", e))?;
+    atomic_write_file(&path, content.as_bytes())?;
+    evict_oldest_drafts(dir, draft_id, MAX_DRAFTS_TOTAL_BYTES)
+}
+
+fn list_drafts_impl(dir: &Path) -> Result<Vec<DraftInfo>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read drafts dir: {}", e)),
+    };
+
+    let mut drafts = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(DRAFT_EXT) {
+            continue;
+        }
+        let Some(draft_id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if validate_draft_id(draft_id).is_err() {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let modified_at = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let title = fs::read_to_string(&path)
+            .map(|content| draft_title(&content))
+            .unwrap_or_default();
+
+        drafts.push(DraftInfo {
+            draft_id: draft_id.to_string(),
+            title,
+            size_bytes: metadata.len(),
+            modified_at,
+        });
+    }
+
+    // Newest first
+    drafts.sort_by(|a, b| b.modified_at.cmp(&a.modified_at).then(a.draft_id.cmp(&b.draft_id)));
+    Ok(drafts)
+}
+
+/// Remove the oldest drafts until total size fits within `max_bytes`.
+/// The draft that was just saved (`keep_id`) is never evicted.
+fn evict_oldest_drafts(dir: &Path, keep_id: &str, max_bytes: u64) -> Result<(), String> {
+    let mut drafts = list_drafts_impl(dir)?;
+    let mut total: u64 = drafts.iter().map(|d| d.size_bytes).sum();
+
+    // Oldest last after sort; pop from the end
+    while total > max_bytes {
+        let Some(oldest) = drafts.pop() else {
+            break;
+        };
+        if oldest.draft_id == keep_id {
+            continue;
+        }
+        remove_file_if_exists(&draft_path(dir, &oldest.draft_id)?)?;
+        total = total.saturating_sub(oldest.size_bytes);
+
+        #[cfg(debug_assertions)]
+        eprintln!("[Drafts] Evicted draft {} ({} bytes)", oldest.draft_id, oldest.size_bytes);
+    }
+    Ok(())
+}
+
+/// Save (create or overwrite) the draft for an untitled document.
+#[tauri::command]
+pub fn save_draft(app: AppHandle, draft_id: String, content: String) -> Result<(), String> {
+    save_draft_impl(&get_drafts_dir(&app)?, &draft_id, &content)
+}
+
+/// List stored drafts, newest first.
+#[tauri::command]
+pub fn list_drafts(app: AppHandle) -> Result<Vec<DraftInfo>, String> {
+    list_drafts_impl(&get_drafts_dir(&app)?)
+}
+
+/// Read the content of a draft.
+#[tauri::command]
+pub fn read_draft(app: AppHandle, draft_id: String) -> Result<String, String> {
+    let path = draft_path(&get_drafts_dir(&app)?, &draft_id)?;
+    fs::read_to_string(&path).map_err(|e| format!("Failed to read draft {}: {}", draft_id, e))
+}
+
+/// Delete a draft (e.g., after the document was saved to disk).
+#[tauri::command]
+pub fn delete_draft(app: AppHandle, draft_id: String) -> Result<(), String> {
+    remove_file_if_exists(&draft_path(&get_drafts_dir(&app)?, &draft_id)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn save_and_list_draft() {
+        let dir = tempdir().unwrap();
+        let drafts_dir = dir.path().join(DRAFTS_DIR);
+
+        save_draft_impl(&drafts_dir, "untitled-1", "# Hello\n\nworld").unwrap();
+
+        let drafts = list_drafts_impl(&drafts_dir).unwrap();
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].draft_id, "untitled-1");
+        assert_eq!(drafts[0].title, "Hello");
+        assert_eq!(
+            fs::read_to_string(drafts_dir.join("untitled-1.md")).unwrap(),
+            "# Hello\n\nworld"
+        );
+    }
+
+    #[test]
+    fn list_missing_dir_is_empty() {
+        let dir = tempdir().unwrap();
+        assert!(list_drafts_impl(&dir.path().join("missing")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_unsafe_draft_ids() {
+        let dir = tempdir().unwrap();
+        assert!(save_draft_impl(dir.path(), "../escape", "x").is_err());
+        assert!(save_draft_impl(dir.path(), "a/b", "x").is_err());
+        assert!(save_draft_impl(dir.path(), "", "x").is_err());
+    }
+
+    #[test]
+    fn eviction_removes_oldest_but_keeps_current() {
+        let dir = tempdir().unwrap();
+        let path = dir.path();
+
+        fs::write(path.join("old.md"), "a".repeat(10)).unwrap();
+        let old_time = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(path.join("old.md"))
+            .unwrap()
+            .set_modified(old_time)
+            .unwrap();
+        fs::write(path.join("new.md"), "b".repeat(10)).unwrap();
+
+        evict_oldest_drafts(path, "new", 15).unwrap();
+        assert!(!path.join("old.md").exists());
+        assert!(path.join("new.md").exists());
+
+        // Current draft survives even when it alone exceeds the cap
+        evict_oldest_drafts(path, "new", 5).unwrap();
+        assert!(path.join("new.md").exists());
+    }
+
+    #[test]
+    fn draft_title_uses_first_non_empty_line() {
+        assert_eq!(draft_title("\n\n## Notes  \nbody"), "Notes");
+        assert_eq!(draft_title(""), "");
+        assert_eq!(draft_title(&"x".repeat(200)).len(), TITLE_MAX_CHARS);
+    }
+}
@@ -0,0 +1,291 @@
+//! Default Markdown handler registration.
+//!
+//! Lets the user make VMark the system default application for Markdown
+//! files. Each platform has its own mechanism:
+//! - macOS: Launch Services (`LSSetDefaultRoleHandlerForContentType`)
+//! - Windows: per-user ProgID in the registry + `SHChangeNotify`
+//! - Linux: `.desktop` entry + `xdg-mime default`
+//!
+//! Some systems ask the user to confirm the change in their own dialog, so a
+//! request may be reported as pending until the user answers.
+
+use serde::Serialize;
+
+/// Result of asking the OS to make VMark the default Markdown handler.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HandlerRequestStatus {
+    /// VMark is now the default handler
+    Applied,
+    /// The OS is asking the user to confirm; query again later
+    Pending,
+}
+
+/// Decide the request status from the handler state after the request.
+fn request_status(is_default_now: bool) -> HandlerRequestStatus {
+    if is_default_now {
+        HandlerRequestStatus::Applied
+    } else {
+        HandlerRequestStatus::Pending
+    }
+}
+
+/// Make VMark the default handler for Markdown files.
+#[tauri::command]
+pub fn set_as_default_markdown_handler() -> Result<HandlerRequestStatus, String> {
+    platform::set_default()?;
+    Ok(request_status(platform::is_default()))
+}
+
+/// Check whether VMark is currently the default handler for Markdown files.
+#[tauri::command]
+pub fn is_default_markdown_handler() -> bool {
+    platform::is_default()
+}
+
+// ============================================================================
+// macOS
+// ============================================================================
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2::rc::Retained;
+    use objc2_foundation::NSString;
+
+    const MARKDOWN_UTI: &str = "net.daringfireball.markdown";
+    const BUNDLE_ID: &str = "app.vmark";
+    /// kLSRolesAll
+    const LS_ROLES_ALL: u32 = 0xFFFF_FFFF;
+
+    type CFStringRef = *const std::ffi::c_void;
+
+    #[link(name = "CoreServices", kind = "framework")]
+    extern "C" {
+        fn LSSetDefaultRoleHandlerForContentType(
+            content_type: CFStringRef,
+            role: u32,
+            handler_bundle_id: CFStringRef,
+        ) -> i32;
+        fn LSCopyDefaultRoleHandlerForContentType(content_type: CFStringRef, role: u32) -> CFStringRef;
+    }
+
+    /// NSString is toll-free bridged with CFString.
+    fn cf(s: &NSString) -> CFStringRef {
+        s as *const NSString as CFStringRef
+    }
+
+    pub fn set_default() -> Result<(), String> {
+        let uti = NSString::from_str(MARKDOWN_UTI);
+        let bundle = NSString::from_str(BUNDLE_ID);
+        // On recent macOS this may show a system confirmation dialog
+        let status = unsafe { LSSetDefaultRoleHandlerForContentType(cf(&uti), LS_ROLES_ALL, cf(&bundle)) };
+        if status != 0 {
+            return Err(format!("Launch Services error: {}", status));
+        }
+        Ok(())
+    }
+
+    pub fn is_default() -> bool {
+        let uti = NSString::from_str(MARKDOWN_UTI);
+        let handler = unsafe { LSCopyDefaultRoleHandlerForContentType(cf(&uti), LS_ROLES_ALL) };
+        if handler.is_null() {
+            return false;
+        }
+        // "Copy" rule: we own the returned string, Retained releases it on drop
+        let handler = unsafe { Retained::from_raw(handler as *mut NSString) };
+        handler
+            .map(|h| h.to_string().eq_ignore_ascii_case(BUNDLE_ID))
+            .unwrap_or(false)
+    }
+}
+
+// ============================================================================
+// Windows
+// ============================================================================
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::process::Command;
+
+    const PROG_ID: &str = "VMark.Markdown";
+    const EXTENSIONS: &[&str] = &[".md", ".markdown", ".mdown", ".mkd"];
+    const USER_CHOICE_KEY: &str =
+        r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\FileExts\.md\UserChoice";
+
+    const SHCNE_ASSOCCHANGED: i32 = 0x0800_0000;
+    const SHCNF_IDLIST: u32 = 0;
+
+    #[link(name = "shell32")]
+    extern "system" {
+        fn SHChangeNotify(
+            event_id: i32,
+            flags: u32,
+            item1: *const std::ffi::c_void,
+            item2: *const std::ffi::c_void,
+        );
+    }
+
+    fn reg(args: &[&str]) -> Result<String, String> {
+        let output = Command::new("reg")
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run reg: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    pub fn set_default() -> Result<(), String> {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let command = format!("\"{}\" \"%1\"", exe.display());
+        let prog_key = format!(r"HKCU\Software\Classes\{}", PROG_ID);
+
+        reg(&["add", &prog_key, "/ve", "/d", "Markdown Document", "/f"])?;
+        reg(&["add", &format!(r"{}\shell\open\command", prog_key), "/ve", "/d", &command, "/f"])?;
+        for ext in EXTENSIONS {
+            let ext_key = format!(r"HKCU\Software\Classes\{}", ext);
+            reg(&["add", &ext_key, "/ve", "/d", PROG_ID, "/f"])?;
+            reg(&["add", &format!(r"{}\OpenWithProgids", ext_key), "/v", PROG_ID, "/t", "REG_NONE", "/f"])?;
+        }
+
+        unsafe {
+            SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST, std::ptr::null(), std::ptr::null());
+        }
+
+        // Windows 10+ protects UserChoice; if the user has picked another app
+        // they must confirm in Settings, so open the default apps page.
+        if !is_default() {
+            let _ = Command::new("cmd")
+                .args(["/C", "start", "", "ms-settings:defaultapps"])
+                .spawn();
+        }
+        Ok(())
+    }
+
+    pub fn is_default() -> bool {
+        match reg(&["query", USER_CHOICE_KEY, "/v", "ProgId"]) {
+            Ok(output) => super::parse_reg_value(&output, "ProgId")
+                .map(|v| v.eq_ignore_ascii_case(PROG_ID))
+                .unwrap_or(false),
+            // No UserChoice: the per-user class default applies
+            Err(_) => reg(&["query", r"HKCU\Software\Classes\.md", "/ve"])
+                .ok()
+                .and_then(|output| super::parse_reg_value(&output, "(Default)"))
+                .map(|v| v.eq_ignore_ascii_case(PROG_ID))
+                .unwrap_or(false),
+        }
+    }
+}
+
+// ============================================================================
+// Linux
+// ============================================================================
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::process::Command;
+
+    const DESKTOP_FILE: &str = "vmark.desktop";
+    const MIME_TYPES: &[&str] = &["text/markdown", "text/x-markdown"];
+
+    /// Write a user-local .desktop entry if no installed one exists.
+    fn ensure_desktop_entry() -> Result<(), String> {
+        let data_dirs = std::env::var("XDG_DATA_DIRS")
+            .unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+        let user_dir = dirs::data_dir().ok_or("Cannot determine data directory")?;
+        let installed = std::iter::once(user_dir.clone())
+            .chain(data_dirs.split(':').map(std::path::PathBuf::from))
+            .any(|dir| dir.join("applications").join(DESKTOP_FILE).exists());
+        if installed {
+            return Ok(());
+        }
+
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let apps_dir = user_dir.join("applications");
+        std::fs::create_dir_all(&apps_dir).map_err(|e| e.to_string())?;
+        let entry = format!(
+            "[Desktop Entry]\nType=Application\nName=VMark\nExec=\"{}\" %F\nMimeType={};\nTerminal=false\nCategories=Office;TextEditor;\n",
+            exe.display(),
+            MIME_TYPES.join(";")
+        );
+        std::fs::write(apps_dir.join(DESKTOP_FILE), entry).map_err(|e| e.to_string())
+    }
+
+    pub fn set_default() -> Result<(), String> {
+        ensure_desktop_entry()?;
+        for mime in MIME_TYPES {
+            let status = Command::new("xdg-mime")
+                .args(["default", DESKTOP_FILE, mime])
+                .status()
+                .map_err(|e| format!("Failed to run xdg-mime: {}", e))?;
+            if !status.success() {
+                return Err(format!("xdg-mime failed for {}", mime));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_default() -> bool {
+        Command::new("xdg-mime")
+            .args(["query", "default", "text/markdown"])
+            .output()
+            .ok()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim() == DESKTOP_FILE)
+            .unwrap_or(false)
+    }
+}
+
+// ============================================================================
+// Unsupported platforms
+// ============================================================================
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod platform {
+    pub fn set_default() -> Result<(), String> {
+        Err("Setting the default Markdown handler is not supported on this platform".to_string())
+    }
+
+    pub fn is_default() -> bool {
+        false
+    }
+}
+
+/// Extract a value from `reg query` output, e.g.
+/// `    ProgId    REG_SZ    VMark.Markdown`.
+#[cfg_attr(not(any(target_os = "windows", test)), allow(dead_code))]
+fn parse_reg_value(output: &str, name: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        if parts.next()? != name {
+            return None;
+        }
+        let _kind = parts.next()?;
+        let value = parts.collect::<Vec<_>>().join(" ");
+        (!value.is_empty()).then_some(value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_reflects_handler_state() {
+        assert_eq!(request_status(true), HandlerRequestStatus::Applied);
+        assert_eq!(request_status(false), HandlerRequestStatus::Pending);
+    }
+
+    #[test]
+    fn parse_reg_value_finds_named_value() {
+        let output = "\r\nHKEY_CURRENT_USER\\Software\\...\\UserChoice\r\n    Hash    REG_SZ    abc=\r\n    ProgId    REG_SZ    VMark.Markdown\r\n";
+        assert_eq!(parse_reg_value(output, "ProgId"), Some("VMark.Markdown".to_string()));
+    }
+
+    #[test]
+    fn parse_reg_value_handles_default_and_spaces() {
+        let output = "HKEY_CURRENT_USER\\Software\\Classes\\.md\n    (Default)    REG_SZ    Some App.md\n";
+        assert_eq!(parse_reg_value(output, "(Default)"), Some("Some App.md".to_string()));
+        assert_eq!(parse_reg_value(output, "ProgId"), None);
+    }
+}
@@ -0,0 +1,139 @@
+//! OS file manager integration: reveal a file, move a file to the trash.
+//!
+//! Revealing:
+//! - macOS: `open -R` selects the item in Finder
+//! - Windows: `explorer /select,` selects the item in Explorer
+//! - Linux: no portable "select" exists, so the parent directory is opened
+//!   with `xdg-open`
+//!
+//! User-initiated deletes go to the trash so they can be undone; permanent
+//! removal (`app_paths::remove_file_if_exists`) is for internal files only.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Error prefix returned by `move_to_trash` when the item could not be moved
+/// to the trash (e.g. a network or removable volume without one). The
+/// frontend can offer to delete permanently instead.
+pub const TRASH_UNSUPPORTED: &str = "TRASH_UNSUPPORTED";
+
+/// Check that `path` is non-empty and exists.
+fn validate_existing_path(path: &str) -> Result<PathBuf, String> {
+    if path.trim().is_empty() {
+        return Err("No path given".to_string());
+    }
+    let path = PathBuf::from(path);
+    if !path.exists() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+    Ok(path)
+}
+
+/// Directory opened on Linux: the item's parent, or the item itself at root.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parent_directory(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => path,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn reveal_command(path: &Path) -> Command {
+    let mut cmd = Command::new("open");
+    cmd.arg("-R").arg(path);
+    cmd
+}
+
+#[cfg(target_os = "windows")]
+fn reveal_command(path: &Path) -> Command {
+    use std::os::windows::process::CommandExt;
+    // Explorer parses `/select,"path"` itself, so the argument must not be
+    // quoted as a whole.
+    let mut cmd = Command::new("explorer");
+    cmd.raw_arg(format!("/select,\"{}\"", path.display()));
+    cmd
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn reveal_command(path: &Path) -> Command {
+    let mut cmd = Command::new("xdg-open");
+    cmd.arg(parent_directory(path));
+    cmd
+}
+
+/// Show a file in Finder/Explorer (or its folder on Linux).
+/// The exit status is ignored (Explorer reports a non-zero exit status even on
+/// success); the child is reaped on a background thread so it doesn't linger
+/// as a zombie.
+#[tauri::command]
+pub fn reveal_in_file_manager(path: String) -> Result<(), String> {
+    let path = validate_existing_path(&path)?;
+    let mut child = reveal_command(&path)
+        .spawn()
+        .map_err(|e| format!("Failed to open file manager: {}", e))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// Error for a failed trash attempt. If the item is still there, it was not
+/// trashed and the caller may fall back to a permanent delete.
+fn trash_error(path: &Path, detail: impl std::fmt::Display) -> String {
+    if path.exists() {
+        format!("{}: {}", TRASH_UNSUPPORTED, detail)
+    } else {
+        format!("Failed to move {} to trash: {}", path.display(), detail)
+    }
+}
+
+/// Move a file or folder to the OS trash / recycle bin.
+/// Fails with a `TRASH_UNSUPPORTED`-prefixed error when the item's volume
+/// has no trash.
+#[tauri::command]
+pub fn move_to_trash(path: String) -> Result<(), String> {
+    let path = validate_existing_path(&path)?;
+    trash::delete(&path).map_err(|e| {
+        #[cfg(debug_assertions)]
+        eprintln!("[FileManager] Failed to trash {:?}: {}", path, e);
+        trash_error(&path, e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_existing_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.md");
+        std::fs::write(&file, "").unwrap();
+
+        assert_eq!(validate_existing_path(file.to_str().unwrap()).unwrap(), file);
+        assert!(validate_existing_path("  ").is_err());
+        let missing = dir.path().join("missing.md");
+        let err = validate_existing_path(missing.to_str().unwrap()).unwrap_err();
+        assert!(err.contains("File not found"));
+    }
+
+    #[test]
+    fn test_trash_error_distinguishes_unsupported() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.md");
+        std::fs::write(&file, "").unwrap();
+
+        let err = trash_error(&file, "no trash on volume");
+        assert!(err.starts_with(TRASH_UNSUPPORTED));
+        let err = trash_error(&dir.path().join("gone.md"), "failed");
+        assert!(!err.starts_with(TRASH_UNSUPPORTED));
+    }
+
+    #[test]
+    fn test_parent_directory() {
+        assert_eq!(parent_directory(Path::new("/docs/notes.md")), Path::new("/docs"));
+        assert_eq!(parent_directory(Path::new("/")), Path::new("/"));
+        assert_eq!(parent_directory(Path::new("notes.md")), Path::new("notes.md"));
+    }
+}
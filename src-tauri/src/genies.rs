@@ -8,7 +8,7 @@ use std::collections::HashMap;
 use std::fs::{self, OpenOptions};
 use std::io::Write as IoWrite;
 use std::path::{Path, PathBuf};
-use tauri::{command, AppHandle, Manager};
+use tauri::{command, AppHandle};
 
 // ============================================================================
 // Types
@@ -98,7 +98,7 @@ pub fn read_genie(app: AppHandle, path: String) -> Result<GenieContent, String>
 // ============================================================================
 
 pub fn global_genies_dir(app: &AppHandle) -> Result<PathBuf, String> {
-    let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let app_data = crate::app_paths::resolve_app_data_dir(app)?;
     Ok(app_data.join("genies"))
 }
 
@@ -0,0 +1,119 @@
+//! Periodic background capture of the hot exit session
+//!
+//! Guards against losing unsaved work to a hard crash between explicit
+//! captures. Autosaved sessions are written exactly like captured ones, under
+//! the same `SESSION_LOCK`; a tick that finds a capture in progress is skipped.
+//! So is a tick where some window didn't respond: a partial session must not
+//! replace (and rotate out) a complete one.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::AppHandle;
+use tokio::time::MissedTickBehavior;
+
+use super::coordinator::capture_session;
+use super::session::SessionData;
+use super::storage::{write_session_atomic, SESSION_LOCK};
+
+/// Shortest allowed autosave interval
+const MIN_INTERVAL_SECS: u64 = 5;
+
+/// Running autosave task, if any
+static AUTOSAVE_TASK: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+/// Start (or restart) autosaving every `interval_secs` seconds.
+pub fn start(app: AppHandle, interval_secs: u64) {
+    let interval = Duration::from_secs(interval_secs.max(MIN_INTERVAL_SECS));
+
+    let task = tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ticker.tick().await; // The first tick completes immediately
+
+        let mut last_hash = None;
+        loop {
+            ticker.tick().await;
+
+            // A capture is writing the session right now; it is newer anyway
+            let Ok(_guard) = SESSION_LOCK.try_lock() else {
+                #[cfg(debug_assertions)]
+                eprintln!("[HotExit] Autosave skipped: capture in progress");
+                continue;
+            };
+
+            let session = match capture_session(&app).await {
+                Ok(capture) if capture.complete => capture.session,
+                Ok(_) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("[HotExit] Autosave skipped: not all windows responded");
+                    continue;
+                }
+                Err(_e) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("[HotExit] Autosave capture skipped: {}", _e);
+                    continue;
+                }
+            };
+
+            let hash = content_hash(&session);
+            if hash.is_some() && hash == last_hash {
+                continue;
+            }
+
+            match write_session_atomic(&app, &session).await {
+                Ok(()) => last_hash = hash,
+                Err(e) => eprintln!("[HotExit] Autosave failed: {}", e),
+            }
+        }
+    });
+
+    if let Some(previous) = AUTOSAVE_TASK
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .replace(task)
+    {
+        previous.abort();
+    }
+}
+
+/// Stop autosaving. No-op if autosave isn't running.
+pub fn stop() {
+    if let Some(task) = AUTOSAVE_TASK
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .take()
+    {
+        task.abort();
+    }
+}
+
+/// Hash of the session content, ignoring the capture timestamp.
+fn content_hash(session: &SessionData) -> Option<u64> {
+    let mut session = session.clone();
+    session.timestamp = 0;
+    let bytes = serde_json::to_vec(&session).ok()?;
+
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash_ignores_timestamp() {
+        let mut a = SessionData::new("1.0.0".to_string());
+        let mut b = a.clone();
+        a.timestamp = 100;
+        b.timestamp = 200;
+        assert_eq!(content_hash(&a), content_hash(&b));
+
+        b.vmark_version = "1.0.1".to_string();
+        assert_ne!(content_hash(&a), content_hash(&b));
+    }
+}
@@ -4,8 +4,9 @@
 //! They are used both in production (update restart flow) and for developer testing.
 
 use tauri::AppHandle;
+use super::{autosave, crypto};
 use super::session::{SessionData, WindowState};
-use super::storage::{read_session, delete_session, write_session_atomic};
+use super::storage::{read_session, delete_session, write_session_atomic, was_unclean_shutdown, SESSION_LOCK};
 use super::coordinator::{
     capture_session,
     restore_session,
@@ -16,14 +17,44 @@ use super::coordinator::{
     RestoreMultiWindowResult,
 };
 
-/// Capture session from all windows and persist to disk atomically
+/// Capture session from all windows and persist to disk atomically.
+/// Windows that didn't respond in time are left out (better than nothing
+/// when quitting).
 #[tauri::command]
 pub async fn hot_exit_capture(app: AppHandle) -> Result<SessionData, String> {
-    let session = capture_session(&app).await?;
+    let _guard = SESSION_LOCK.lock().await;
+    let session = capture_session(&app).await?.session;
     write_session_atomic(&app, &session).await?;
     Ok(session)
 }
 
+/// Capture and persist the session every `interval_secs` seconds (min 5)
+/// until `hot_exit_stop_autosave`. Unchanged sessions are not rewritten.
+#[tauri::command]
+pub fn hot_exit_start_autosave(app: AppHandle, interval_secs: u64) {
+    autosave::start(app, interval_secs);
+}
+
+/// Stop the periodic session autosave
+#[tauri::command]
+pub fn hot_exit_stop_autosave() {
+    autosave::stop();
+}
+
+/// Encrypt the session file at rest from the next write on. Existing
+/// sessions stay readable whichever way this is set.
+#[tauri::command]
+pub fn hot_exit_set_encryption(enabled: bool) {
+    crypto::set_enabled(enabled);
+}
+
+/// Whether the previous run ended without a clean quit (crash or kill).
+/// The frontend offers crash recovery only in that case.
+#[tauri::command]
+pub fn hot_exit_was_unclean_shutdown() -> bool {
+    was_unclean_shutdown()
+}
+
 /// Restore session to current window from provided session data
 #[tauri::command]
 pub fn hot_exit_restore(app: AppHandle, session: SessionData) -> Result<(), String> {
@@ -103,14 +103,21 @@ fn normalize_window_label(state: &mut WindowState, expected_label: &str) {
     }
 }
 
+/// A captured session and whether every window responded in time
+pub struct SessionCapture {
+    pub session: SessionData,
+    /// False when some windows timed out and are missing from `session`
+    pub complete: bool,
+}
+
 /// Capture session from all windows
-pub async fn capture_session(app: &AppHandle) -> Result<SessionData, String> {
+pub async fn capture_session(app: &AppHandle) -> Result<SessionCapture, String> {
     // Get all document windows (main + doc-*)
     let windows: Vec<String> = app
         .webview_windows()
         .into_iter()
         .filter_map(|(label, _)| {
-            if label == MAIN_WINDOW_LABEL || label.starts_with("doc-") {
+            if crate::quit::is_document_window_label(&label) {
                 Some(label)
             } else {
                 None
@@ -245,7 +252,10 @@ pub async fn capture_session(app: &AppHandle) -> Result<SessionData, String> {
         workspace: None, // Workspace capture not yet implemented
     };
 
-    Ok(session)
+    Ok(SessionCapture {
+        session,
+        complete: got_responses == expected_responses,
+    })
 }
 
 async fn wait_for_all_responses(state: Arc<Mutex<CaptureState>>, expected: usize) {
@@ -316,7 +326,7 @@ pub fn restore_session(
         .or_else(|| {
             app.webview_windows()
                 .into_iter()
-                .find(|(label, _)| label.starts_with("doc-"))
+                .find(|(label, _)| crate::quit::is_document_window_label(label))
                 .map(|(_, w)| w)
         })
         .ok_or("No document window found for restore")?;
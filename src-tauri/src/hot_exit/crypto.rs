@@ -0,0 +1,167 @@
+//! At-rest encryption for hot exit sessions
+//!
+//! The session holds unsaved document content, so it can optionally be
+//! written as AES-256-GCM ciphertext. The key lives in the OS keychain,
+//! never next to the session file.
+//!
+//! File format: `MAGIC || nonce (12 bytes) || ciphertext || tag`.
+//! Plain JSON sessions are still read, so toggling encryption is seamless.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// Header identifying an encrypted session file
+const MAGIC: &[u8] = b"VMKSESS1";
+
+/// Keychain service and account for the session key
+const KEYRING_SERVICE: &str = "app.vmark";
+const KEYRING_ACCOUNT: &str = "hot-exit-session-key";
+
+/// AES-256 key length in bytes
+pub const KEY_LEN: usize = 32;
+
+/// Whether new sessions are written encrypted
+static ENCRYPTION_ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENCRYPTION_ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENCRYPTION_ENABLED.load(Ordering::SeqCst)
+}
+
+/// Whether a session file's bytes are ciphertext written by `encrypt`.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Load the session key from the keychain. With `create`, a missing key is
+/// generated and stored; otherwise a missing key returns `Ok(None)`.
+pub fn load_key(create: bool) -> Result<Option<[u8; KEY_LEN]>, String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_ACCOUNT)
+        .map_err(|e| format!("Failed to open keychain entry: {}", e))?;
+
+    match entry.get_password() {
+        Ok(stored) => match decode_key(&stored) {
+            Some(key) => return Ok(Some(key)),
+            None if !create => return Ok(None),
+            None => {
+                #[cfg(debug_assertions)]
+                eprintln!("[HotExit] Session key in keychain is malformed, replacing it");
+            }
+        },
+        Err(keyring::Error::NoEntry) if !create => return Ok(None),
+        Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(format!("Failed to read session key: {}", e)),
+    }
+
+    let mut key = [0u8; KEY_LEN];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| "Failed to generate session key".to_string())?;
+    entry
+        .set_password(&encode_key(&key))
+        .map_err(|e| format!("Failed to store session key: {}", e))?;
+    Ok(Some(key))
+}
+
+/// Encrypt a serialized session.
+pub fn encrypt(key: &[u8; KEY_LEN], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| "Failed to generate nonce".to_string())?;
+
+    let mut sealed = plaintext.to_vec();
+    aead_key(key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(MAGIC), &mut sealed)
+        .map_err(|_| "Failed to encrypt session".to_string())?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + sealed.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+/// Decrypt a session written by `encrypt`. Fails if the key doesn't match
+/// (e.g. it was rotated) or the file was tampered with.
+pub fn decrypt(key: &[u8; KEY_LEN], data: &[u8]) -> Result<Vec<u8>, String> {
+    let body = data.strip_prefix(MAGIC).ok_or("Session is not encrypted")?;
+    if body.len() < NONCE_LEN {
+        return Err("Encrypted session is truncated".to_string());
+    }
+    let (nonce, sealed) = body.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| "Invalid session nonce".to_string())?;
+
+    let mut buf = sealed.to_vec();
+    let plaintext = aead_key(key)?
+        .open_in_place(nonce, Aad::from(MAGIC), &mut buf)
+        .map_err(|_| "Failed to decrypt session (wrong key or corrupted file)".to_string())?;
+    Ok(plaintext.to_vec())
+}
+
+fn aead_key(key: &[u8; KEY_LEN]) -> Result<LessSafeKey, String> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| "Invalid session key".to_string())
+}
+
+/// Hex-encode the key for keychain storage.
+fn encode_key(key: &[u8; KEY_LEN]) -> String {
+    key.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_key(hex: &str) -> Option<[u8; KEY_LEN]> {
+    if hex.len() != KEY_LEN * 2 || !hex.is_ascii() {
+        return None;
+    }
+    let mut key = [0u8; KEY_LEN];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; KEY_LEN] = [7; KEY_LEN];
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let plaintext = br#"{"version":2,"windows":[]}"#;
+        let sealed = encrypt(&KEY, plaintext).unwrap();
+
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.windows(plaintext.len()).any(|w| w == plaintext));
+        assert_eq!(decrypt(&KEY, &sealed).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_decrypt_with_rotated_key_fails() {
+        let sealed = encrypt(&KEY, b"secret draft").unwrap();
+        assert!(decrypt(&[8; KEY_LEN], &sealed).is_err());
+        assert!(decrypt(&KEY, &sealed[..MAGIC.len() + 4]).is_err());
+    }
+
+    #[test]
+    fn test_plain_json_is_not_encrypted() {
+        assert!(!is_encrypted(br#"{"version":2}"#));
+    }
+
+    #[test]
+    fn test_key_hex_round_trip() {
+        let mut key = [0u8; KEY_LEN];
+        key[0] = 0xab;
+        key[31] = 0x01;
+        assert_eq!(decode_key(&encode_key(&key)), Some(key));
+        assert_eq!(decode_key("zz"), None);
+        assert_eq!(decode_key(&"g".repeat(KEY_LEN * 2)), None);
+    }
+}
@@ -9,6 +9,8 @@
 //! - Future sessions (higher version) cannot be migrated (fail gracefully)
 //! - Version 0 is invalid and rejected
 
+use serde_json::Value;
+
 use super::session::{SessionData, SCHEMA_VERSION};
 
 /// Minimum supported version for migration
@@ -83,6 +85,30 @@ fn migrate_v1_to_v2(mut session: SessionData) -> Result<SessionData, String> {
     Ok(session)
 }
 
+/// Parse a stored session payload of any supported version and migrate it.
+///
+/// The version is checked before deserializing, so a payload from a newer
+/// app is rejected by version rather than by whichever field fails to parse.
+/// Fields added since the payload was written are filled by serde defaults.
+pub fn migrate_session_value(value: Value) -> Result<SessionData, String> {
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or("Session has no schema version")?;
+    let version = u32::try_from(version).map_err(|_| format!("Invalid session version: {}", version))?;
+
+    if !can_migrate(version) {
+        return Err(format!(
+            "Cannot migrate session from version {} to {}. Supported versions: {} to {}",
+            version, SCHEMA_VERSION, MIN_SUPPORTED_VERSION, SCHEMA_VERSION
+        ));
+    }
+
+    let session: SessionData = serde_json::from_value(value)
+        .map_err(|e| format!("Invalid v{} session: {}", version, e))?;
+    migrate_session(session)
+}
+
 /// Check if session needs migration.
 pub fn needs_migration(session: &SessionData) -> bool {
     session.version < SCHEMA_VERSION
@@ -158,6 +184,82 @@ mod tests {
         assert!(result.unwrap_err().contains("Cannot migrate"));
     }
 
+    fn v1_session_json() -> Value {
+        serde_json::json!({
+            "version": 1,
+            "timestamp": 1_700_000_000,
+            "vmark_version": "0.3.0",
+            "windows": [{
+                "window_label": "main",
+                "is_main_window": true,
+                "active_tab_id": "tab-1",
+                "tabs": [{
+                    "id": "tab-1",
+                    "file_path": null,
+                    "title": "Untitled-1",
+                    "is_pinned": false,
+                    "document": {
+                        "content": "# Hello",
+                        "saved_content": "",
+                        "is_dirty": true,
+                        "is_missing": false,
+                        "is_divergent": false,
+                        "line_ending": "\n",
+                        "cursor_info": null,
+                        "last_modified_timestamp": null,
+                        "is_untitled": true,
+                        "untitled_number": 1
+                    }
+                }],
+                "ui_state": {
+                    "sidebar_visible": true,
+                    "sidebar_width": 260,
+                    "outline_visible": false,
+                    "sidebar_view_mode": "files",
+                    "status_bar_visible": true,
+                    "source_mode_enabled": false,
+                    "focus_mode_enabled": false,
+                    "typewriter_mode_enabled": false
+                },
+                "geometry": null
+            }],
+            "workspace": null
+        })
+    }
+
+    #[test]
+    fn test_migrate_value_v1_fills_history() {
+        let session = migrate_session_value(v1_session_json()).unwrap();
+        assert_eq!(session.version, SCHEMA_VERSION);
+
+        let doc = &session.windows[0].tabs[0].document;
+        assert_eq!(doc.content, "# Hello");
+        assert!(doc.undo_history.is_empty());
+        assert!(doc.redo_history.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_value_current_round_trip() {
+        let v2 = migrate_session_value(v1_session_json()).unwrap();
+        let json = serde_json::to_value(&v2).unwrap();
+        let again = migrate_session_value(json).unwrap();
+        assert_eq!(again.version, SCHEMA_VERSION);
+        assert_eq!(again.windows[0].tabs[0].id, "tab-1");
+    }
+
+    #[test]
+    fn test_migrate_value_rejects_unknown_payloads() {
+        let mut future = v1_session_json();
+        future["version"] = (SCHEMA_VERSION + 1).into();
+        assert!(migrate_session_value(future).unwrap_err().contains("Cannot migrate"));
+
+        let mut unversioned = v1_session_json();
+        unversioned.as_object_mut().unwrap().remove("version");
+        assert!(migrate_session_value(unversioned).is_err());
+
+        assert!(migrate_session_value(serde_json::json!({ "version": 1 })).is_err());
+    }
+
     #[test]
     fn test_needs_migration() {
         let mut session = SessionData::new("0.3.24".to_string());
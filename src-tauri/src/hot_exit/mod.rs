@@ -8,6 +8,8 @@ pub mod storage;
 pub mod coordinator;
 pub mod commands;
 pub mod migration;
+pub mod autosave;
+pub mod crypto;
 
 // Re-export commonly used types
 
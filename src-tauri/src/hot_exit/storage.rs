@@ -1,19 +1,32 @@
 //! Atomic storage operations for hot exit sessions
 //!
-//! Uses tmp + rename pattern to ensure atomic writes and data durability.
+//! Writes go through `app_paths` (tmp + rename + fsync) for atomicity and durability.
+//! Sessions are encrypted at rest when `crypto::is_enabled()`.
 
-use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
-use tauri::Manager;
-use tempfile::NamedTempFile;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use super::crypto;
+use super::migration::migrate_session_value;
 use super::session::SessionData;
 
+/// Per-instance marker (`running-<pid>`) present while the app runs; one
+/// left behind by a dead process means that run crashed
+const RUNNING_MARKER_PREFIX: &str = "running-";
+
+/// Single running marker written by older versions (holds the pid)
+const LEGACY_RUNNING_MARKER_FILE: &str = "running";
+
+/// Whether the previous run left its running marker behind
+static UNCLEAN_SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+/// Held across a session capture and its write, so explicit captures, the
+/// restart capture and autosave never write the session concurrently.
+/// Autosave only `try_lock`s it and skips its tick while a capture runs.
+pub static SESSION_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
 /// Get the hot exit session file path in app data directory
 pub fn get_session_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let app_data = app
-        .path()
-        .app_data_dir()
+    let app_data = crate::app_paths::resolve_app_data_dir(app)
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
 
     // Ensure directory exists
@@ -25,9 +38,7 @@ pub fn get_session_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
 
 /// Get the backup session path
 pub fn get_backup_session_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let app_data = app
-        .path()
-        .app_data_dir()
+    let app_data = crate::app_paths::resolve_app_data_dir(app)
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
     Ok(app_data.join("session.prev.json"))
 }
@@ -40,62 +51,64 @@ pub async fn write_session_atomic(
     let session_path = get_session_path(app)?;
     let backup_path = get_backup_session_path(app)?;
 
-    // Serialize to JSON
-    let json = serde_json::to_string_pretty(session)
-        .map_err(|e| format!("JSON serialization failed: {}", e))?;
+    let session = session.clone();
 
     // Perform all blocking I/O in spawn_blocking to avoid blocking async executor
     tokio::task::spawn_blocking(move || {
-        // Write to temporary file in same directory (ensures same filesystem)
-        let tmp_dir = session_path
-            .parent()
-            .ok_or("Session path has no parent")?;
-        let mut tmp_file = NamedTempFile::new_in(tmp_dir)
-            .map_err(|e| format!("Failed to create temp file: {}", e))?;
-
-        tmp_file
-            .write_all(json.as_bytes())
-            .map_err(|e| format!("Failed to write temp file: {}", e))?;
-
-        // Flush to disk (critical for durability)
-        tmp_file
-            .flush()
-            .map_err(|e| format!("Failed to flush temp file: {}", e))?;
-
-        tmp_file
-            .as_file()
-            .sync_all()
-            .map_err(|e| format!("Failed to sync temp file: {}", e))?;
-
-        // Backup existing session (attempt copy, ignore NotFound errors)
-        // This avoids TOCTOU race from exists() check
-        match std::fs::copy(&session_path, &backup_path) {
-            Ok(_) => {},
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                // No existing session to backup - this is fine
-            },
-            Err(e) => return Err(format!("Failed to backup session: {}", e)),
-        }
+        // Keychain access may block (or prompt), so it happens here too
+        let key = if crypto::is_enabled() {
+            Some(crypto::load_key(true)?.ok_or("Session key unavailable")?)
+        } else {
+            None
+        };
 
-        // Atomic rename (overwrites existing session.json)
-        tmp_file
-            .persist(&session_path)
-            .map_err(|e| format!("Failed to persist session: {}", e))?;
+        backup_session(&session_path, &backup_path, key.as_ref())?;
 
-        // Sync parent directory to ensure directory entry is persisted
-        // Critical for crash safety - ensures the file appears in directory after crash
-        if let Some(parent) = session_path.parent() {
-            if let Ok(dir) = File::open(parent) {
-                let _ = dir.sync_all(); // Best effort - ignore errors on non-Unix systems
-            }
-        }
-
-        Ok(())
+        let Some(key) = key else {
+            return crate::app_paths::write_json_atomic(&session_path, &session);
+        };
+        let json = serde_json::to_vec_pretty(&session)
+            .map_err(|e| format!("JSON serialization failed: {}", e))?;
+        crate::app_paths::atomic_write_file(&session_path, &crypto::encrypt(&key, &json)?)
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+/// Rotate the current session into the backup slot. With a `key` (encryption
+/// on), a plaintext session is encrypted on the way and a plaintext backup
+/// with nothing to replace it is deleted, so no plaintext copy outlives the
+/// switch to encryption.
+fn backup_session(
+    session_path: &Path,
+    backup_path: &Path,
+    key: Option<&[u8; crypto::KEY_LEN]>,
+) -> Result<(), String> {
+    let Some(key) = key else {
+        // Copy without an exists() check to avoid a TOCTOU race
+        return match std::fs::copy(session_path, backup_path) {
+            Ok(_) => Ok(()),
+            // No existing session to backup - this is fine
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to backup session: {}", e)),
+        };
+    };
+
+    match std::fs::read(session_path) {
+        Ok(data) if crypto::is_encrypted(&data) => {
+            crate::app_paths::atomic_write_file(backup_path, &data)
+        }
+        Ok(data) => crate::app_paths::atomic_write_file(backup_path, &crypto::encrypt(key, &data)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => match std::fs::read(backup_path) {
+            Ok(old) if !crypto::is_encrypted(&old) => {
+                crate::app_paths::remove_file_if_exists(backup_path)
+            }
+            _ => Ok(()),
+        },
+        Err(e) => Err(format!("Failed to backup session: {}", e)),
+    }
+}
+
 /// Read session from disk
 pub async fn read_session(
     app: &tauri::AppHandle,
@@ -106,14 +119,133 @@ pub async fn read_session(
         return Ok(None);
     }
 
-    let contents = tokio::fs::read_to_string(&session_path)
+    let contents = tokio::fs::read(&session_path)
         .await
         .map_err(|e| format!("Failed to read session file: {}", e))?;
 
-    let session: SessionData = serde_json::from_str(&contents)
-        .map_err(|e| format!("Failed to parse session JSON: {}", e))?;
+    let contents = if crypto::is_encrypted(&contents) {
+        let decrypted = tokio::task::spawn_blocking(move || decrypt_session(&contents))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?;
+        match decrypted {
+            Some(plaintext) => plaintext,
+            None => return Ok(None),
+        }
+    } else {
+        contents
+    };
+
+    Ok(parse_session(&contents))
+}
+
+/// Decrypt a stored session with the keychain key. A missing or rotated key
+/// is logged and the session dropped, like any other unreadable session.
+fn decrypt_session(data: &[u8]) -> Option<Vec<u8>> {
+    let result = crypto::load_key(false)
+        .and_then(|key| key.ok_or_else(|| "no session key in keychain".to_string()))
+        .and_then(|key| crypto::decrypt(&key, data));
+
+    match result {
+        Ok(plaintext) => Some(plaintext),
+        Err(e) => {
+            eprintln!("[HotExit] Ignoring encrypted session: {}", e);
+            None
+        }
+    }
+}
+
+/// Parse and migrate a stored session. A session that is unreadable, too
+/// old or from a newer app is logged and dropped so startup continues.
+fn parse_session(contents: &[u8]) -> Option<SessionData> {
+    let result = serde_json::from_slice(contents)
+        .map_err(|e| format!("Failed to parse session JSON: {}", e))
+        .and_then(migrate_session_value);
+
+    match result {
+        Ok(session) => Some(session),
+        Err(e) => {
+            eprintln!("[HotExit] Ignoring saved session: {}", e);
+            None
+        }
+    }
+}
+
+/// Record that the app is running. Call once at startup; a marker left by a
+/// process that is no longer alive means that run didn't quit cleanly.
+/// Markers of other running instances are left alone.
+pub fn mark_running(app: &tauri::AppHandle) -> Result<(), String> {
+    let app_data = crate::app_paths::resolve_app_data_dir(app)?;
+    let unclean = mark_running_impl(
+        &app_data,
+        std::process::id(),
+        crate::app_paths::is_process_alive,
+    )?;
+    UNCLEAN_SHUTDOWN.store(unclean, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Remove this instance's running marker on a clean quit or restart.
+pub fn clear_running_marker(app: &tauri::AppHandle) -> Result<(), String> {
+    let app_data = crate::app_paths::resolve_app_data_dir(app)?;
+    crate::app_paths::remove_file_if_exists(&running_marker_path(&app_data, std::process::id()))
+}
+
+/// Whether the previous run crashed (or was killed) instead of quitting.
+pub fn was_unclean_shutdown() -> bool {
+    UNCLEAN_SHUTDOWN.load(Ordering::SeqCst)
+}
 
-    Ok(Some(session))
+fn running_marker_path(app_data: &Path, pid: u32) -> PathBuf {
+    app_data.join(format!("{}{}", RUNNING_MARKER_PREFIX, pid))
+}
+
+/// Pid a running marker belongs to, or `None` if `name` isn't a marker.
+/// `Some(None)` is a marker whose owner can't be told (treated as dead).
+fn running_marker_owner(path: &Path, name: &str) -> Option<Option<u32>> {
+    if name == LEGACY_RUNNING_MARKER_FILE {
+        let pid = std::fs::read_to_string(path).ok()?;
+        return Some(pid.trim().parse().ok());
+    }
+    name.strip_prefix(RUNNING_MARKER_PREFIX)
+        .and_then(|pid| pid.parse().ok())
+        .map(Some)
+}
+
+/// Write the marker for `pid`, removing markers left by dead processes.
+/// Returns true if there were any (a previous run crashed).
+fn mark_running_impl(
+    app_data: &Path,
+    pid: u32,
+    is_alive: impl Fn(u32) -> bool,
+) -> Result<bool, String> {
+    std::fs::create_dir_all(app_data)
+        .map_err(|e| format!("Failed to create app data dir: {}", e))?;
+
+    let entries =
+        std::fs::read_dir(app_data).map_err(|e| format!("Failed to read app data dir: {}", e))?;
+    let mut unclean = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(owner) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| running_marker_owner(&path, name))
+        else {
+            continue;
+        };
+        // Our own pid here is a leftover from a crashed run that had it before
+        if owner.is_some_and(|owner| owner != pid && is_alive(owner)) {
+            continue;
+        }
+        unclean = true;
+        crate::app_paths::remove_file_if_exists(&path)?;
+    }
+
+    crate::app_paths::atomic_write_file(
+        &running_marker_path(app_data, pid),
+        pid.to_string().as_bytes(),
+    )?;
+    Ok(unclean)
 }
 
 /// Delete session file after successful restore
@@ -131,7 +263,84 @@ pub async fn delete_session(app: &tauri::AppHandle) -> Result<(), String> {
 
 #[cfg(test)]
 mod tests {
-    
-    // Note: These tests would require mocking AppHandle
-    // For now, we test the logic with manual integration tests
+    use super::*;
+
+    // Note: Session I/O tests would require mocking AppHandle
+    // For now, we test that logic with manual integration tests
+
+    #[test]
+    fn test_parse_session_drops_unreadable() {
+        let session = SessionData::new("0.3.24".to_string());
+        let json = serde_json::to_string(&session).unwrap();
+        assert!(parse_session(json.as_bytes()).is_some());
+
+        assert!(parse_session(b"not json").is_none());
+        assert!(parse_session(br#"{"version": 0}"#).is_none());
+    }
+
+    #[test]
+    fn test_backup_is_encrypted_once_encryption_is_on() {
+        let dir = tempfile::tempdir().unwrap();
+        let session_path = dir.path().join("session.json");
+        let backup_path = dir.path().join("session.prev.json");
+        let key = [7; crypto::KEY_LEN];
+        let plaintext = br#"{"version":2,"windows":[]}"#;
+
+        // Encryption off: plain copy
+        std::fs::write(&session_path, plaintext).unwrap();
+        backup_session(&session_path, &backup_path, None).unwrap();
+        assert_eq!(std::fs::read(&backup_path).unwrap(), plaintext);
+
+        // First encrypted write: the plaintext session is encrypted into the backup
+        backup_session(&session_path, &backup_path, Some(&key)).unwrap();
+        let backup = std::fs::read(&backup_path).unwrap();
+        assert!(crypto::is_encrypted(&backup));
+        assert_eq!(crypto::decrypt(&key, &backup).unwrap(), plaintext);
+
+        // No session to rotate in: a leftover plaintext backup is deleted
+        std::fs::remove_file(&session_path).unwrap();
+        std::fs::write(&backup_path, plaintext).unwrap();
+        backup_session(&session_path, &backup_path, Some(&key)).unwrap();
+        assert!(!backup_path.exists());
+    }
+
+    #[test]
+    fn test_running_marker_detects_unclean_shutdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let dead = |_| false;
+
+        // First launch: no marker yet
+        assert!(!mark_running_impl(dir.path(), 100, dead).unwrap());
+        // Next launch without clearing it: the previous run crashed
+        assert!(mark_running_impl(dir.path(), 200, dead).unwrap());
+        assert!(!running_marker_path(dir.path(), 100).exists());
+
+        // Clean quit removes it
+        std::fs::remove_file(running_marker_path(dir.path(), 200)).unwrap();
+        assert!(!mark_running_impl(dir.path(), 300, dead).unwrap());
+    }
+
+    #[test]
+    fn test_running_marker_ignores_live_instances() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(!mark_running_impl(dir.path(), 100, |_| true).unwrap());
+        // A second instance while the first still runs: not a crash
+        assert!(!mark_running_impl(dir.path(), 200, |pid| pid == 100).unwrap());
+        assert!(running_marker_path(dir.path(), 100).exists());
+        assert!(running_marker_path(dir.path(), 200).exists());
+    }
+
+    #[test]
+    fn test_legacy_running_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = dir.path().join(LEGACY_RUNNING_MARKER_FILE);
+
+        std::fs::write(&legacy, "100").unwrap();
+        assert!(!mark_running_impl(dir.path(), 200, |pid| pid == 100).unwrap());
+        assert!(legacy.exists());
+
+        assert!(mark_running_impl(dir.path(), 300, |_| false).unwrap());
+        assert!(!legacy.exists());
+    }
 }
@@ -1,4 +1,6 @@
+mod ai_context;
 mod ai_provider;
+mod ai_transcript;
 mod app_paths;
 mod mcp_bridge;
 mod mcp_config;
@@ -13,10 +15,17 @@ mod workspace;
 mod file_tree;
 mod hot_exit;
 mod tab_transfer;
+mod unsaved;
+mod file_association;
+mod file_manager;
+mod text_diff;
+mod drafts;
+mod markdown;
+mod recent_documents;
 
 #[cfg(target_os = "macos")]
 mod macos_menu;
-#[cfg(target_os = "macos")]
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
 mod dock_recent;
 
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -80,10 +89,12 @@ fn get_default_shell() -> String {
     }
 }
 
-/// Register a file with macOS Dock recent documents
-#[cfg(target_os = "macos")]
+/// Register a file as recently opened: the Open Recent menu and the OS
+/// recent documents (Dock, Jump List, XDG list)
 #[tauri::command]
-fn register_dock_recent(path: String) {
+fn register_dock_recent(app: tauri::AppHandle, path: String) {
+    recent_documents::add(&app, &path);
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
     dock_recent::register_recent_document(&path);
 }
 
@@ -123,19 +134,44 @@ pub fn run() {
             window_manager::open_workspace_in_new_window,
             window_manager::open_workspace_with_files_in_new_window,
             window_manager::close_window,
+            window_manager::list_document_windows,
             window_manager::force_quit,
             window_manager::request_quit,
             quit::cancel_quit,
+            quit::cancel_quit_with_reason,
+            quit::save_all_and_quit,
+            quit::report_saved,
+            quit::set_quit_window_busy,
+            quit::restart_app,
             quit::acknowledge_quit_window,
+            unsaved::unsaved_summary,
+            file_association::set_as_default_markdown_handler,
+            file_association::is_default_markdown_handler,
+            file_manager::reveal_in_file_manager,
+            file_manager::move_to_trash,
+            text_diff::diff_texts,
+            drafts::save_draft,
+            drafts::list_drafts,
+            drafts::read_draft,
+            drafts::delete_draft,
+            markdown::format::format_markdown,
+            markdown::code_blocks::extract_code_blocks,
             watcher::start_watching,
             watcher::stop_watching,
             watcher::stop_all_watchers,
             watcher::list_watchers,
             file_tree::list_directory_entries,
             workspace::open_folder_dialog,
+            workspace::open_file_dialog,
+            workspace::open_files_dialog,
             workspace::read_workspace_config,
             workspace::write_workspace_config,
             workspace::has_workspace_config,
+            workspace::watch_workspace_config,
+            workspace::unwatch_workspace_config,
+            workspace::grant_workspace_trust,
+            workspace::revoke_workspace_trust,
+            workspace::is_workspace_trusted,
             mcp_server::mcp_bridge_start,
             mcp_server::mcp_bridge_stop,
             mcp_server::mcp_server_start,
@@ -145,12 +181,19 @@ pub fn run() {
             mcp_server::mcp_bridge_client_count,
             mcp_server::write_mcp_tool_mode,
             mcp_bridge::mcp_bridge_respond,
+            mcp_bridge::mcp_bridge_notify,
+            mcp_bridge::mcp_bridge_status,
+            mcp_bridge::mcp_bridge_metrics,
             mcp_config::mcp_config_get_status,
             mcp_config::mcp_config_diagnose,
             mcp_config::mcp_config_preview,
             mcp_config::mcp_config_install,
             mcp_config::mcp_config_uninstall,
             hot_exit::commands::hot_exit_capture,
+            hot_exit::commands::hot_exit_start_autosave,
+            hot_exit::commands::hot_exit_stop_autosave,
+            hot_exit::commands::hot_exit_was_unclean_shutdown,
+            hot_exit::commands::hot_exit_set_encryption,
             hot_exit::commands::hot_exit_restore,
             hot_exit::commands::hot_exit_inspect_session,
             hot_exit::commands::hot_exit_clear_session,
@@ -158,22 +201,42 @@ pub fn run() {
             hot_exit::commands::hot_exit_get_window_state,
             hot_exit::commands::hot_exit_window_restore_complete,
             tab_transfer::detach_tab_to_new_window,
+            tab_transfer::detach_tab_to_new_window_at,
+            tab_transfer::detach_tabs_to_new_window,
             tab_transfer::claim_tab_transfer,
+            tab_transfer::claim_tab_transfers,
+            tab_transfer::find_drop_target_window,
+            tab_transfer::hover_drop_target,
+            tab_transfer::cancel_hover_drop_target,
+            tab_transfer::transfer_tab_to_existing_window,
+            tab_transfer::confirm_tab_transfer,
+            tab_transfer::push_closed_tab,
+            tab_transfer::reopen_last_closed_tab,
             get_default_shell,
             genies::get_genies_dir,
             genies::list_genies,
             genies::read_genie,
             ai_provider::detect_ai_providers,
+            ai_provider::detect_custom_ai_provider,
             ai_provider::run_ai_prompt,
             ai_provider::read_env_api_keys,
             ai_provider::test_api_key,
+            ai_provider::validate_ai_key,
             ai_provider::list_models,
+            ai_provider::list_ai_models,
             ai_provider::validate_model,
+            ai_provider::warm_provider,
+            ai_provider::set_ai_concurrency,
+            ai_provider::cancel_ai_prompt,
+            ai_provider::check_ollama_server,
+            ai_transcript::set_ai_transcript,
+            ai_transcript::get_ai_transcript_path,
+            ai_context::resolve_context_mentions,
             #[cfg(debug_assertions)]
             debug_log,
             write_temp_html,
-            #[cfg(target_os = "macos")]
             register_dock_recent,
+            recent_documents::set_recent_documents_prune_missing,
         ])
         .setup(|app| {
             let menu = menu::create_menu(app.handle())?;
@@ -183,17 +246,34 @@ pub fn run() {
             #[cfg(target_os = "macos")]
             macos_menu::apply_menu_fixes();
 
-            // Write bootstrap file for MCP sidecar discovery
-            // This file contains the path to the app data directory
-            if let Err(e) = app_paths::write_app_data_path_bootstrap(app.handle()) {
-                eprintln!("[Tauri] Warning: Failed to write app-data-path bootstrap: {}", e);
+            // Hold the startup lock while touching shared files, so a second
+            // instance launched at the same time doesn't race us
+            match app_paths::acquire_app_lock(app.handle()) {
+                Ok(_lock) => {
+                    // Write bootstrap file for MCP sidecar discovery
+                    // This file contains the path to the app data directory
+                    if let Err(e) = app_paths::write_app_data_path_bootstrap(app.handle()) {
+                        eprintln!("[Tauri] Warning: Failed to write app-data-path bootstrap: {}", e);
+                    }
+
+                    // Migrate legacy files from ~/.vmark/ to app data directory
+                    if let Err(e) = app_paths::migrate_legacy_files(app.handle()) {
+                        eprintln!("[Tauri] Warning: Failed to migrate legacy files: {}", e);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("[Tauri] Skipping startup migrations: {}", e);
+                }
             }
 
-            // Migrate legacy files from ~/.vmark/ to app data directory
-            if let Err(e) = app_paths::migrate_legacy_files(app.handle()) {
-                eprintln!("[Tauri] Warning: Failed to migrate legacy files: {}", e);
+            // Mark this run as live; a marker left by a dead process means it crashed
+            if let Err(e) = hot_exit::storage::mark_running(app.handle()) {
+                eprintln!("[Tauri] Warning: Failed to write running marker: {}", e);
             }
 
+            // Populate Open Recent from the persisted list before the frontend loads
+            recent_documents::load(app.handle());
+
             // Install default AI genies (no-op if already present)
             if let Err(e) = genies::install_default_genies(app.handle()) {
                 eprintln!("[Tauri] Warning: Failed to install default genies: {}", e);
@@ -258,7 +338,7 @@ pub fn run() {
                 #[cfg(debug_assertions)]
                 eprintln!("[Tauri] WindowEvent::CloseRequested for window '{}'", label);
                 // Only intercept close for document windows
-                if label == "main" || label.starts_with("doc-") {
+                if quit::is_document_window_label(label) {
                     api.prevent_close();
                     // Include target label in payload so frontend can filter
                     let _ = window.emit("window:close-requested", label);
@@ -326,6 +406,7 @@ pub fn run() {
                     quit::handle_window_destroyed(app, &label);
                     menu_events::clear_window_ready(&label);
                     tab_transfer::clear_unclaimed_transfer(&label);
+                    tab_transfer::clear_closed_tabs(&label);
                 }
                 // macOS: Clicking dock icon when no windows visible -> create main window
                 #[cfg(target_os = "macos")]
@@ -0,0 +1,247 @@
+//! Fenced code block extraction.
+//!
+//! Finds every fenced code block (``` or ~~~) in a document, including fences
+//! indented inside list items and fences inside blockquotes. Outside a list
+//! item a fence indented 4+ spaces is indented code, not a fence. An
+//! unterminated fence runs to the end of the document.
+
+use serde::Serialize;
+
+use super::{is_fence_close, leading_spaces, parse_fence_open};
+
+/// A fenced code block. Line numbers are 1-based and inclusive.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    /// Full info string after the fence (may contain attributes)
+    pub info: String,
+    pub content: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub fence_char: char,
+    /// False if the block ran to end of document without a closing fence
+    pub closed: bool,
+}
+
+/// Language from an info string: `rust`, `rust,ignore`, `{.rust .numberLines}`.
+fn language_from_info(info: &str) -> Option<String> {
+    let info = info.trim().trim_start_matches('{').trim_start();
+    let word = info.split([' ', '\t', ',', '}']).next()?.trim_start_matches('.');
+    (!word.is_empty()).then(|| word.to_string())
+}
+
+/// Split off blockquote markers (`> > `), returning (depth, rest).
+fn strip_blockquote(line: &str) -> (usize, &str) {
+    let mut depth = 0;
+    let mut rest = line;
+    loop {
+        let trimmed = rest.trim_start_matches(' ');
+        if rest.len() - trimmed.len() > 3 {
+            break;
+        }
+        let Some(after) = trimmed.strip_prefix('>') else {
+            break;
+        };
+        depth += 1;
+        rest = after.strip_prefix(' ').unwrap_or(after);
+    }
+    (depth, rest)
+}
+
+/// Remove up to `count` leading spaces.
+fn strip_indent(line: &str, count: usize) -> &str {
+    let n = leading_spaces(line).min(count);
+    &line[n..]
+}
+
+struct OpenBlock {
+    fence: super::FenceOpen,
+    indent: usize,
+    quote_depth: usize,
+    start_line: usize,
+    lines: Vec<String>,
+}
+
+impl OpenBlock {
+    fn finish(self, end_line: usize, closed: bool) -> CodeBlock {
+        let mut content = self.lines.join("\n");
+        if !self.lines.is_empty() {
+            content.push('\n');
+        }
+        CodeBlock {
+            language: language_from_info(&self.fence.info),
+            info: self.fence.info,
+            content,
+            start_line: self.start_line,
+            end_line,
+            fence_char: self.fence.ch,
+            closed,
+        }
+    }
+}
+
+/// Content column of a list item line (`- `, `* `, `+ `, `1. `, `1) `).
+fn list_content_indent(line: &str) -> Option<usize> {
+    let indent = leading_spaces(line);
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let marker_len = match rest[digits..].chars().next()? {
+        '-' | '*' | '+' if digits == 0 => 1,
+        '.' | ')' if (1..=9).contains(&digits) => digits + 1,
+        _ => return None,
+    };
+    let after = &rest[marker_len..];
+    if !after.is_empty() && !after.starts_with(' ') {
+        return None;
+    }
+    Some(indent + marker_len + leading_spaces(after).clamp(1, 4))
+}
+
+/// Extract all fenced code blocks from a document.
+pub fn extract(markdown: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut open: Option<OpenBlock> = None;
+    let mut last_line = 0;
+    let mut list_indent: Option<usize> = None;
+
+    for (index, raw) in markdown.lines().enumerate() {
+        let line_no = index + 1;
+        last_line = line_no;
+        let (quote_depth, line) = strip_blockquote(raw);
+
+        if let Some(block) = open.as_mut() {
+            let body = if block.quote_depth > 0 && quote_depth >= block.quote_depth {
+                line
+            } else {
+                raw
+            };
+            let indent = leading_spaces(body);
+            if indent <= block.indent + 3 && is_fence_close(&body[indent.min(body.len())..], &block.fence) {
+                if let Some(block) = open.take() {
+                    blocks.push(block.finish(line_no, true));
+                }
+            } else {
+                block.lines.push(strip_indent(body, block.indent).to_string());
+            }
+            continue;
+        }
+
+        // Fences may be indented further when nested in list items
+        let indent = leading_spaces(line);
+        if let Some(content) = list_content_indent(line) {
+            list_indent = Some(content);
+            continue;
+        }
+        if !line.trim().is_empty() && indent < list_indent.unwrap_or(0) {
+            list_indent = None;
+        }
+        if indent > list_indent.unwrap_or(0) + 3 {
+            continue;
+        }
+        if let Some(fence) = parse_fence_open(&line[indent..]) {
+            open = Some(OpenBlock {
+                fence,
+                indent,
+                quote_depth,
+                start_line: line_no,
+                lines: Vec::new(),
+            });
+        }
+    }
+
+    if let Some(block) = open {
+        blocks.push(block.finish(last_line, false));
+    }
+    blocks
+}
+
+/// Extract all fenced code blocks from a Markdown document.
+#[tauri::command]
+pub fn extract_code_blocks(markdown: String) -> Vec<CodeBlock> {
+    extract(&markdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_basic_block() {
+        let blocks = extract("text\n```rust\nfn main() {}\n```\nafter");
+        assert_eq!(blocks.len(), 1);
+        let b = &blocks[0];
+        assert_eq!(b.language.as_deref(), Some("rust"));
+        assert_eq!(b.content, "fn main() {}\n");
+        assert_eq!((b.start_line, b.end_line), (2, 4));
+        assert_eq!(b.fence_char, '`');
+        assert!(b.closed);
+    }
+
+    #[test]
+    fn tilde_fence_with_attributes() {
+        let blocks = extract("~~~ {.python .numberLines}\nprint(1)\n~~~\n");
+        assert_eq!(blocks[0].language.as_deref(), Some("python"));
+        assert_eq!(blocks[0].fence_char, '~');
+        assert_eq!(blocks[0].info, "{.python .numberLines}");
+    }
+
+    #[test]
+    fn info_string_with_comma_options() {
+        assert_eq!(language_from_info("rust,ignore"), Some("rust".to_string()));
+        assert_eq!(language_from_info("js title=\"a.js\""), Some("js".to_string()));
+        assert_eq!(language_from_info(""), None);
+    }
+
+    #[test]
+    fn nested_shorter_fence_is_content() {
+        let md = "````md\n```js\nx\n```\n````\n";
+        let blocks = extract(md);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].content, "```js\nx\n```\n");
+    }
+
+    #[test]
+    fn indented_fence_in_list_item() {
+        let md = "- item\n\n    ```sh\n    echo hi\n      indented\n    ```\n";
+        let blocks = extract(md);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].content, "echo hi\n  indented\n");
+        assert_eq!(blocks[0].language.as_deref(), Some("sh"));
+    }
+
+    #[test]
+    fn deeply_indented_fence_is_indented_code() {
+        assert!(extract("text\n\n    ```\n    code\n    ```\n").is_empty());
+        assert!(extract("- item\n\nafter\n\n    ```\n    x\n    ```\n").is_empty());
+        assert_eq!(extract("   ```\nx\n   ```\n").len(), 1);
+        assert_eq!(extract("1. item\n\n     ```\n     x\n     ```\n").len(), 1);
+    }
+
+    #[test]
+    fn fence_in_blockquote() {
+        let blocks = extract("> ```\n> quoted\n> ```\n");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].content, "quoted\n");
+    }
+
+    #[test]
+    fn unterminated_fence_runs_to_eof() {
+        let blocks = extract("```\na\nb");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].content, "a\nb\n");
+        assert_eq!(blocks[0].end_line, 3);
+        assert!(!blocks[0].closed);
+    }
+
+    #[test]
+    fn empty_block_and_multiple_blocks() {
+        let blocks = extract("```\n```\n\n~~~~\nx\n~~~~");
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].content, "");
+        assert_eq!(blocks[1].content, "x\n");
+    }
+}
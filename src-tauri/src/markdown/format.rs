@@ -0,0 +1,463 @@
+//! Markdown normalization ("clean up" action).
+//!
+//! Applies targeted line-based transforms rather than re-rendering the whole
+//! document, so anything not explicitly normalized is left untouched:
+//! - ATX heading spacing, optional closing hashes, blank lines around headings
+//! - Bullet list marker style
+//! - Trailing whitespace (hard breaks are kept as two spaces)
+//! - Runs of blank lines collapsed, exactly one final newline
+//! - Optional paragraph wrapping (off by default)
+//!
+//! Frontmatter, fenced code blocks and indented code blocks are preserved
+//! verbatim. Formatting is idempotent.
+
+use serde::Deserialize;
+
+use super::{frontmatter_end, is_fence_close, leading_spaces, parse_fence_open, FenceOpen};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BulletMarker {
+    #[default]
+    Dash,
+    Asterisk,
+    Plus,
+}
+
+impl BulletMarker {
+    fn as_char(self) -> char {
+        match self {
+            BulletMarker::Dash => '-',
+            BulletMarker::Asterisk => '*',
+            BulletMarker::Plus => '+',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FormatOpts {
+    pub bullet_marker: BulletMarker,
+    /// Emit closing hashes on ATX headings (`## Title ##`)
+    pub atx_closing: bool,
+    /// Wrap paragraph lines at this width (None = no wrapping)
+    pub wrap_width: Option<usize>,
+}
+
+/// Parse an ATX heading into (level, text), without closing hashes.
+fn parse_atx_heading(line: &str) -> Option<(usize, &str)> {
+    let indent = leading_spaces(line);
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let level = rest.len() - rest.trim_start_matches('#').len();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let after = &rest[level..];
+    if !after.is_empty() && !after.starts_with([' ', '\t']) {
+        return None;
+    }
+    let text = after.trim();
+    // Closing sequence must be preceded by whitespace (or be the whole text)
+    let without_closing = text.trim_end_matches('#');
+    let text = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        without_closing.trim_end()
+    } else {
+        text
+    };
+    Some((level, text))
+}
+
+/// Line consisting of 3+ of the same `-`, `*` or `_` (spaces allowed).
+fn is_thematic_break(line: &str) -> bool {
+    let compact: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && matches!(compact[0], '-' | '*' | '_')
+        && compact.iter().all(|c| *c == compact[0])
+}
+
+/// If the line is a bullet list item, return the byte index of its marker.
+fn bullet_marker_index(line: &str) -> Option<usize> {
+    let indent = leading_spaces(line);
+    let rest = &line[indent..];
+    let mut chars = rest.chars();
+    let marker = chars.next()?;
+    if !matches!(marker, '-' | '*' | '+') {
+        return None;
+    }
+    match chars.next() {
+        None | Some(' ') | Some('\t') => {}
+        _ => return None,
+    }
+    if is_thematic_break(line) {
+        return None;
+    }
+    Some(indent)
+}
+
+/// Setext `-` underline: only dashes, indented at most 3 spaces.
+/// Directly under a paragraph line this makes a heading, not a list item.
+fn is_setext_dash_underline(line: &str) -> bool {
+    let trimmed = line.trim();
+    leading_spaces(line) <= 3 && !trimmed.is_empty() && trimmed.chars().all(|c| c == '-')
+}
+
+/// `1.` / `1)` style ordered list marker as a standalone word.
+fn is_ordered_marker(word: &str) -> bool {
+    let digits = word.trim_end_matches(['.', ')']);
+    word.len() == digits.len() + 1
+        && (1..=9).contains(&digits.len())
+        && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_ordered_item(line: &str) -> bool {
+    line.trim_start()
+        .split([' ', '\t'])
+        .next()
+        .is_some_and(|word| is_ordered_marker(word) && line.trim_start().len() > word.len())
+}
+
+/// A word that would change meaning if it started a line.
+fn starts_block(word: &str) -> bool {
+    matches!(word, "-" | "+" | "*")
+        || is_ordered_marker(word)
+        || word.starts_with(['>', '<', '|', '#'])
+        || word.starts_with("```")
+        || word.starts_with("~~~")
+        || word.chars().all(|c| c == '=' || c == '-')
+}
+
+/// Plain paragraph line that is safe to rewrap.
+fn is_wrappable(line: &str) -> bool {
+    !line.is_empty()
+        && !line.starts_with([' ', '\t', '>', '<', '|', '#'])
+        && !line.contains('|')
+        && bullet_marker_index(line).is_none()
+        && !is_ordered_item(line)
+        && !is_thematic_break(line)
+}
+
+/// Greedy wrap at `width`, never breaking before a block-starting word.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in line.split_whitespace() {
+        if current.is_empty() {
+            current.push_str(word);
+        } else if current.chars().count() + 1 + word.chars().count() > width && !starts_block(word) {
+            out.push(std::mem::take(&mut current));
+            current.push_str(word);
+        } else {
+            current.push(' ');
+            current.push_str(word);
+        }
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+/// Trim trailing whitespace, keeping a two-space hard break.
+fn trim_trailing(line: &str) -> String {
+    let trimmed = line.trim_end();
+    let trailing_spaces = line.len() - line.trim_end_matches(' ').len();
+    if !trimmed.is_empty() && trailing_spaces >= 2 && line.trim_end_matches(' ').len() == trimmed.len() {
+        format!("{}  ", trimmed)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+struct Output {
+    lines: Vec<String>,
+    /// Insert a blank line before the next non-blank line (after a heading)
+    blank_pending: bool,
+}
+
+impl Output {
+    fn last_is_blank(&self) -> bool {
+        self.lines.last().is_some_and(|l| l.trim().is_empty())
+    }
+
+    fn push(&mut self, line: String) {
+        if self.blank_pending && !self.lines.is_empty() && !self.last_is_blank() {
+            self.lines.push(String::new());
+        }
+        self.blank_pending = false;
+        self.lines.push(line);
+    }
+
+    /// Push a blank line, collapsing runs and skipping leading blanks.
+    fn push_blank(&mut self) {
+        if !self.lines.is_empty() && !self.last_is_blank() {
+            self.lines.push(String::new());
+        }
+    }
+}
+
+/// Normalize a Markdown document.
+pub fn format(content: &str, opts: &FormatOpts) -> String {
+    let crlf = content.contains("\r\n");
+    let body = content.strip_suffix('\n').unwrap_or(content);
+    let lines: Vec<&str> = if body.is_empty() && content.is_empty() {
+        Vec::new()
+    } else {
+        body.split('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).collect()
+    };
+
+    let mut out = Output {
+        lines: Vec::new(),
+        blank_pending: false,
+    };
+    let mut index = 0;
+
+    if let Some(end) = frontmatter_end(&lines) {
+        out.lines.extend(lines[..end].iter().map(|l| l.to_string()));
+        index = end;
+    }
+
+    let mut fence: Option<FenceOpen> = None;
+    let mut in_indented_code = false;
+    let mut in_list = false;
+    let mut after_paragraph = false;
+
+    for line in &lines[index..] {
+        let line = *line;
+        let follows_paragraph = std::mem::take(&mut after_paragraph);
+
+        // Fenced code: verbatim until the closing fence
+        if let Some(open) = &fence {
+            let closes = is_fence_close(line, open);
+            out.lines.push(line.to_string());
+            if closes {
+                fence = None;
+            }
+            continue;
+        }
+
+        // Indented code: verbatim (including blank lines) while indented
+        let indented = leading_spaces(line) >= 4 || line.starts_with('\t');
+        if in_indented_code {
+            if line.trim().is_empty() || indented {
+                out.lines.push(line.to_string());
+                continue;
+            }
+            in_indented_code = false;
+        } else if indented && !in_list && (out.lines.is_empty() || out.last_is_blank()) && !line.trim().is_empty() {
+            in_indented_code = true;
+            out.push(line.to_string());
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            out.push_blank();
+            continue;
+        }
+
+        if let Some(open) = parse_fence_open(line) {
+            fence = Some(open);
+            out.push(line.to_string());
+            continue;
+        }
+
+        if let Some((level, text)) = parse_atx_heading(line) {
+            let hashes = "#".repeat(level);
+            let mut heading = if text.is_empty() {
+                hashes.clone()
+            } else {
+                format!("{} {}", hashes, text)
+            };
+            if opts.atx_closing && !text.is_empty() {
+                heading.push(' ');
+                heading.push_str(&hashes);
+            }
+            if in_list && leading_spaces(line) > 0 {
+                // Heading inside a list item: keep its indentation and spacing
+                out.push(format!("{}{}", " ".repeat(leading_spaces(line)), heading));
+            } else {
+                in_list = false;
+                out.blank_pending = true;
+                out.push(heading);
+                out.blank_pending = true;
+            }
+            continue;
+        }
+
+        let line = trim_trailing(line);
+
+        if follows_paragraph && is_setext_dash_underline(&line) {
+            out.push(line);
+            continue;
+        }
+
+        if let Some(marker_at) = bullet_marker_index(&line) {
+            in_list = true;
+            let mut normalized = line.clone();
+            normalized.replace_range(marker_at..marker_at + 1, &opts.bullet_marker.as_char().to_string());
+            out.push(normalized);
+            continue;
+        }
+
+        if is_ordered_item(&line) {
+            in_list = true;
+            out.push(line);
+            continue;
+        }
+
+        if !line.starts_with([' ', '\t']) && !out.last_is_blank() && in_list {
+            // Lazy continuation of a list item
+            out.push(line);
+            continue;
+        }
+        if !line.starts_with([' ', '\t']) {
+            in_list = false;
+        }
+        after_paragraph = !in_list;
+
+        match opts.wrap_width {
+            Some(width) if width > 0 && is_wrappable(&line) && line.chars().count() > width => {
+                let hard_break = line.ends_with("  ");
+                let mut segments = wrap_line(&line, width);
+                if hard_break {
+                    if let Some(last) = segments.last_mut() {
+                        last.push_str("  ");
+                    }
+                }
+                for segment in segments {
+                    out.push(segment);
+                }
+            }
+            _ => out.push(line),
+        }
+    }
+
+    // Drop trailing blank lines (unless inside an unterminated fence)
+    if fence.is_none() && !in_indented_code {
+        while out.last_is_blank() {
+            out.lines.pop();
+        }
+    }
+
+    if out.lines.is_empty() {
+        return String::new();
+    }
+    let newline = if crlf { "\r\n" } else { "\n" };
+    let mut result = out.lines.join(newline);
+    result.push_str(newline);
+    result
+}
+
+/// Normalize and repair a Markdown document.
+#[tauri::command]
+pub fn format_markdown(content: String, opts: Option<FormatOpts>) -> String {
+    format(&content, &opts.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(s: &str) -> String {
+        format(s, &FormatOpts::default())
+    }
+
+    const SAMPLES: &[&str] = &[
+        "# Title\nSome text   \n\n\n\n* a\n* b\n  + nested\n## Sub ##\ntext",
+        "---\ntitle:  x   \n---\n\n\n#   Heading\n```rust\nfn main() {}   \n\n\n```\n",
+        "Para with hard break  \nnext line\n\n    indented code   \n\n\n    more\nafter\n",
+        "1. one\n2. two\n\n***\n- - -\n\ntext\n~~~\nunterminated\n\n",
+        "",
+        "\n\n\n",
+        "#hashtag is not a heading\n# \n#\n",
+    ];
+
+    #[test]
+    fn idempotent_on_samples() {
+        for sample in SAMPLES {
+            let once = fmt(sample);
+            assert_eq!(fmt(&once), once, "not idempotent for {:?}", sample);
+        }
+    }
+
+    #[test]
+    fn idempotent_with_options() {
+        let opts = FormatOpts {
+            bullet_marker: BulletMarker::Asterisk,
+            atx_closing: true,
+            wrap_width: Some(20),
+        };
+        let long = "This is a rather long paragraph line - with a dash and 1. a number that should wrap nicely\n# Head\n";
+        for sample in SAMPLES.iter().chain(std::iter::once(&long)) {
+            let once = format(sample, &opts);
+            assert_eq!(format(&once, &opts), once, "not idempotent for {:?}", sample);
+        }
+    }
+
+    #[test]
+    fn normalizes_headings_and_spacing() {
+        assert_eq!(fmt("text\n#   Title  ##\nmore"), "text\n\n# Title\n\nmore\n");
+        let opts = FormatOpts {
+            atx_closing: true,
+            ..Default::default()
+        };
+        assert_eq!(format("## Sub", &opts), "## Sub ##\n");
+    }
+
+    #[test]
+    fn hashtag_is_not_heading() {
+        assert_eq!(fmt("#tag"), "#tag\n");
+    }
+
+    #[test]
+    fn normalizes_bullets_but_not_thematic_breaks() {
+        assert_eq!(fmt("* a\n+ b\n\n* * *\n"), "- a\n- b\n\n* * *\n");
+    }
+
+    #[test]
+    fn setext_underline_is_not_a_bullet() {
+        let opts = FormatOpts {
+            bullet_marker: BulletMarker::Asterisk,
+            ..Default::default()
+        };
+        assert_eq!(format("Title\n-\n\ntext", &opts), "Title\n-\n\ntext\n");
+        assert_eq!(format("Title\n---", &opts), "Title\n---\n");
+        // After a blank line or a list item it is still an (empty) item
+        assert_eq!(format("Title\n\n-", &opts), "Title\n\n*\n");
+        assert_eq!(format("- a\n-", &opts), "* a\n*\n");
+    }
+
+    #[test]
+    fn trims_trailing_whitespace_keeps_hard_breaks() {
+        assert_eq!(fmt("a   \nb \nc"), "a  \nb\nc\n");
+    }
+
+    #[test]
+    fn collapses_blank_lines_and_ensures_final_newline() {
+        assert_eq!(fmt("\n\na\n\n\n\nb"), "a\n\nb\n");
+        assert_eq!(fmt("a\n\n\n"), "a\n");
+    }
+
+    #[test]
+    fn preserves_code_and_frontmatter_verbatim() {
+        let input = "---\nkey:   value   \n---\n```\n*  x   \n\n\n#  y\n```\n";
+        assert_eq!(fmt(input), input);
+    }
+
+    #[test]
+    fn preserves_crlf() {
+        assert_eq!(fmt("* a \r\n* b\r\n"), "- a\r\n- b\r\n");
+    }
+
+    #[test]
+    fn wraps_without_creating_blocks() {
+        let opts = FormatOpts {
+            wrap_width: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(format("aaaa bbbb - cccc", &opts), "aaaa bbbb -\ncccc\n");
+        assert_eq!(format("| a | b |", &opts), "| a | b |\n");
+    }
+}
@@ -0,0 +1,106 @@
+//! Markdown Module
+//!
+//! Pure, line-based Markdown utilities used by editor commands.
+//! Nothing here touches the filesystem or Tauri state.
+
+pub mod code_blocks;
+pub mod format;
+
+/// An opening code fence (``` or ~~~), as defined by CommonMark.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FenceOpen {
+    pub ch: char,
+    pub len: usize,
+    /// Info string after the fence, trimmed
+    pub info: String,
+}
+
+/// Count leading spaces (tabs are treated as not indentation-compatible).
+pub(crate) fn leading_spaces(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Parse an opening code fence line.
+pub(crate) fn parse_fence_open(line: &str) -> Option<FenceOpen> {
+    let indent = leading_spaces(line);
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let ch = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = rest.len() - rest.trim_start_matches(ch).len();
+    if len < 3 {
+        return None;
+    }
+    let info = rest[len..].trim();
+    // Backtick fences cannot have backticks in the info string
+    if ch == '`' && info.contains('`') {
+        return None;
+    }
+    Some(FenceOpen {
+        ch,
+        len,
+        info: info.to_string(),
+    })
+}
+
+/// Check whether a line closes the given fence.
+pub(crate) fn is_fence_close(line: &str, open: &FenceOpen) -> bool {
+    let indent = leading_spaces(line);
+    if indent > 3 {
+        return false;
+    }
+    let rest = &line[indent..];
+    let len = rest.len() - rest.trim_start_matches(open.ch).len();
+    len >= open.len && rest[len..].trim().is_empty()
+}
+
+/// Find the end (exclusive line index) of a frontmatter block starting at line 0.
+/// Supports YAML (`---`) and TOML (`+++`) frontmatter. Returns None if the
+/// document does not start with a closed frontmatter block.
+pub(crate) fn frontmatter_end(lines: &[&str]) -> Option<usize> {
+    let first = lines.first()?.trim_end();
+    let closers: &[&str] = match first {
+        "---" => &["---", "..."],
+        "+++" => &["+++"],
+        _ => return None,
+    };
+    lines
+        .iter()
+        .skip(1)
+        .position(|line| closers.contains(&line.trim_end()))
+        .map(|pos| pos + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fence_open_variants() {
+        let open = parse_fence_open("```rust title=\"x\"").unwrap();
+        assert_eq!((open.ch, open.len, open.info.as_str()), ('`', 3, "rust title=\"x\""));
+        assert_eq!(parse_fence_open("  ~~~~").unwrap().len, 4);
+        assert!(parse_fence_open("``").is_none());
+        assert!(parse_fence_open("    ```").is_none());
+        assert!(parse_fence_open("``` a`b").is_none());
+    }
+
+    #[test]
+    fn fence_close_requires_same_char_and_length() {
+        let open = parse_fence_open("````").unwrap();
+        assert!(is_fence_close("````", &open));
+        assert!(is_fence_close("`````  ", &open));
+        assert!(!is_fence_close("```", &open));
+        assert!(!is_fence_close("~~~~", &open));
+        assert!(!is_fence_close("```` x", &open));
+    }
+
+    #[test]
+    fn frontmatter_detection() {
+        assert_eq!(frontmatter_end(&["---", "a: 1", "---", "body"]), Some(3));
+        assert_eq!(frontmatter_end(&["+++", "a = 1", "+++"]), Some(3));
+        assert_eq!(frontmatter_end(&["---", "unterminated"]), None);
+        assert_eq!(frontmatter_end(&["# Title"]), None);
+    }
+}
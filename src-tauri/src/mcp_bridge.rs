@@ -4,25 +4,145 @@
 //! Access model:
 //! - Read operations: All clients can execute simultaneously
 //! - Write operations: Serialized via write lock, released after each write
+//! - Request types outside `allowedRequestTypes`, or writes while the
+//!   configured workspace is untrusted, are answered with `FORBIDDEN`
 //!
 //! Port discovery:
 //! - Server binds to port 0 (OS assigns available port)
 //! - Actual port written to Tauri's app data directory (platform-specific)
 //! - MCP sidecar reads app data path from ~/.vmark/app-data-path bootstrap file
+//!
+//! Authentication:
+//! - A random token is generated per start and written next to the port file
+//! - The first message on a connection must be `{"type":"auth","token":"..."}`
+//!
+//! TLS (optional): with `tls` enabled the bridge serves `wss://`, using the
+//! configured certificate or a self-signed one kept in the app data directory.
+//! The served certificate is published next to the port file so the bundled
+//! sidecar connects with `wss://` and trusts it.
 
 use crate::app_paths;
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tauri::AppHandle;
 use tauri::Emitter;
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
-use tokio_tungstenite::{accept_async, tungstenite::Message};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot, Mutex, Notify, RwLock};
+use tokio_rustls::rustls;
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::Error as WsError;
+use tokio_tungstenite::{accept_async_with_config, tungstenite::Message, WebSocketStream};
+
+/// How long a new connection has to send its auth message.
+const AUTH_TIMEOUT_SECS: u64 = 5;
+
+/// Default time to wait for the frontend to answer a request.
+const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 10_000;
+
+/// Upper bound for any request timeout, including per-call overrides.
+const MAX_REQUEST_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+
+/// Longest a queued connection waits for a free slot.
+const QUEUE_WAIT_SECS: u64 = 120;
+
+/// Default cap on a single incoming message (and frame).
+const DEFAULT_MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Interval between keepalive pings.
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Unanswered pings after which a connection is considered dead.
+const MAX_MISSED_PONGS: u32 = 2;
+
+/// Upper bounds (ms) of the request latency histogram buckets. Slower
+/// requests land in one extra open-ended bucket.
+const LATENCY_BUCKETS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+/// How long `stop_bridge` waits for the server loop to exit and release its socket.
+const SERVER_STOP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Options for starting the bridge (sent by the frontend as camelCase JSON).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BridgeConfig {
+    /// Maximum simultaneous clients. `None` means unlimited.
+    pub max_clients: Option<usize>,
+    /// Connections held waiting for a slot when the bridge is full (default 0).
+    pub max_queued: Option<usize>,
+    /// Address to listen on (default `127.0.0.1`).
+    pub bind_host: Option<String>,
+    /// Require the token handshake (default `true`).
+    pub require_auth: Option<bool>,
+    /// Default frontend response timeout in milliseconds.
+    pub request_timeout_ms: Option<u64>,
+    /// Timeouts in milliseconds for specific request types (e.g. `document.getContent`).
+    pub request_timeouts: HashMap<String, u64>,
+    /// Largest message a client may send, in bytes (default 16 MiB).
+    pub max_message_bytes: Option<usize>,
+    /// Serve `wss://` instead of `ws://`.
+    pub tls: bool,
+    /// PEM certificate chain for TLS. When neither this nor `tls_key_path`
+    /// is set, a self-signed certificate is generated into app data.
+    pub tls_cert_path: Option<String>,
+    /// PEM private key for TLS.
+    pub tls_key_path: Option<String>,
+    /// Request types clients may send. `None` allows every type.
+    pub allowed_request_types: Option<Vec<String>>,
+    /// Workspace whose trust level gates requests: while it is untrusted,
+    /// only read-only request types are allowed.
+    pub workspace_root: Option<String>,
+}
+
+impl BridgeConfig {
+    fn auth_enabled(&self) -> bool {
+        self.require_auth.unwrap_or(true)
+    }
+
+    /// WebSocket limits so an oversized message fails instead of being buffered.
+    fn ws_config(&self) -> WebSocketConfig {
+        let max = self
+            .max_message_bytes
+            .unwrap_or(DEFAULT_MAX_MESSAGE_BYTES)
+            .max(1);
+        WebSocketConfig {
+            max_message_size: Some(max),
+            max_frame_size: Some(max),
+            ..Default::default()
+        }
+    }
+
+    /// Timeout for a request: the per-call override, then the per-type
+    /// setting, then the default.
+    fn request_timeout(&self, request_type: &str, override_ms: Option<u64>) -> Duration {
+        let ms = override_ms
+            .or_else(|| self.request_timeouts.get(request_type).copied())
+            .or(self.request_timeout_ms)
+            .unwrap_or(DEFAULT_REQUEST_TIMEOUT_MS);
+        Duration::from_millis(ms.clamp(1, MAX_REQUEST_TIMEOUT_MS))
+    }
+
+    /// Whether clients may send `request_type`, given the workspace trust.
+    fn is_request_allowed(&self, request_type: &str, workspace_trusted: bool) -> bool {
+        if !workspace_trusted && !is_read_only_operation(request_type) {
+            return false;
+        }
+        match &self.allowed_request_types {
+            Some(types) => types.iter().any(|t| t == request_type),
+            None => true,
+        }
+    }
+}
 
 /// Message format for WebSocket communication with the sidecar.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -38,6 +158,8 @@ pub struct WsMessage {
 pub struct McpRequest {
     pub request_type: String,
     pub args: serde_json::Value,
+    /// Optional `timeoutMs` override (not forwarded to the frontend).
+    pub timeout_ms: Option<u64>,
 }
 
 impl McpRequest {
@@ -50,9 +172,11 @@ impl McpRequest {
             .ok_or("Request must have a 'type' field")?
             .to_string();
 
+        let timeout_ms = obj.get("timeoutMs").and_then(|v| v.as_u64());
+
         let mut args = serde_json::Map::new();
         for (key, val) in obj.iter() {
-            if key != "type" {
+            if key != "type" && key != "timeoutMs" {
                 args.insert(key.clone(), val.clone());
             }
         }
@@ -60,6 +184,7 @@ impl McpRequest {
         Ok(McpRequest {
             request_type,
             args: serde_json::Value::Object(args),
+            timeout_ms,
         })
     }
 }
@@ -72,6 +197,28 @@ pub struct McpResponse {
     pub data: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Machine-readable error code (e.g. `TIMEOUT`), read by the sidecar as `code`
+    #[serde(rename = "code", skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+}
+
+/// Error codes set by the bridge itself. The frontend may send others
+/// (e.g. `NOT_FOUND`, `UNAUTHORIZED`) which are passed through as-is.
+pub const ERROR_CODE_TIMEOUT: &str = "TIMEOUT";
+pub const ERROR_CODE_BRIDGE_STOPPED: &str = "BRIDGE_STOPPED";
+pub const ERROR_CODE_CHANNEL_CLOSED: &str = "CHANNEL_CLOSED";
+pub const ERROR_CODE_FORBIDDEN: &str = "FORBIDDEN";
+
+impl McpResponse {
+    /// Failed response with a human-readable message and an error code.
+    pub fn error(message: impl Into<String>, code: &str) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(message.into()),
+            error_code: Some(code.to_string()),
+        }
+    }
 }
 
 /// Event payload sent to frontend.
@@ -93,6 +240,100 @@ pub struct McpResponsePayload {
     pub success: bool,
     pub data: Option<serde_json::Value>,
     pub error: Option<String>,
+    #[serde(rename = "code", default)]
+    pub error_code: Option<String>,
+}
+
+/// Bridge status for the frontend's connection indicator.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpBridgeStatus {
+    /// Whether at least one sidecar is connected.
+    pub connected: bool,
+    /// Port the bridge listens on, while running.
+    pub port: Option<u16>,
+    /// Requests waiting for a frontend response.
+    pub pending_requests: usize,
+}
+
+/// Request counters and latencies since the bridge started.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpBridgeMetrics {
+    /// Requests forwarded to the frontend.
+    pub total_requests: u64,
+    pub requests_by_type: HashMap<String, u64>,
+    /// Mean latency of answered requests, if any.
+    pub average_latency_ms: Option<f64>,
+    /// 95th percentile latency, to histogram bucket precision.
+    pub p95_latency_ms: Option<u64>,
+    /// Requests the frontend didn't answer in time.
+    pub timeouts: u64,
+}
+
+/// Counters behind `McpBridgeMetrics`.
+#[derive(Default)]
+struct RequestMetrics {
+    total: u64,
+    by_type: HashMap<String, u64>,
+    timeouts: u64,
+    /// Answered requests per `LATENCY_BUCKETS_MS` bucket.
+    latency_buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    latency_sum_ms: u64,
+    latency_max_ms: u64,
+}
+
+impl RequestMetrics {
+    fn record_request(&mut self, request_type: &str) {
+        self.total += 1;
+        *self.by_type.entry(request_type.to_string()).or_default() += 1;
+    }
+
+    fn record_latency(&mut self, latency: Duration) {
+        let ms = u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&bound| ms <= bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.latency_buckets[bucket] += 1;
+        self.latency_sum_ms = self.latency_sum_ms.saturating_add(ms);
+        self.latency_max_ms = self.latency_max_ms.max(ms);
+    }
+
+    fn record_timeout(&mut self) {
+        self.timeouts += 1;
+    }
+
+    /// Upper bound of the bucket holding the 95th percentile, capped at the
+    /// slowest latency seen.
+    fn p95_latency_ms(&self) -> Option<u64> {
+        let answered: u64 = self.latency_buckets.iter().sum();
+        if answered == 0 {
+            return None;
+        }
+        let rank = (answered * 95).div_ceil(100);
+        let mut seen = 0;
+        for (i, count) in self.latency_buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = LATENCY_BUCKETS_MS.get(i).copied().unwrap_or(u64::MAX);
+                return Some(bound.min(self.latency_max_ms));
+            }
+        }
+        None
+    }
+
+    fn snapshot(&self) -> McpBridgeMetrics {
+        let answered: u64 = self.latency_buckets.iter().sum();
+        McpBridgeMetrics {
+            total_requests: self.total,
+            requests_by_type: self.by_type.clone(),
+            average_latency_ms: (answered > 0)
+                .then(|| self.latency_sum_ms as f64 / answered as f64),
+            p95_latency_ms: self.p95_latency_ms(),
+            timeouts: self.timeouts,
+        }
+    }
 }
 
 /// Client identity information sent during handshake.
@@ -142,6 +383,31 @@ struct ClientConnection {
     identity: Option<ClientIdentity>,
 }
 
+/// Keepalive bookkeeping shared by a connection's reader and send task.
+#[derive(Default)]
+struct Keepalive {
+    /// Pings sent since the client was last heard from.
+    missed: AtomicU32,
+    /// Set while a request is handled; the reader isn't polling for pongs then.
+    busy: AtomicBool,
+}
+
+impl Keepalive {
+    /// Record a ping tick. Returns false once too many pings went unanswered.
+    fn on_ping_tick(&self) -> bool {
+        if self.busy.load(Ordering::SeqCst) {
+            self.missed.store(0, Ordering::SeqCst);
+            return true;
+        }
+        self.missed.fetch_add(1, Ordering::SeqCst) < MAX_MISSED_PONGS
+    }
+
+    /// Any frame from the client proves the connection is alive.
+    fn heard_from_client(&self) {
+        self.missed.store(0, Ordering::SeqCst);
+    }
+}
+
 /// Bridge state shared across connections.
 struct BridgeState {
     /// All connected clients (equal access for reads).
@@ -150,6 +416,22 @@ struct BridgeState {
     pending: HashMap<String, PendingRequest>,
     /// Counter for generating unique client IDs.
     next_client_id: u64,
+    /// Options the bridge was started with.
+    config: BridgeConfig,
+    /// Whether `config.workspace_root` is trusted (true when none is set).
+    workspace_trusted: bool,
+    /// Token clients must present, when auth is enabled.
+    token: Option<String>,
+    /// Whether the server is accepting clients (false once stopped).
+    running: bool,
+    /// Port the server listens on, while running.
+    port: Option<u16>,
+    /// Connections currently waiting for a free slot.
+    queued: usize,
+    /// Signalled whenever a client slot is released.
+    slot_freed: Arc<Notify>,
+    /// Request counters, reset on each start.
+    metrics: RequestMetrics,
 }
 
 /// Pending request with client ID for routing response.
@@ -166,6 +448,11 @@ static BRIDGE_STATE: std::sync::OnceLock<Arc<Mutex<BridgeState>>> = std::sync::O
 static SHUTDOWN_TX: std::sync::OnceLock<Arc<RwLock<Option<oneshot::Sender<()>>>>> =
     std::sync::OnceLock::new();
 
+/// Server loop task, awaited by `stop_bridge` so the listener is closed
+/// before the bridge can be started again.
+static SERVER_TASK: std::sync::OnceLock<Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>> =
+    std::sync::OnceLock::new();
+
 /// Write lock for serializing write operations.
 /// All clients can read simultaneously, but writes are serialized.
 static WRITE_LOCK: std::sync::OnceLock<Arc<tokio::sync::Mutex<()>>> = std::sync::OnceLock::new();
@@ -177,6 +464,14 @@ fn get_bridge_state() -> Arc<Mutex<BridgeState>> {
                 clients: HashMap::new(),
                 pending: HashMap::new(),
                 next_client_id: 1,
+                config: BridgeConfig::default(),
+                workspace_trusted: true,
+                token: None,
+                running: false,
+                port: None,
+                queued: 0,
+                slot_freed: Arc::new(Notify::new()),
+                metrics: RequestMetrics::default(),
             }))
         })
         .clone()
@@ -188,6 +483,12 @@ fn get_shutdown_holder() -> Arc<RwLock<Option<oneshot::Sender<()>>>> {
         .clone()
 }
 
+fn get_server_task() -> Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>> {
+    SERVER_TASK
+        .get_or_init(|| Arc::new(Mutex::new(None)))
+        .clone()
+}
+
 fn get_write_lock() -> Arc<tokio::sync::Mutex<()>> {
     WRITE_LOCK
         .get_or_init(|| Arc::new(tokio::sync::Mutex::new(())))
@@ -201,12 +502,8 @@ fn write_port_file(app: &AppHandle, port: u16) -> Result<(), String> {
 
     // Create app data directory if it doesn't exist
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| {
-            format!(
-                "Failed to create app data directory {:?}: {}",
-                parent, e
-            )
-        })?;
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory {:?}: {}", parent, e))?;
     }
 
     // Write port atomically to prevent partial reads
@@ -218,30 +515,61 @@ fn write_port_file(app: &AppHandle, port: u16) -> Result<(), String> {
     Ok(())
 }
 
-/// Remove the port file when bridge stops.
+/// Write the auth token for the sidecar, readable only by the current user.
+fn write_token_file(app: &AppHandle, token: &str) -> Result<(), String> {
+    write_private_file(&app_paths::get_token_file_path(app)?, token.as_bytes())
+}
+
+/// Atomically write a secret file, readable only by the current user.
+fn write_private_file(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory {:?}: {}", parent, e))?;
+    }
+
+    app_paths::atomic_write_file(path, contents)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict permissions on {:?}: {}", path, e))?;
+    }
+
+    Ok(())
+}
+
+/// Remove the port, token and TLS certificate files when bridge stops.
+fn remove_discovery_files(app: &AppHandle) {
+    remove_app_file(app_paths::get_port_file_path(app));
+    remove_app_file(app_paths::get_token_file_path(app));
+    remove_app_file(app_paths::get_tls_ca_path(app));
+}
+
+/// Remove a bridge file from the app data directory.
 /// Logs errors for non-NotFound failures (permission issues, etc.)
-fn remove_port_file(app: &AppHandle) {
-    match app_paths::get_port_file_path(app) {
+fn remove_app_file(path: Result<PathBuf, String>) {
+    match path {
         Ok(path) => {
             match fs::remove_file(&path) {
                 Ok(()) => {
                     #[cfg(debug_assertions)]
-                    eprintln!("[MCP Bridge] Port file removed: {:?}", path);
+                    eprintln!("[MCP Bridge] Removed {:?}", path);
                 }
                 Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
                     // Already removed - not an error
                 }
                 Err(e) => {
                     // Real error - log it
-                    eprintln!(
-                        "[MCP Bridge] Warning: Failed to remove port file {:?}: {}",
-                        path, e
-                    );
+                    eprintln!("[MCP Bridge] Warning: Failed to remove {:?}: {}", path, e);
                 }
             }
         }
         Err(e) => {
-            eprintln!("[MCP Bridge] Warning: Cannot determine port file path: {}", e);
+            eprintln!(
+                "[MCP Bridge] Warning: Cannot determine bridge file path: {}",
+                e
+            );
         }
     }
 }
@@ -286,15 +614,80 @@ fn is_read_only_operation(request_type: &str) -> bool {
     )
 }
 
-/// Start the MCP bridge WebSocket server.
-/// Returns the actual port the server is listening on.
-pub async fn start_bridge(app: AppHandle, _port: u16) -> Result<u16, String> {
-    // Always bind to port 0 to let OS assign an available port
-    // This eliminates port conflicts entirely
-    let addr = "127.0.0.1:0";
-    let listener = TcpListener::bind(addr)
+/// Key for a pending request. Sidecars number their own requests, so the
+/// client ID keeps two clients' ids from colliding.
+fn pending_key(client_id: u64, request_id: &str) -> String {
+    format!("{}:{}", client_id, request_id)
+}
+
+/// Whether another client would exceed the configured limit.
+fn at_capacity(connected: usize, max_clients: Option<usize>) -> bool {
+    max_clients.is_some_and(|max| connected >= max)
+}
+
+/// Parse the configured bind host, defaulting to loopback.
+///
+/// Non-loopback addresses expose the bridge to the network, so they are
+/// refused unless token authentication is enabled.
+fn resolve_bind_host(host: Option<&str>, require_auth: bool) -> Result<IpAddr, String> {
+    let ip = match host.map(str::trim).filter(|h| !h.is_empty()) {
+        Some(host) => host
+            .parse::<IpAddr>()
+            .map_err(|_| format!("Invalid bind address '{}': expected an IP address", host))?,
+        None => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    };
+    if !ip.is_loopback() && !require_auth {
+        return Err(format!(
+            "Refusing to bind the MCP bridge to {}: non-loopback addresses require token authentication",
+            ip
+        ));
+    }
+    Ok(ip)
+}
+
+/// Bind the listener on `port`, or on an OS-assigned port when `port` is 0
+/// or already taken, so a port collision never fails startup.
+async fn bind_listener(host: IpAddr, port: u16) -> Result<TcpListener, String> {
+    if port != 0 {
+        let addr = SocketAddr::new(host, port);
+        match TcpListener::bind(addr).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                #[cfg(debug_assertions)]
+                eprintln!("[MCP Bridge] Port {} is in use, picking a free port", port);
+            }
+            Err(e) => return Err(format!("Failed to bind to {}: {}", addr, e)),
+        }
+    }
+
+    let addr = SocketAddr::new(host, 0);
+    TcpListener::bind(addr)
         .await
-        .map_err(|e| format!("Failed to bind to {}: {}", addr, e))?;
+        .map_err(|e| format!("Failed to bind to {}: {}", addr, e))
+}
+
+/// Start the MCP bridge WebSocket server on `port` (0 = any free port).
+/// Returns the actual port the server is listening on; the sidecar finds it
+/// through the port file.
+pub async fn start_bridge(app: AppHandle, port: u16, config: BridgeConfig) -> Result<u16, String> {
+    let require_auth = config.auth_enabled();
+    let host = resolve_bind_host(config.bind_host.as_deref(), require_auth)?;
+    let served_pem = if config.tls {
+        Some(tls_pem(&app, &config, host)?)
+    } else {
+        None
+    };
+    let tls = served_pem
+        .as_ref()
+        .map(|(cert_pem, key_pem)| tls_acceptor_from_pem(cert_pem, key_pem))
+        .transpose()?;
+
+    let workspace_trusted = match config.workspace_root.as_deref() {
+        Some(root) => crate::workspace::is_workspace_trusted(root)?,
+        None => true,
+    };
+
+    let listener = bind_listener(host, port).await?;
 
     // Get the actual port assigned by the OS
     let actual_port = listener
@@ -302,15 +695,46 @@ pub async fn start_bridge(app: AppHandle, _port: u16) -> Result<u16, String> {
         .map_err(|e| format!("Failed to get local address: {}", e))?
         .port();
 
+    // Write the token before the port, so a sidecar that sees the port can authenticate
+    let token = if require_auth {
+        let token = generate_token();
+        write_token_file(&app, &token)?;
+        Some(token)
+    } else {
+        remove_app_file(app_paths::get_token_file_path(&app));
+        None
+    };
+
+    // Likewise the certificate, so the sidecar knows to use wss:// and trust it
+    match &served_pem {
+        Some((cert_pem, _)) => {
+            app_paths::atomic_write_file(&app_paths::get_tls_ca_path(&app)?, cert_pem)?
+        }
+        None => remove_app_file(app_paths::get_tls_ca_path(&app)),
+    }
+
     // Write port to file for MCP sidecar discovery
     write_port_file(&app, actual_port)?;
 
     #[cfg(debug_assertions)]
     eprintln!(
-        "[MCP Bridge] WebSocket server listening on 127.0.0.1:{}",
+        "[MCP Bridge] WebSocket server listening on {}://{}:{}",
+        if tls.is_some() { "wss" } else { "ws" },
+        host,
         actual_port
     );
 
+    {
+        let state = get_bridge_state();
+        let mut guard = state.lock().await;
+        guard.config = config;
+        guard.workspace_trusted = workspace_trusted;
+        guard.token = token;
+        guard.running = true;
+        guard.port = Some(actual_port);
+        guard.metrics = RequestMetrics::default();
+    }
+
     let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
     {
         let holder = get_shutdown_holder();
@@ -320,7 +744,7 @@ pub async fn start_bridge(app: AppHandle, _port: u16) -> Result<u16, String> {
 
     let app_handle = app.clone();
 
-    tauri::async_runtime::spawn(async move {
+    let server_task = tauri::async_runtime::spawn(async move {
         loop {
             tokio::select! {
                 _ = &mut shutdown_rx => {
@@ -332,7 +756,19 @@ pub async fn start_bridge(app: AppHandle, _port: u16) -> Result<u16, String> {
                     match result {
                         Ok((stream, addr)) => {
                             let app = app_handle.clone();
-                            tauri::async_runtime::spawn(handle_connection(stream, addr, app));
+                            let tls = tls.clone();
+                            tauri::async_runtime::spawn(async move {
+                                match tls {
+                                    Some(acceptor) => match acceptor.accept(stream).await {
+                                        Ok(tls_stream) => handle_connection(tls_stream, addr, app).await,
+                                        Err(_e) => {
+                                            #[cfg(debug_assertions)]
+                                            eprintln!("[MCP Bridge] TLS handshake failed for {}: {}", addr, _e);
+                                        }
+                                    },
+                                    None => handle_connection(stream, addr, app).await,
+                                }
+                            });
                         }
                         Err(_e) => {
                             #[cfg(debug_assertions)]
@@ -342,15 +778,130 @@ pub async fn start_bridge(app: AppHandle, _port: u16) -> Result<u16, String> {
                 }
             }
         }
+        // The listener is dropped here, releasing the port
     });
+    *get_server_task().lock().await = Some(server_task);
 
     Ok(actual_port)
 }
 
+/// Load the PEM certificate chain and key to serve: the configured files, or
+/// the self-signed certificate in app data.
+fn tls_pem(
+    app: &AppHandle,
+    config: &BridgeConfig,
+    host: IpAddr,
+) -> Result<(Vec<u8>, Vec<u8>), String> {
+    match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert), Some(key)) => Ok((
+            fs::read(cert)
+                .map_err(|e| format!("Failed to read TLS certificate {}: {}", cert, e))?,
+            fs::read(key).map_err(|e| format!("Failed to read TLS key {}: {}", key, e))?,
+        )),
+        (None, None) => self_signed_pem(app, host),
+        _ => Err(
+            "TLS needs both tlsCertPath and tlsKeyPath (or neither for a self-signed certificate)"
+                .to_string(),
+        ),
+    }
+}
+
+/// Load (or create on first use) the self-signed certificate in app data.
+/// Reusing it across restarts lets remote clients pin it once.
+fn self_signed_pem(app: &AppHandle, host: IpAddr) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let cert_path = app_paths::get_tls_cert_path(app)?;
+    let key_path = app_paths::get_tls_key_path(app)?;
+    if let (Ok(cert), Ok(key)) = (fs::read(&cert_path), fs::read(&key_path)) {
+        return Ok((cert, key));
+    }
+
+    // The local sidecar connects to 127.0.0.1 whatever the bind host
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+    if !names.contains(&host.to_string()) {
+        names.push(host.to_string());
+    }
+    let certified = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| format!("Failed to generate TLS certificate: {}", e))?;
+    let cert_pem = certified.cert.pem().into_bytes();
+    let key_pem = certified.key_pair.serialize_pem().into_bytes();
+
+    write_private_file(&key_path, &key_pem)?;
+    app_paths::atomic_write_file(&cert_path, &cert_pem)?;
+
+    #[cfg(debug_assertions)]
+    eprintln!(
+        "[MCP Bridge] Self-signed TLS certificate written to {:?}",
+        cert_path
+    );
+
+    Ok((cert_pem, key_pem))
+}
+
+/// Parse a PEM certificate chain and private key into a TLS acceptor.
+fn tls_acceptor_from_pem(cert_pem: &[u8], key_pem: &[u8]) -> Result<TlsAcceptor, String> {
+    let certs = rustls_pemfile::certs(&mut &cert_pem[..])
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid TLS certificate: {}", e))?;
+    if certs.is_empty() {
+        return Err("TLS certificate file contains no certificates".to_string());
+    }
+    let key = rustls_pemfile::private_key(&mut &key_pem[..])
+        .map_err(|e| format!("Invalid TLS key: {}", e))?
+        .ok_or("TLS key file contains no private key")?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let server_config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// Random per-start token for the auth handshake.
+fn generate_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Compare tokens without short-circuiting on the first differing byte.
+fn tokens_match(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Check a `{"type":"auth","token":"..."}` message against the expected token.
+fn is_valid_auth_message(text: &str, expected: &str) -> bool {
+    let Ok(msg) = serde_json::from_str::<serde_json::Value>(text) else {
+        return false;
+    };
+    msg.get("type").and_then(|v| v.as_str()) == Some("auth")
+        && msg
+            .get("token")
+            .and_then(|v| v.as_str())
+            .is_some_and(|token| tokens_match(token.as_bytes(), expected.as_bytes()))
+}
+
+/// Wait for the client's first message and check that it authenticates.
+async fn authenticate<S>(ws_stream: &mut WebSocketStream<S>, expected: &str) -> bool
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let first =
+        tokio::time::timeout(Duration::from_secs(AUTH_TIMEOUT_SECS), ws_stream.next()).await;
+    matches!(first, Ok(Some(Ok(Message::Text(ref text)))) if is_valid_auth_message(text, expected))
+}
+
 /// Stop the MCP bridge WebSocket server.
+///
+/// Returns once the server loop has exited and closed its listener (or after
+/// `SERVER_STOP_TIMEOUT`), so an immediate restart can't race the old socket.
 pub async fn stop_bridge(app: &AppHandle) {
-    // Remove port file so MCP sidecar knows bridge is stopped
-    remove_port_file(app);
+    // Remove discovery files so MCP sidecar knows bridge is stopped
+    remove_discovery_files(app);
 
     // Send shutdown signal to server loop
     let holder = get_shutdown_holder();
@@ -360,39 +911,181 @@ pub async fn stop_bridge(app: &AppHandle) {
     }
     drop(guard);
 
+    // Wait for the loop to exit and drop the listener
+    let server_task = get_server_task().lock().await.take();
+    if let Some(server_task) = server_task {
+        match tokio::time::timeout(SERVER_STOP_TIMEOUT, server_task).await {
+            Ok(Ok(())) => {}
+            Ok(Err(_e)) => {
+                #[cfg(debug_assertions)]
+                eprintln!("[MCP Bridge] Server task failed: {}", _e);
+            }
+            Err(_) => {
+                eprintln!(
+                    "[MCP Bridge] Server loop did not stop within {:?}",
+                    SERVER_STOP_TIMEOUT
+                );
+            }
+        }
+    }
+
     // Close all client connections
     let state = get_bridge_state();
     let mut guard = state.lock().await;
 
-    // Shutdown all clients
+    // Shutdown all clients; queued connections wake up and leave
+    guard.running = false;
+    guard.port = None;
+    guard.slot_freed.notify_waiters();
+    let had_clients = !guard.clients.is_empty();
     for (_, mut client) in guard.clients.drain() {
         if let Some(shutdown_tx) = client.shutdown.take() {
             let _ = shutdown_tx.send(());
         }
     }
+    if had_clients {
+        let _ = app.emit("mcp-bridge:disconnected", 0usize);
+    }
 
     // Reject all pending requests
     for (_, pending) in guard.pending.drain() {
-        let _ = pending.response_tx.send(McpResponse {
-            success: false,
-            data: None,
-            error: Some("Bridge stopped".to_string()),
-        });
+        let _ = pending.response_tx.send(McpResponse::error(
+            "Bridge stopped",
+            ERROR_CODE_BRIDGE_STOPPED,
+        ));
+    }
+}
+
+/// Apply a trust change to the running bridge, if it is gated on `root_path`.
+pub async fn set_workspace_trusted(root_path: &str, trusted: bool) {
+    let state = get_bridge_state();
+    let mut guard = state.lock().await;
+    if guard.config.workspace_root.as_deref() == Some(root_path) {
+        guard.workspace_trusted = trusted;
     }
 }
 
-/// Handle a single WebSocket connection.
-async fn handle_connection(stream: TcpStream, addr: SocketAddr, app: AppHandle) {
-    let ws_stream = match accept_async(stream).await {
+/// Register a client. When the bridge is full, wait in the queue (if there is
+/// room) until a slot frees up. Returns `None` if the client is turned away.
+async fn admit_client(
+    app: &AppHandle,
+    addr: SocketAddr,
+    tx: &mpsc::UnboundedSender<String>,
+    shutdown_tx: oneshot::Sender<()>,
+) -> Option<u64> {
+    let state = get_bridge_state();
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(QUEUE_WAIT_SECS);
+    let mut queued = false;
+
+    loop {
+        let mut guard = state.lock().await;
+        if !guard.running {
+            if queued {
+                guard.queued -= 1;
+            }
+            return None;
+        }
+
+        if !at_capacity(guard.clients.len(), guard.config.max_clients) {
+            if queued {
+                guard.queued -= 1;
+            }
+            let client_id = guard.next_client_id;
+            guard.next_client_id += 1;
+
+            let client = ClientConnection {
+                id: client_id,
+                addr,
+                tx: tx.clone(),
+                shutdown: Some(shutdown_tx),
+                connected_at: Instant::now(),
+                identity: None,
+            };
+
+            guard.clients.insert(client_id, client);
+            if guard.clients.len() == 1 {
+                let _ = app.emit("mcp-bridge:connected", 1usize);
+            }
+            return Some(client_id);
+        }
+
+        if !queued {
+            if guard.queued >= guard.config.max_queued.unwrap_or(0) {
+                return None;
+            }
+            guard.queued += 1;
+            queued = true;
+            #[cfg(debug_assertions)]
+            eprintln!("[MCP Bridge] {} queued for a client slot", addr);
+        }
+
+        // Create the waiter before unlocking so a release can't slip in between
+        let slot_freed = guard.slot_freed.clone();
+        let notified = slot_freed.notified();
+        drop(guard);
+
+        if tokio::time::timeout_at(deadline, notified).await.is_err() {
+            state.lock().await.queued -= 1;
+            return None;
+        }
+    }
+}
+
+/// Tell a client the bridge is full, then close with 1013 (Try Again Later)
+/// so it can back off instead of reconnecting immediately.
+async fn reject_busy<S>(mut ws_stream: WebSocketStream<S>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let busy = WsMessage {
+        id: "system".to_string(),
+        msg_type: "busy".to_string(),
+        payload: serde_json::json!({}),
+    };
+    if let Ok(text) = serde_json::to_string(&busy) {
+        let _ = ws_stream.send(Message::Text(text)).await;
+    }
+    let _ = ws_stream
+        .close(Some(CloseFrame {
+            code: CloseCode::Again,
+            reason: "Too many clients".into(),
+        }))
+        .await;
+}
+
+/// Handle a single WebSocket connection (plain TCP or TLS).
+async fn handle_connection<S>(stream: S, addr: SocketAddr, app: AppHandle)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let ws_config = get_bridge_state().lock().await.config.ws_config();
+    let mut ws_stream = match accept_async_with_config(stream, Some(ws_config)).await {
         Ok(ws) => ws,
         Err(_e) => {
             #[cfg(debug_assertions)]
-            eprintln!("[MCP Bridge] WebSocket handshake failed for {}: {}", addr, _e);
+            eprintln!(
+                "[MCP Bridge] WebSocket handshake failed for {}: {}",
+                addr, _e
+            );
             return;
         }
     };
 
-    let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+    // Authenticate before the client is registered
+    let expected_token = get_bridge_state().lock().await.token.clone();
+    if let Some(expected) = expected_token {
+        if !authenticate(&mut ws_stream, &expected).await {
+            #[cfg(debug_assertions)]
+            eprintln!("[MCP Bridge] Authentication failed for {}", addr);
+            let _ = ws_stream
+                .close(Some(CloseFrame {
+                    code: CloseCode::Policy,
+                    reason: "Authentication failed".into(),
+                }))
+                .await;
+            return;
+        }
+    }
 
     // Create channel for sending messages to this client
     let (tx, mut rx) = mpsc::unbounded_channel::<String>();
@@ -400,30 +1093,19 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, app: AppHandle)
     // Create shutdown channel for this connection
     let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
 
-    // Register client
-    let client_id = {
-        let state = get_bridge_state();
-        let mut guard = state.lock().await;
-
-        let client_id = guard.next_client_id;
-        guard.next_client_id += 1;
-
-        let client = ClientConnection {
-            id: client_id,
-            addr,
-            tx: tx.clone(),
-            shutdown: Some(shutdown_tx),
-            connected_at: Instant::now(),
-            identity: None,
-        };
-
-        guard.clients.insert(client_id, client);
-        client_id
+    // Register client, waiting in the queue while the bridge is full
+    let Some(client_id) = admit_client(&app, addr, &tx, shutdown_tx).await else {
+        #[cfg(debug_assertions)]
+        eprintln!("[MCP Bridge] Rejecting {}: client limit reached", addr);
+        reject_busy(ws_stream).await;
+        return;
     };
 
     #[cfg(debug_assertions)]
     eprintln!("[MCP Bridge] Client {} connected from {}", client_id, addr);
 
+    let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+
     // Send welcome notification to client
     let welcome_msg = WsMessage {
         id: "system".to_string(),
@@ -437,11 +1119,32 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, app: AppHandle)
         let _ = tx.send(msg_str);
     }
 
-    // Spawn task to forward messages from channel to WebSocket
-    let send_task = tauri::async_runtime::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if ws_sender.send(Message::Text(msg)).await.is_err() {
-                break;
+    // Spawn task to forward messages from channel to WebSocket and send keepalive pings.
+    // The task ends when the socket can't be written or the client stops answering.
+    let keepalive = Arc::new(Keepalive::default());
+    let send_keepalive = keepalive.clone();
+    let mut send_task = tauri::async_runtime::spawn(async move {
+        let mut ping_timer =
+            tokio::time::interval_at(tokio::time::Instant::now() + PING_INTERVAL, PING_INTERVAL);
+        ping_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                msg = rx.recv() => {
+                    let Some(msg) = msg else { break };
+                    if ws_sender.send(Message::Text(msg)).await.is_err() {
+                        break;
+                    }
+                }
+                _ = ping_timer.tick() => {
+                    if !send_keepalive.on_ping_tick() {
+                        #[cfg(debug_assertions)]
+                        eprintln!("[MCP Bridge] Client {} missed {} pings", client_id, MAX_MISSED_PONGS);
+                        break;
+                    }
+                    if ws_sender.send(Message::Ping(Vec::new())).await.is_err() {
+                        break;
+                    }
+                }
             }
         }
     });
@@ -454,19 +1157,36 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, app: AppHandle)
                 eprintln!("[MCP Bridge] Client {} closing due to shutdown", client_id);
                 break;
             }
+            _ = &mut send_task => {
+                #[cfg(debug_assertions)]
+                eprintln!("[MCP Bridge] Client {} connection lost", client_id);
+                break;
+            }
             result = ws_receiver.next() => {
+                if matches!(result, Some(Ok(_))) {
+                    keepalive.heard_from_client();
+                }
                 match result {
                     Some(Ok(Message::Text(text))) => {
+                        keepalive.busy.store(true, Ordering::SeqCst);
                         if let Err(_e) = handle_message(&text, client_id, &app).await {
                             #[cfg(debug_assertions)]
                             eprintln!("[MCP Bridge] Error handling message from client {}: {}", client_id, _e);
                         }
+                        keepalive.busy.store(false, Ordering::SeqCst);
                     }
                     Some(Ok(Message::Close(_))) => {
                         #[cfg(debug_assertions)]
                         eprintln!("[MCP Bridge] Client {} disconnected", client_id);
                         break;
                     }
+                    Some(Err(WsError::Capacity(e))) => {
+                        eprintln!(
+                            "[MCP Bridge] Closing client {}: message exceeds size limit ({})",
+                            client_id, e
+                        );
+                        break;
+                    }
                     Some(Err(_e)) => {
                         #[cfg(debug_assertions)]
                         eprintln!("[MCP Bridge] WebSocket error from client {}: {}", client_id, _e);
@@ -489,6 +1209,10 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, app: AppHandle)
         let mut guard = state.lock().await;
 
         if let Some(_client) = guard.clients.remove(&client_id) {
+            guard.slot_freed.notify_waiters();
+            if guard.clients.is_empty() {
+                let _ = app.emit("mcp-bridge:disconnected", 0usize);
+            }
             #[cfg(debug_assertions)]
             {
                 let name = _client
@@ -546,22 +1270,50 @@ async fn handle_message(text: &str, client_id: u64, app: &AppHandle) -> Result<(
 
     // Debug: Log request args to trace markdown escaping issues
     #[cfg(debug_assertions)]
-    if request.request_type.starts_with("document.insert") || request.request_type == "selection.replace" {
+    if request.request_type.starts_with("document.insert")
+        || request.request_type == "selection.replace"
+    {
         eprintln!("[MCP Bridge DEBUG] Request type: {}", request.request_type);
-        eprintln!("[MCP Bridge DEBUG] Args: {}", serde_json::to_string_pretty(&request.args).unwrap_or_default());
+        eprintln!(
+            "[MCP Bridge DEBUG] Args: {}",
+            serde_json::to_string_pretty(&request.args).unwrap_or_default()
+        );
     }
 
     let is_read = is_read_only_operation(&request.request_type);
 
-    // Get client's tx channel
-    let client_tx = {
+    // Get client's tx channel, the timeout for this request and whether it's allowed
+    let (client_tx, timeout, allowed) = {
         let state = get_bridge_state();
         let guard = state.lock().await;
-        guard.clients.get(&client_id).map(|c| c.tx.clone())
+        (
+            guard.clients.get(&client_id).map(|c| c.tx.clone()),
+            guard
+                .config
+                .request_timeout(&request.request_type, request.timeout_ms),
+            guard
+                .config
+                .is_request_allowed(&request.request_type, guard.workspace_trusted),
+        )
     };
 
     let client_tx = client_tx.ok_or("Client not found")?;
 
+    // Disallowed requests are answered here and never reach the frontend
+    if !allowed {
+        #[cfg(debug_assertions)]
+        eprintln!(
+            "[MCP Bridge] Client {} request {} is not allowed",
+            client_id, request.request_type
+        );
+        let error_response = McpResponse::error(
+            format!("Request type not allowed: {}", request.request_type),
+            ERROR_CODE_FORBIDDEN,
+        );
+        send_response(&client_tx, &msg.id, &error_response);
+        return Ok(());
+    }
+
     // For write operations, acquire the write lock
     // This serializes writes while allowing concurrent reads
     let write_lock = get_write_lock();
@@ -579,7 +1331,7 @@ async fn handle_message(text: &str, client_id: u64, app: &AppHandle) -> Result<(
     // Create a oneshot channel for the response
     let (response_tx, response_rx) = oneshot::channel();
 
-    let request_id = msg.id.clone();
+    let request_id = pending_key(client_id, &msg.id);
     #[cfg(debug_assertions)]
     let request_type_for_log = request.request_type.clone();
 
@@ -594,12 +1346,12 @@ async fn handle_message(text: &str, client_id: u64, app: &AppHandle) -> Result<(
                 client_id,
             },
         );
+        guard.metrics.record_request(&request.request_type);
     }
 
     // Emit event to frontend
     // Serialize args to JSON string to avoid Tauri IPC double-encoding
-    let args_json = serde_json::to_string(&request.args)
-        .unwrap_or_else(|_| "{}".to_string());
+    let args_json = serde_json::to_string(&request.args).unwrap_or_else(|_| "{}".to_string());
     let event = McpRequestEvent {
         id: request_id.clone(),
         request_type: request.request_type.clone(),
@@ -620,10 +1372,14 @@ async fn handle_message(text: &str, client_id: u64, app: &AppHandle) -> Result<(
         request.request_type, request_id
     );
 
-    // Wait for response with timeout (10 seconds - operations should be fast)
-    let response = match tokio::time::timeout(std::time::Duration::from_secs(10), response_rx).await
-    {
-        Ok(Ok(response)) => response,
+    // Wait for response with timeout
+    let started = Instant::now();
+    let response = match tokio::time::timeout(timeout, response_rx).await {
+        Ok(Ok(response)) => {
+            let state = get_bridge_state();
+            state.lock().await.metrics.record_latency(started.elapsed());
+            response
+        }
         Ok(Err(_)) => {
             // Channel closed - clean up and send error to sidecar
             let state = get_bridge_state();
@@ -631,19 +1387,9 @@ async fn handle_message(text: &str, client_id: u64, app: &AppHandle) -> Result<(
             guard.pending.remove(&request_id);
             drop(guard);
 
-            let error_response = McpResponse {
-                success: false,
-                data: None,
-                error: Some("Response channel closed".to_string()),
-            };
-            let ws_response = WsMessage {
-                id: msg.id.clone(),
-                msg_type: "response".to_string(),
-                payload: serde_json::to_value(&error_response).unwrap_or_default(),
-            };
-            if let Ok(json) = serde_json::to_string(&ws_response) {
-                let _ = client_tx.send(json);
-            }
+            let error_response =
+                McpResponse::error("Response channel closed", ERROR_CODE_CHANNEL_CLOSED);
+            send_response(&client_tx, &msg.id, &error_response);
             return Ok(());
         }
         Err(_) => {
@@ -651,27 +1397,22 @@ async fn handle_message(text: &str, client_id: u64, app: &AppHandle) -> Result<(
             let state = get_bridge_state();
             let mut guard = state.lock().await;
             guard.pending.remove(&request_id);
+            guard.metrics.record_timeout();
             drop(guard);
 
             #[cfg(debug_assertions)]
             eprintln!(
-                "[MCP Bridge] Client {} request {} timed out after 10s",
-                client_id, request_type_for_log
+                "[MCP Bridge] Client {} request {} timed out after {}ms",
+                client_id,
+                request_type_for_log,
+                timeout.as_millis()
             );
 
-            let error_response = McpResponse {
-                success: false,
-                data: None,
-                error: Some("Request timeout after 10s".to_string()),
-            };
-            let ws_response = WsMessage {
-                id: msg.id.clone(),
-                msg_type: "response".to_string(),
-                payload: serde_json::to_value(&error_response).unwrap_or_default(),
-            };
-            if let Ok(json) = serde_json::to_string(&ws_response) {
-                let _ = client_tx.send(json);
-            }
+            let error_response = McpResponse::error(
+                format!("Request timeout after {}ms", timeout.as_millis()),
+                ERROR_CODE_TIMEOUT,
+            );
+            send_response(&client_tx, &msg.id, &error_response);
             return Ok(());
         }
     };
@@ -703,6 +1444,18 @@ async fn handle_message(text: &str, client_id: u64, app: &AppHandle) -> Result<(
     Ok(())
 }
 
+/// Send a response for request `id` to a client, ignoring a closed channel.
+fn send_response(client_tx: &mpsc::UnboundedSender<String>, id: &str, response: &McpResponse) {
+    let ws_response = WsMessage {
+        id: id.to_string(),
+        msg_type: "response".to_string(),
+        payload: serde_json::to_value(response).unwrap_or_default(),
+    };
+    if let Ok(json) = serde_json::to_string(&ws_response) {
+        let _ = client_tx.send(json);
+    }
+}
+
 /// Tauri command to send a response from the frontend.
 #[tauri::command]
 pub async fn mcp_bridge_respond(payload: McpResponsePayload) -> Result<(), String> {
@@ -724,6 +1477,7 @@ pub async fn mcp_bridge_respond(payload: McpResponsePayload) -> Result<(), Strin
             success: payload.success,
             data: payload.data,
             error: payload.error,
+            error_code: payload.error_code,
         };
         pending
             .response_tx
@@ -734,12 +1488,57 @@ pub async fn mcp_bridge_respond(payload: McpResponsePayload) -> Result<(), Strin
     Ok(())
 }
 
-/// Check if the bridge has any connected clients.
-#[allow(dead_code)]
-pub async fn is_client_connected() -> bool {
+/// Build an unsolicited event message for sidecars.
+fn event_message(event_type: &str, payload: serde_json::Value) -> WsMessage {
+    WsMessage {
+        id: "system".to_string(),
+        msg_type: "event".to_string(),
+        payload: serde_json::json!({
+            "event": event_type,
+            "data": payload,
+        }),
+    }
+}
+
+/// Tauri command to push an event (e.g. active document changed) to all
+/// connected sidecars. No response is expected; a no-op without clients.
+#[tauri::command]
+pub async fn mcp_bridge_notify(
+    event_type: String,
+    payload: serde_json::Value,
+) -> Result<(), String> {
+    let message = serde_json::to_string(&event_message(&event_type, payload))
+        .map_err(|e| format!("Failed to serialize: {}", e))?;
+
     let state = get_bridge_state();
     let guard = state.lock().await;
-    !guard.clients.is_empty()
+    for client in guard.clients.values() {
+        // A closed channel means the client is disconnecting; cleanup handles it
+        let _ = client.tx.send(message.clone());
+    }
+
+    Ok(())
+}
+
+/// Tauri command to report whether a sidecar is attached.
+/// `mcp-bridge:connected` / `mcp-bridge:disconnected` events signal changes.
+#[tauri::command]
+pub async fn mcp_bridge_status() -> McpBridgeStatus {
+    let state = get_bridge_state();
+    let guard = state.lock().await;
+    McpBridgeStatus {
+        connected: !guard.clients.is_empty(),
+        port: guard.port,
+        pending_requests: guard.pending.len(),
+    }
+}
+
+/// Tauri command returning request counts and latencies since the bridge started.
+#[tauri::command]
+pub async fn mcp_bridge_metrics() -> McpBridgeMetrics {
+    let state = get_bridge_state();
+    let guard = state.lock().await;
+    guard.metrics.snapshot()
 }
 
 /// Get count of connected clients.
@@ -748,3 +1547,297 @@ pub async fn client_count() -> usize {
     let guard = state.lock().await;
     guard.clients.len()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_keys_are_per_client() {
+        assert_ne!(pending_key(1, "req-1"), pending_key(2, "req-1"));
+        assert_eq!(pending_key(3, "abc"), "3:abc");
+    }
+
+    #[test]
+    fn bind_host_defaults_to_loopback() {
+        assert_eq!(
+            resolve_bind_host(None, false),
+            Ok(IpAddr::V4(Ipv4Addr::LOCALHOST))
+        );
+        assert_eq!(
+            resolve_bind_host(Some(" "), false),
+            Ok(IpAddr::V4(Ipv4Addr::LOCALHOST))
+        );
+        assert_eq!(
+            resolve_bind_host(Some("::1"), false),
+            Ok("::1".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn bind_host_rejects_invalid_and_exposed_addresses() {
+        assert!(resolve_bind_host(Some("localhost"), true)
+            .unwrap_err()
+            .contains("Invalid bind address"));
+        assert!(resolve_bind_host(Some("0.0.0.0"), false)
+            .unwrap_err()
+            .contains("token authentication"));
+        assert!(resolve_bind_host(Some("192.168.1.10"), false).is_err());
+    }
+
+    #[test]
+    fn bind_host_allows_exposed_addresses_with_auth() {
+        assert_eq!(
+            resolve_bind_host(Some("0.0.0.0"), true),
+            Ok(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+        );
+    }
+
+    #[test]
+    fn auth_message_validation() {
+        let token = generate_token();
+        assert_eq!(token.len(), 64);
+        let auth = |t: &str| serde_json::json!({"type": "auth", "token": t}).to_string();
+
+        assert!(is_valid_auth_message(&auth(&token), &token));
+        assert!(!is_valid_auth_message(&auth("wrong"), &token));
+        assert!(!is_valid_auth_message(&auth(""), &token));
+        assert!(!is_valid_auth_message(r#"{"type":"auth"}"#, &token));
+        assert!(!is_valid_auth_message(
+            r#"{"type":"request","token":"x"}"#,
+            &token
+        ));
+        assert!(!is_valid_auth_message("not json", &token));
+    }
+
+    #[test]
+    fn request_timeout_precedence() {
+        let mut config = BridgeConfig::default();
+        assert_eq!(
+            config.request_timeout("tabs.list", None),
+            Duration::from_millis(DEFAULT_REQUEST_TIMEOUT_MS)
+        );
+
+        config.request_timeout_ms = Some(20_000);
+        config
+            .request_timeouts
+            .insert("document.getContent".to_string(), 60_000);
+        assert_eq!(
+            config.request_timeout("tabs.list", None),
+            Duration::from_secs(20)
+        );
+        assert_eq!(
+            config.request_timeout("document.getContent", None),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            config.request_timeout("document.getContent", Some(90_000)),
+            Duration::from_secs(90)
+        );
+        assert_eq!(
+            config.request_timeout("tabs.list", Some(u64::MAX)),
+            Duration::from_millis(MAX_REQUEST_TIMEOUT_MS)
+        );
+    }
+
+    #[test]
+    fn timeout_override_is_not_forwarded() {
+        let request = McpRequest::from_value(serde_json::json!({
+            "type": "document.getContent",
+            "timeoutMs": 45000,
+            "windowId": "main",
+        }))
+        .unwrap();
+        assert_eq!(request.timeout_ms, Some(45_000));
+        assert_eq!(request.args, serde_json::json!({"windowId": "main"}));
+    }
+
+    #[test]
+    fn keepalive_gives_up_after_missed_pongs() {
+        let keepalive = Keepalive::default();
+        assert!(keepalive.on_ping_tick());
+        assert!(keepalive.on_ping_tick());
+        assert!(!keepalive.on_ping_tick());
+
+        keepalive.heard_from_client();
+        assert!(keepalive.on_ping_tick());
+    }
+
+    #[test]
+    fn keepalive_waits_while_busy() {
+        let keepalive = Keepalive::default();
+        keepalive.busy.store(true, Ordering::SeqCst);
+        for _ in 0..5 {
+            assert!(keepalive.on_ping_tick());
+        }
+        keepalive.busy.store(false, Ordering::SeqCst);
+        assert!(keepalive.on_ping_tick());
+    }
+
+    #[tokio::test]
+    async fn bind_listener_falls_back_when_port_taken() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let taken = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let taken_port = taken.local_addr().unwrap().port();
+
+        let listener = bind_listener(localhost, taken_port).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert_ne!(port, 0);
+        assert_ne!(port, taken_port);
+
+        // A free requested port is honored
+        drop(listener);
+        let listener = bind_listener(localhost, port).await.unwrap();
+        assert_eq!(listener.local_addr().unwrap().port(), port);
+    }
+
+    #[tokio::test]
+    async fn oversized_message_closes_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let config = BridgeConfig {
+            max_message_bytes: Some(1024),
+            ..Default::default()
+        };
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async_with_config(stream, Some(config.ws_config()))
+                .await
+                .unwrap();
+            let small = ws.next().await;
+            let large = ws.next().await;
+            (small, large)
+        });
+
+        let (mut client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        client.send(Message::Text("x".repeat(100))).await.unwrap();
+        client.send(Message::Text("x".repeat(4096))).await.unwrap();
+
+        let (small, large) = server.await.unwrap();
+        assert!(matches!(small, Some(Ok(Message::Text(_)))));
+        assert!(matches!(large, Some(Err(WsError::Capacity(_)))));
+    }
+
+    #[tokio::test]
+    async fn busy_rejection_sends_status_then_closes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            reject_busy(tokio_tungstenite::accept_async(stream).await.unwrap()).await;
+        });
+
+        let (mut client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let Some(Ok(Message::Text(text))) = client.next().await else {
+            panic!("expected busy message");
+        };
+        let busy: WsMessage = serde_json::from_str(&text).unwrap();
+        assert_eq!(busy.msg_type, "busy");
+
+        let Some(Ok(Message::Close(Some(frame)))) = client.next().await else {
+            panic!("expected close frame");
+        };
+        assert_eq!(frame.code, CloseCode::Again);
+    }
+
+    #[test]
+    fn tls_acceptor_from_generated_cert() {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_pem = certified.cert.pem();
+        let key_pem = certified.key_pair.serialize_pem();
+        assert!(tls_acceptor_from_pem(cert_pem.as_bytes(), key_pem.as_bytes()).is_ok());
+
+        let no_cert = tls_acceptor_from_pem(b"", key_pem.as_bytes()).err();
+        assert!(no_cert.is_some_and(|e| e.contains("no certificates")));
+        let no_key = tls_acceptor_from_pem(cert_pem.as_bytes(), b"").err();
+        assert!(no_key.is_some_and(|e| e.contains("no private key")));
+    }
+
+    #[test]
+    fn request_metrics_snapshot() {
+        let mut metrics = RequestMetrics::default();
+        assert_eq!(metrics.snapshot().p95_latency_ms, None);
+        assert_eq!(metrics.snapshot().average_latency_ms, None);
+
+        for _ in 0..19 {
+            metrics.record_request("tabs.list");
+            metrics.record_latency(Duration::from_millis(8));
+        }
+        metrics.record_request("document.getContent");
+        metrics.record_latency(Duration::from_millis(3_000));
+        metrics.record_request("document.getContent");
+        metrics.record_timeout();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.total_requests, 21);
+        assert_eq!(snapshot.requests_by_type["tabs.list"], 19);
+        assert_eq!(snapshot.requests_by_type["document.getContent"], 2);
+        assert_eq!(snapshot.timeouts, 1);
+        assert_eq!(snapshot.p95_latency_ms, Some(10));
+        assert_eq!(
+            snapshot.average_latency_ms,
+            Some((19.0 * 8.0 + 3_000.0) / 20.0)
+        );
+
+        // A single slow outlier above the last bucket reports its own latency
+        metrics.record_latency(Duration::from_millis(60_000));
+        metrics.record_latency(Duration::from_millis(60_000));
+        assert_eq!(metrics.snapshot().p95_latency_ms, Some(60_000));
+    }
+
+    #[test]
+    fn request_allowlist_and_trust() {
+        let mut config = BridgeConfig::default();
+        assert!(config.is_request_allowed("document.setContent", true));
+        assert!(!config.is_request_allowed("document.setContent", false));
+        assert!(config.is_request_allowed("document.getContent", false));
+
+        config.allowed_request_types = Some(vec!["document.getContent".to_string()]);
+        assert!(config.is_request_allowed("document.getContent", true));
+        assert!(!config.is_request_allowed("tabs.list", true));
+        assert!(!config.is_request_allowed("document.setContent", true));
+    }
+
+    #[test]
+    fn error_code_round_trips_as_code() {
+        let value = serde_json::to_value(McpResponse::error(
+            "Request timeout after 5ms",
+            ERROR_CODE_TIMEOUT,
+        ))
+        .unwrap();
+        assert_eq!(value["success"], false);
+        assert_eq!(value["code"], "TIMEOUT");
+        assert_eq!(value["error"], "Request timeout after 5ms");
+
+        let payload: McpResponsePayload =
+            serde_json::from_value(serde_json::json!({ "id": "1", "success": false, "error": "No such file", "code": "NOT_FOUND" }))
+                .unwrap();
+        assert_eq!(payload.error_code.as_deref(), Some("NOT_FOUND"));
+
+        let payload: McpResponsePayload =
+            serde_json::from_value(serde_json::json!({ "id": "1", "success": true })).unwrap();
+        assert_eq!(payload.error_code, None);
+    }
+
+    #[test]
+    fn event_message_shape() {
+        let msg = event_message(
+            "document.activeChanged",
+            serde_json::json!({"path": "/a.md"}),
+        );
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["type"], "event");
+        assert_eq!(json["payload"]["event"], "document.activeChanged");
+        assert_eq!(json["payload"]["data"]["path"], "/a.md");
+    }
+
+    #[test]
+    fn client_limit() {
+        assert!(!at_capacity(5, None));
+        assert!(!at_capacity(0, Some(1)));
+        assert!(at_capacity(1, Some(1)));
+        assert!(at_capacity(2, Some(1)));
+    }
+}
@@ -42,6 +42,14 @@ static BRIDGE_RUNNING: AtomicBool = AtomicBool::new(false);
 /// Bridge port (stored when started)
 static BRIDGE_PORT: Mutex<Option<u16>> = Mutex::new(None);
 
+/// Payload of `mcp-server:port-fallback`: the preferred port was taken.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpPortFallback {
+    pub requested: u16,
+    pub actual: u16,
+}
+
 /// MCP server status for frontend
 #[derive(Clone, Serialize, Deserialize)]
 pub struct McpServerStatus {
@@ -54,10 +62,16 @@ pub struct McpServerStatus {
 
 /// Start only the MCP bridge WebSocket server (no sidecar).
 /// This is the recommended way to enable MCP - AI clients spawn their own sidecars.
-/// The port parameter is ignored - the OS assigns an available port automatically.
-/// The actual port is written to ~/.vmark/mcp-port for sidecar discovery.
+/// `port` is the preferred port (0 = any free port). If it is already in use the
+/// OS picks a free one and `mcp-server:port-fallback` is emitted with both ports.
+/// The actual port is returned and written to ~/.vmark/mcp-port for sidecar discovery.
+/// `config` carries optional bridge settings (see `mcp_bridge::BridgeConfig`).
 #[command]
-pub async fn mcp_bridge_start(app: AppHandle, port: u16) -> Result<McpServerStatus, String> {
+pub async fn mcp_bridge_start(
+    app: AppHandle,
+    port: u16,
+    config: Option<mcp_bridge::BridgeConfig>,
+) -> Result<McpServerStatus, String> {
     // Check if bridge is already running
     if BRIDGE_RUNNING.load(Ordering::SeqCst) {
         let current_port = BRIDGE_PORT.lock().map_err(|e| e.to_string())?.unwrap_or(port);
@@ -69,7 +83,8 @@ pub async fn mcp_bridge_start(app: AppHandle, port: u16) -> Result<McpServerStat
     }
 
     // Start the bridge WebSocket server (returns actual port assigned by OS)
-    let actual_port = mcp_bridge::start_bridge(app.clone(), port).await?;
+    let actual_port =
+        mcp_bridge::start_bridge(app.clone(), port, config.unwrap_or_default()).await?;
 
     // Mark bridge as running with actual port
     BRIDGE_RUNNING.store(true, Ordering::SeqCst);
@@ -80,6 +95,15 @@ pub async fn mcp_bridge_start(app: AppHandle, port: u16) -> Result<McpServerStat
 
     // Emit started event with actual port
     let _ = app.emit("mcp-server:started", actual_port);
+    if port != 0 && actual_port != port {
+        let _ = app.emit(
+            "mcp-server:port-fallback",
+            McpPortFallback {
+                requested: port,
+                actual: actual_port,
+            },
+        );
+    }
 
     #[cfg(debug_assertions)]
     eprintln!(
@@ -145,7 +169,8 @@ pub async fn mcp_server_start(app: AppHandle, port: u16) -> Result<McpServerStat
 
     // Start the bridge first (if not already running)
     let actual_port = if !BRIDGE_RUNNING.load(Ordering::SeqCst) {
-        let actual = mcp_bridge::start_bridge(app.clone(), port).await?;
+        let actual =
+            mcp_bridge::start_bridge(app.clone(), port, mcp_bridge::BridgeConfig::default()).await?;
         BRIDGE_RUNNING.store(true, Ordering::SeqCst);
         {
             let mut port_guard = BRIDGE_PORT.lock().map_err(|e| e.to_string())?;
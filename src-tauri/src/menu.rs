@@ -631,9 +631,11 @@ pub fn update_recent_files_menu(app: &AppHandle, files: Vec<String>) -> tauri::R
     Ok(())
 }
 
+/// Replace the recent documents list (persisted) and rebuild Open Recent.
 #[tauri::command]
 pub fn update_recent_files(app: AppHandle, files: Vec<String>) -> Result<(), String> {
-    update_recent_files_menu(&app, files).map_err(|e| e.to_string())
+    crate::recent_documents::set(&app, files);
+    Ok(())
 }
 
 /// Update the Open Recent Workspace submenu with the given list of workspace paths
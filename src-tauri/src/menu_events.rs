@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
+use serde::Serialize;
 use tauri::{AppHandle, Emitter, Manager};
 
 use crate::quit;
@@ -11,6 +12,17 @@ struct PendingMenuEvent {
     /// For simple events, payload is just the window label
     /// For recent-file events, payload includes the file path
     recent_file_path: Option<String>,
+    /// Open Recent menu index, set only for `menu:open-recent-file`
+    recent_file_index: Option<usize>,
+}
+
+/// Payload for `menu:open-recent-file`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecentFileOpen<'a> {
+    index: usize,
+    path: &'a str,
+    window_label: &'a str,
 }
 
 /// Global state for window readiness tracking
@@ -121,8 +133,7 @@ fn get_focused_document_window(app: &AppHandle) -> Option<tauri::WebviewWindow>
     app.webview_windows()
         .values()
         .find(|w| {
-            let label = w.label();
-            w.is_focused().unwrap_or(false) && (label == "main" || label.starts_with("doc-"))
+            w.is_focused().unwrap_or(false) && quit::is_document_window_label(w.label())
         })
         .cloned()
 }
@@ -132,17 +143,17 @@ fn get_focused_document_window(app: &AppHandle) -> Option<tauri::WebviewWindow>
 fn get_any_document_window(app: &AppHandle) -> Option<tauri::WebviewWindow> {
     app.webview_windows()
         .values()
-        .find(|w| {
-            let label = w.label();
-            label == "main" || label.starts_with("doc-")
-        })
+        .find(|w| quit::is_document_window_label(w.label()))
         .cloned()
 }
 
 /// Emit an event immediately using its payload format
 fn emit_event(window: &tauri::WebviewWindow, event: &PendingMenuEvent) {
     let label = window.label();
-    if let Some(ref path) = event.recent_file_path {
+    if let (Some(index), Some(path)) = (event.recent_file_index, event.recent_file_path.as_deref()) {
+        let payload = RecentFileOpen { index, path, window_label: label };
+        let _ = window.emit(&event.event_name, payload);
+    } else if let Some(ref path) = event.recent_file_path {
         let _ = window.emit(&event.event_name, (path.as_str(), label));
     } else {
         let _ = window.emit(&event.event_name, label);
@@ -177,14 +188,16 @@ fn make_menu_event(event_name: &str) -> PendingMenuEvent {
     PendingMenuEvent {
         event_name: event_name.to_string(),
         recent_file_path: None,
+        recent_file_index: None,
     }
 }
 
-/// Create a PendingMenuEvent for a recent-file event (payload includes file path)
-fn make_recent_file_event(path: &str) -> PendingMenuEvent {
+/// Create a PendingMenuEvent for a recent-file event (payload includes index and file path)
+fn make_recent_file_event(index: usize, path: &str) -> PendingMenuEvent {
     PendingMenuEvent {
         event_name: "menu:open-recent-file".to_string(),
         recent_file_path: Some(path.to_string()),
+        recent_file_index: Some(index),
     }
 }
 
@@ -193,6 +206,7 @@ fn make_recent_workspace_event(path: &str) -> PendingMenuEvent {
     PendingMenuEvent {
         event_name: "menu:open-recent-workspace".to_string(),
         recent_file_path: Some(path.to_string()),
+        recent_file_index: None,
     }
 }
 
@@ -234,25 +248,29 @@ pub fn handle_menu_event(app: &AppHandle, event: tauri::menu::MenuEvent) {
     }
 
     // Handle recent file clicks specially - look up path from snapshot and emit
-    // Emit to focused window with (path, windowLabel) tuple
+    // Emit to focused window with { index, path, windowLabel }
     // Three cases: focused window, no windows, windows exist but not focused
     if let Some(index_str) = id.strip_prefix("recent-file-") {
         if let Ok(index) = index_str.parse::<usize>() {
             // Look up path from the snapshot stored when menu was built
             // This prevents race conditions if store changed since menu creation
-            if let Some(path) = crate::menu::get_recent_file_path(index) {
-                let event = make_recent_file_event(&path);
-                if let Some(focused) = get_focused_window(app) {
-                    // Case 1: Focused window - emit directly (window is ready)
-                    emit_event(&focused, &event);
-                } else if !has_document_windows(app) {
-                    // Case 2: No windows - create one and queue event
-                    create_window_and_queue(app, event);
-                } else if let Some(window) = get_any_document_window(app) {
-                    // Case 3: Window exists but not focused (just created by Reopen)
-                    // Atomically queue event - will be flushed when window becomes ready
-                    emit_or_queue_atomic(&window, event);
-                }
+            let Some(path) = crate::menu::get_recent_file_path(index) else {
+                // Menu and snapshot out of sync; opening a guess would be worse
+                #[cfg(debug_assertions)]
+                eprintln!("[Menu] Ignoring recent-file click, index {} out of range", index);
+                return;
+            };
+            let event = make_recent_file_event(index, &path);
+            if let Some(focused) = get_focused_window(app) {
+                // Case 1: Focused window - emit directly (window is ready)
+                emit_event(&focused, &event);
+            } else if !has_document_windows(app) {
+                // Case 2: No windows - create one and queue event
+                create_window_and_queue(app, event);
+            } else if let Some(window) = get_any_document_window(app) {
+                // Case 3: Window exists but not focused (just created by Reopen)
+                // Atomically queue event - will be flushed when window becomes ready
+                emit_or_queue_atomic(&window, event);
             }
             return;
         }
@@ -282,6 +300,7 @@ pub fn handle_menu_event(app: &AppHandle, event: tauri::menu::MenuEvent) {
                 let event = PendingMenuEvent {
                     event_name: "menu:invoke-genie".to_string(),
                     recent_file_path: Some(path),
+                    recent_file_index: None,
                 };
                 if let Some(focused) = get_focused_window(app) {
                     emit_event(&focused, &event);
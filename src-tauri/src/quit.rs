@@ -1,7 +1,10 @@
-use std::sync::{Mutex, LazyLock, atomic::{AtomicBool, Ordering}};
+use std::collections::HashMap;
+use std::sync::{Mutex, LazyLock, atomic::{AtomicBool, AtomicU64, Ordering}};
+use std::time::{Duration, Instant};
+use serde::Serialize;
 use tauri::{AppHandle, Emitter, Manager};
 
-use crate::mcp_server;
+use crate::{hot_exit, mcp_server};
 
 static QUIT_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
 // IMPORTANT: A coordinated quit can be "in progress" while we still need to
@@ -13,10 +16,38 @@ static EXIT_ALLOWED: AtomicBool = AtomicBool::new(false);
 /// cancels the save dialog in one window, the remaining windows are untouched.
 static QUIT_TARGETS: LazyLock<Mutex<Vec<String>>> =
     LazyLock::new(|| Mutex::new(Vec::new()));
+/// Set while a save-all quit is running: every target saves at once and
+/// reports back via `report_saved`, instead of being prompted in turn.
+static SAVE_ALL_QUIT: AtomicBool = AtomicBool::new(false);
+/// Quit targets that have answered and are busy (showing a save prompt or
+/// saving), with when they became busy. The quit timeout doesn't force-close
+/// while any target is busy, for up to `QUIT_BUSY_LIMIT`.
+static BUSY_QUIT_TARGETS: LazyLock<Mutex<HashMap<String, Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// Bumped whenever a quit timeout is armed or the quit is cancelled, so a
+/// stale timer can tell it no longer applies.
+static QUIT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// How long a window may take to respond to `app:quit-requested` (or to
+/// report being busy) before the remaining windows are force-closed.
+const QUIT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest a target may stay busy before it counts as unresponsive after all.
+const QUIT_BUSY_LIMIT: Duration = Duration::from_secs(5 * 60);
+/// Time between `app:quit-forced` and destroying the windows, for a last save.
+const QUIT_FORCE_GRACE: Duration = Duration::from_millis(500);
+
+/// Payload for `app:quit-cancelled`.
+#[derive(Clone, Serialize)]
+pub struct QuitCancelled {
+    /// Window that cancelled the quit
+    pub label: String,
+    pub reason: String,
+}
 
 /// Determine whether a window label is a document window.
+/// Delegates to the window manager's prefix registry.
 pub fn is_document_window_label(label: &str) -> bool {
-    label == "main" || label.starts_with("doc-")
+    crate::window_manager::is_document_window_label(label)
 }
 
 /// Check if a coordinated quit is in progress.
@@ -35,9 +66,55 @@ fn set_quit_targets(targets: Vec<String>) {
     }
 }
 
+fn take_quit_targets() -> Vec<String> {
+    QUIT_TARGETS
+        .lock()
+        .map(|mut guard| std::mem::take(&mut *guard))
+        .unwrap_or_default()
+}
+
+/// Start a new quit generation, invalidating any armed timeout.
+fn next_quit_generation() -> u64 {
+    QUIT_GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+fn is_quit_generation_current(generation: u64) -> bool {
+    QUIT_GENERATION.load(Ordering::SeqCst) == generation
+}
+
+/// Mark a quit target busy (or no longer busy). Marking it busy again keeps
+/// the original start, so repeated reports can't extend `QUIT_BUSY_LIMIT`.
+fn set_target_busy(label: &str, busy: bool) {
+    if let Ok(mut guard) = BUSY_QUIT_TARGETS.lock() {
+        if busy {
+            guard.entry(label.to_string()).or_insert_with(Instant::now);
+        } else {
+            guard.remove(label);
+        }
+    }
+}
+
+/// Whether any of `targets` is busy, i.e. still responsive.
+fn any_target_busy(targets: &[String]) -> bool {
+    BUSY_QUIT_TARGETS
+        .lock()
+        .map(|busy| targets_busy(targets, &busy, Instant::now()))
+        .unwrap_or(false)
+}
+
+/// Whether any of `targets` has been busy since less than `QUIT_BUSY_LIMIT`
+/// before `now`.
+fn targets_busy(targets: &[String], busy: &HashMap<String, Instant>, now: Instant) -> bool {
+    targets.iter().any(|label| {
+        busy.get(label)
+            .is_some_and(|since| now.saturating_duration_since(*since) < QUIT_BUSY_LIMIT)
+    })
+}
+
 /// Remove a label from the quit targets queue.
 /// Returns `true` if the queue is now empty (all targets processed).
 fn remove_quit_target(label: &str) -> bool {
+    set_target_busy(label, false);
     if let Ok(mut guard) = QUIT_TARGETS.lock() {
         guard.retain(|l| l != label);
         return guard.is_empty();
@@ -48,6 +125,15 @@ fn remove_quit_target(label: &str) -> bool {
 /// Emit `app:quit-requested` to the next window in the queue (the first entry).
 /// If the queue is empty, finalise quit.
 fn process_next_quit_target(app: &AppHandle) {
+    if SAVE_ALL_QUIT.load(Ordering::SeqCst) {
+        // All targets were already asked to save; just wait for the queue to drain
+        let done = QUIT_TARGETS.lock().map(|g| g.is_empty()).unwrap_or(false);
+        if done {
+            finalize_quit(app, true);
+        }
+        return;
+    }
+
     let next = {
         let guard = QUIT_TARGETS.lock();
         guard.ok().and_then(|g| g.first().cloned())
@@ -57,29 +143,80 @@ fn process_next_quit_target(app: &AppHandle) {
             if let Some(window) = app.webview_windows().get(&label) {
                 let _ = window.emit("app:quit-requested", &label);
             }
+            arm_quit_timeout(app);
         }
         None => {
             // All targets already handled – finish quit
-            set_exit_allowed(true);
-            mcp_server::cleanup(app);
-            app.exit(0);
+            finalize_quit(app, true);
         }
     }
 }
 
-/// Start coordinated quit: request close of document windows sequentially.
+/// Allow exit, stop the MCP bridge and exit. Runs at most once per quit.
 ///
-/// Builds an ordered queue of document windows and emits `app:quit-requested`
-/// only to the **first** window.  When that window closes (or the frontend
-/// calls `acknowledge_quit_window`), `process_next_quit_target` advances to
-/// the next window.  If `cancel_quit` is called at any point the remaining
-/// windows are left untouched.
-pub fn start_quit(app: &AppHandle) {
-    if QUIT_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+/// `clean` clears the hot-exit running marker; a forced quit leaves it so the
+/// next launch offers to restore the captured session.
+fn finalize_quit(app: &AppHandle, clean: bool) {
+    if EXIT_ALLOWED.swap(true, Ordering::SeqCst) {
         return;
     }
-    set_exit_allowed(false);
+    if clean {
+        let _ = hot_exit::storage::clear_running_marker(app);
+    }
+    mcp_server::cleanup(app);
+    app.exit(0);
+}
+
+/// Give the quit targets `QUIT_TIMEOUT` to respond. If the quit is still
+/// waiting after that and no target reports being busy, the remaining windows
+/// have stopped responding: capture the hot-exit session and force-close them.
+/// While a target is busy the check is repeated every `QUIT_TIMEOUT`, until
+/// it reports back (which re-arms the timeout) or exceeds `QUIT_BUSY_LIMIT`.
+///
+/// Runs on a plain thread: `mcp_server::cleanup` blocks on the async runtime.
+fn arm_quit_timeout(app: &AppHandle) {
+    let generation = next_quit_generation();
+    let app = app.clone();
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(QUIT_TIMEOUT);
+            if !is_quit_generation_current(generation) || !QUIT_IN_PROGRESS.load(Ordering::SeqCst) {
+                return;
+            }
+            let pending = QUIT_TARGETS.lock().map(|g| g.clone()).unwrap_or_default();
+            if !any_target_busy(&pending) {
+                break;
+            }
+            #[cfg(debug_assertions)]
+            eprintln!("[Tauri] Quit timeout paused, a window is busy: {:?}", pending);
+        }
+
+        // Keep unsaved edits recoverable before anything is destroyed
+        if let Err(e) = tauri::async_runtime::block_on(
+            hot_exit::commands::hot_exit_capture(app.clone()),
+        ) {
+            eprintln!("[Tauri] Failed to capture session before forced quit: {}", e);
+        }
+        if !is_quit_generation_current(generation) || !QUIT_IN_PROGRESS.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let remaining = take_quit_targets();
+        eprintln!("[Tauri] Quit timed out, force-closing {:?}", remaining);
+        let _ = app.emit("app:quit-forced", &remaining);
+        std::thread::sleep(QUIT_FORCE_GRACE);
+
+        for label in &remaining {
+            if let Some(window) = app.get_webview_window(label) {
+                let _ = window.destroy();
+            }
+        }
+        finalize_quit(&app, false);
+    });
+}
 
+/// Collect document windows as quit targets, closing all other windows.
+fn collect_quit_targets(app: &AppHandle) -> Vec<String> {
     let mut targets = Vec::new();
     for (label, window) in app.webview_windows() {
         if is_document_window_label(&label) {
@@ -89,12 +226,26 @@ pub fn start_quit(app: &AppHandle) {
             let _ = window.close();
         }
     }
+    targets
+}
 
+/// Start coordinated quit: request close of document windows sequentially.
+///
+/// Builds an ordered queue of document windows and emits `app:quit-requested`
+/// only to the **first** window.  When that window closes (or the frontend
+/// calls `acknowledge_quit_window`), `process_next_quit_target` advances to
+/// the next window.  If `cancel_quit` is called at any point the remaining
+/// windows are left untouched.
+pub fn start_quit(app: &AppHandle) {
+    if QUIT_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    set_exit_allowed(false);
+
+    let targets = collect_quit_targets(app);
     if targets.is_empty() {
         // Keep QUIT_IN_PROGRESS true so ExitRequested handler allows exit
-        set_exit_allowed(true);
-        mcp_server::cleanup(app);
-        app.exit(0);
+        finalize_quit(app, true);
         return;
     }
 
@@ -103,14 +254,131 @@ pub fn start_quit(app: &AppHandle) {
     process_next_quit_target(app);
 }
 
+/// Save every document window, then quit without per-window prompts.
+///
+/// Emits `app:save-all` to all document windows at once; each marks itself
+/// busy via `set_quit_window_busy` (a Save As dialog can take a while) and
+/// answers with `report_saved`. The app quits once every window has saved. A
+/// failed save aborts the quit and emits `app:quit-save-failed` with that
+/// window's label.
+#[tauri::command]
+pub fn save_all_and_quit(app: AppHandle) {
+    if QUIT_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    set_exit_allowed(false);
+
+    let targets = collect_quit_targets(&app);
+    if targets.is_empty() {
+        finalize_quit(&app, true);
+        return;
+    }
+
+    SAVE_ALL_QUIT.store(true, Ordering::SeqCst);
+    set_quit_targets(targets.clone());
+    for label in &targets {
+        if let Some(window) = app.get_webview_window(label) {
+            let _ = window.emit("app:save-all", label);
+        }
+    }
+    arm_quit_timeout(&app);
+}
+
+/// Called by a window after handling `app:save-all`. `error` is set when the
+/// save failed, which aborts the quit.
+#[tauri::command]
+pub fn report_saved(app: AppHandle, label: String, error: Option<String>) {
+    if !QUIT_IN_PROGRESS.load(Ordering::SeqCst) || !SAVE_ALL_QUIT.load(Ordering::SeqCst) {
+        return;
+    }
+
+    if let Some(_e) = error {
+        #[cfg(debug_assertions)]
+        eprintln!("[Tauri] Save-all quit aborted: {} failed to save: {}", label, _e);
+        reset_quit();
+        let _ = app.emit("app:quit-save-failed", &label);
+        return;
+    }
+
+    if !remove_quit_target(&label) {
+        // Windows that never answered get a fresh timeout
+        arm_quit_timeout(&app);
+    }
+    process_next_quit_target(&app);
+}
+
+/// Relaunch the app (e.g. to apply an update).
+///
+/// Captures the hot-exit session first unless `skip_session` is set; a failed
+/// capture is logged and the restart goes ahead, as the user asked for it.
+#[tauri::command]
+pub async fn restart_app(app: AppHandle, skip_session: bool) -> Result<(), String> {
+    let has_documents = app
+        .webview_windows()
+        .keys()
+        .any(|label| is_document_window_label(label));
+
+    if !skip_session && has_documents {
+        if let Err(e) = hot_exit::commands::hot_exit_capture(app.clone()).await {
+            eprintln!("[Tauri] Failed to capture session before restart: {}", e);
+        }
+    }
+
+    // `mcp_server::cleanup` blocks on the async runtime, so leave it first
+    std::thread::spawn(move || {
+        QUIT_IN_PROGRESS.store(true, Ordering::SeqCst);
+        set_exit_allowed(true);
+        let _ = hot_exit::storage::clear_running_marker(&app);
+        mcp_server::cleanup(&app);
+        app.restart();
+    });
+
+    Ok(())
+}
+
 /// Cancel an in-progress quit (e.g., user cancelled save prompt).
+/// Prefer `cancel_quit_with_reason` so other windows learn why.
 #[tauri::command]
 pub fn cancel_quit() {
+    reset_quit();
+}
+
+/// Cancel an in-progress quit on behalf of `label` and emit `app:quit-cancelled`
+/// so other windows can dismiss their prompts.
+#[tauri::command]
+pub fn cancel_quit_with_reason(app: AppHandle, label: String, reason: String) {
+    #[cfg(debug_assertions)]
+    eprintln!("[Tauri] Quit cancelled by {}: {}", label, reason);
+
+    reset_quit();
+    let _ = app.emit("app:quit-cancelled", QuitCancelled { label, reason });
+}
+
+fn reset_quit() {
+    next_quit_generation();
+    if let Ok(mut busy) = BUSY_QUIT_TARGETS.lock() {
+        busy.clear();
+    }
+    SAVE_ALL_QUIT.store(false, Ordering::SeqCst);
     QUIT_IN_PROGRESS.store(false, Ordering::SeqCst);
     set_exit_allowed(false);
     set_quit_targets(Vec::new());
 }
 
+/// Called by a quit target while it handles the quit: `busy` while a save
+/// prompt or save is in progress (pausing the force-close timeout), then
+/// `false` when done, which gives the remaining targets a fresh timeout.
+#[tauri::command]
+pub fn set_quit_window_busy(app: AppHandle, label: String, busy: bool) {
+    if !QUIT_IN_PROGRESS.load(Ordering::SeqCst) {
+        return;
+    }
+    set_target_busy(&label, busy);
+    if !busy {
+        arm_quit_timeout(&app);
+    }
+}
+
 /// Called by the frontend after a window has been successfully closed during
 /// a coordinated quit.  Advances to the next window in the queue.
 #[tauri::command]
@@ -154,4 +422,35 @@ mod tests {
         assert!(is_document_window_label("doc-123"));
         assert!(!is_document_window_label("settings"));
     }
+
+    #[test]
+    fn test_busy_target_pauses_timeout() {
+        let targets = vec!["doc-1".to_string(), "doc-2".to_string()];
+        let now = Instant::now();
+        let mut busy = HashMap::new();
+        assert!(!targets_busy(&targets, &busy, now));
+
+        busy.insert("doc-2".to_string(), now);
+        assert!(targets_busy(&targets, &busy, now));
+        assert!(!targets_busy(&targets[..1], &busy, now));
+
+        // A target busy for too long counts as unresponsive
+        assert!(targets_busy(&targets, &busy, now + QUIT_BUSY_LIMIT / 2));
+        assert!(!targets_busy(&targets, &busy, now + QUIT_BUSY_LIMIT));
+    }
+
+    // The only test touching the quit statics, so it can't race the others
+    #[test]
+    fn test_quit_generation_invalidates_timeout() {
+        let armed = next_quit_generation();
+        assert!(is_quit_generation_current(armed));
+
+        // Advancing to the next window re-arms; the old timer no longer applies
+        let rearmed = next_quit_generation();
+        assert!(!is_quit_generation_current(armed));
+        assert!(is_quit_generation_current(rearmed));
+
+        cancel_quit();
+        assert!(!is_quit_generation_current(rearmed));
+    }
 }
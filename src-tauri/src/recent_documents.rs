@@ -0,0 +1,200 @@
+//! Recent documents store.
+//!
+//! Single source of truth for the "Open Recent" menu: documents registered
+//! via `register_dock_recent` (which also feeds the OS recent list) or set by
+//! the frontend's recent files store. Persisted to app data so the menu is
+//! populated before the frontend loads.
+//!
+//! Entries for files that no longer exist are kept by default (a network
+//! drive may just be unmounted); `set_recent_documents_prune_missing` opts in
+//! to dropping them.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+use crate::app_paths;
+
+/// Maximum number of recent documents kept
+const MAX_RECENT_DOCUMENTS: usize = 15;
+
+/// Recent documents file name in app data
+const RECENT_DOCUMENTS_FILE: &str = "recent-documents.json";
+
+/// Most recent first
+static RECENT_DOCUMENTS: Mutex<VecDeque<PathBuf>> = Mutex::new(VecDeque::new());
+
+/// Whether entries whose files are missing are dropped when the list is loaded
+static PRUNE_MISSING: AtomicBool = AtomicBool::new(false);
+
+/// Load the persisted list and build the menu from it. Call once at startup,
+/// after the menu is set.
+pub fn load(app: &AppHandle) {
+    match read_documents(app) {
+        Ok(paths) => {
+            let prune_missing = PRUNE_MISSING.load(Ordering::SeqCst);
+            if let Ok(mut docs) = RECENT_DOCUMENTS.lock() {
+                *docs = normalize_recent(paths, prune_missing);
+            }
+        }
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("[RecentDocuments] Failed to load recent documents: {}", _e);
+        }
+    }
+    rebuild_recent_menu(app);
+}
+
+/// Move a document to the top of the list, persist it and rebuild the menu.
+pub fn add(app: &AppHandle, path: &str) {
+    if let Ok(mut docs) = RECENT_DOCUMENTS.lock() {
+        push_recent(&mut docs, PathBuf::from(path));
+    }
+    save_and_rebuild(app);
+}
+
+/// Replace the whole list (e.g. the frontend removed or cleared entries).
+pub fn set(app: &AppHandle, paths: Vec<String>) {
+    if let Ok(mut docs) = RECENT_DOCUMENTS.lock() {
+        *docs = normalize_recent(paths.into_iter().map(PathBuf::from), false);
+    }
+    save_and_rebuild(app);
+}
+
+/// Enable or disable dropping entries whose files no longer exist. The list
+/// is loaded before the frontend can set this, so enabling it also prunes
+/// the current list.
+#[tauri::command]
+pub fn set_recent_documents_prune_missing(app: AppHandle, enabled: bool) {
+    PRUNE_MISSING.store(enabled, Ordering::SeqCst);
+    if !enabled {
+        return;
+    }
+    let pruned = match RECENT_DOCUMENTS.lock() {
+        Ok(mut docs) => {
+            let before = docs.len();
+            *docs = normalize_recent(docs.drain(..), true);
+            docs.len() != before
+        }
+        Err(_) => false,
+    };
+    if pruned {
+        save_and_rebuild(&app);
+    }
+}
+
+/// Current list, most recent first.
+pub fn list() -> Vec<String> {
+    RECENT_DOCUMENTS
+        .lock()
+        .map(|docs| docs.iter().map(|p| p.to_string_lossy().into_owned()).collect())
+        .unwrap_or_default()
+}
+
+/// Regenerate the Open Recent submenu (`recent-file-N` items) from the store.
+pub fn rebuild_recent_menu(app: &AppHandle) {
+    if let Err(_e) = crate::menu::update_recent_files_menu(app, list()) {
+        #[cfg(debug_assertions)]
+        eprintln!("[RecentDocuments] Failed to rebuild recent menu: {}", _e);
+    }
+}
+
+fn save_and_rebuild(app: &AppHandle) {
+    if let Err(_e) = write_documents(app, &list()) {
+        #[cfg(debug_assertions)]
+        eprintln!("[RecentDocuments] Failed to save recent documents: {}", _e);
+    }
+    rebuild_recent_menu(app);
+}
+
+/// Insert `path` at the front, dropping a previous entry and the overflow.
+fn push_recent(docs: &mut VecDeque<PathBuf>, path: PathBuf) {
+    docs.retain(|p| p != &path);
+    docs.push_front(path);
+    docs.truncate(MAX_RECENT_DOCUMENTS);
+}
+
+/// Build a list from `paths` (most recent first): duplicates keep their first
+/// position, the length is capped, and with `prune_missing` entries whose
+/// files no longer exist are dropped.
+fn normalize_recent(
+    paths: impl IntoIterator<Item = PathBuf>,
+    prune_missing: bool,
+) -> VecDeque<PathBuf> {
+    let mut docs = VecDeque::new();
+    for path in paths {
+        if docs.len() == MAX_RECENT_DOCUMENTS {
+            break;
+        }
+        if docs.contains(&path) || (prune_missing && !path.exists()) {
+            continue;
+        }
+        docs.push_back(path);
+    }
+    docs
+}
+
+fn documents_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app_paths::resolve_app_data_dir(app)?.join(RECENT_DOCUMENTS_FILE))
+}
+
+fn read_documents(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    read_documents_from(&documents_path(app)?)
+}
+
+fn read_documents_from(path: &Path) -> Result<Vec<PathBuf>, String> {
+    Ok(app_paths::read_json(path)?.unwrap_or_default())
+}
+
+fn write_documents(app: &AppHandle, paths: &[String]) -> Result<(), String> {
+    app_paths::write_json_atomic(&documents_path(app)?, paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_recent_dedupes_and_caps() {
+        let mut docs = VecDeque::new();
+        for i in 0..MAX_RECENT_DOCUMENTS + 3 {
+            push_recent(&mut docs, PathBuf::from(format!("/doc-{}.md", i)));
+        }
+        assert_eq!(docs.len(), MAX_RECENT_DOCUMENTS);
+        assert_eq!(docs[0], PathBuf::from(format!("/doc-{}.md", MAX_RECENT_DOCUMENTS + 2)));
+
+        // Re-registering moves an entry to the front instead of duplicating it
+        push_recent(&mut docs, PathBuf::from("/doc-5.md"));
+        assert_eq!(docs.len(), MAX_RECENT_DOCUMENTS);
+        assert_eq!(docs[0], PathBuf::from("/doc-5.md"));
+        assert_eq!(docs.iter().filter(|p| p.ends_with("doc-5.md")).count(), 1);
+    }
+
+    #[test]
+    fn test_normalize_recent_dedupes_and_prunes() {
+        let dir = tempfile::tempdir().unwrap();
+        let kept = dir.path().join("kept.md");
+        std::fs::write(&kept, "").unwrap();
+        let missing = dir.path().join("missing.md");
+        let paths = vec![kept.clone(), missing.clone(), kept.clone()];
+
+        let docs = normalize_recent(paths.clone(), false);
+        assert_eq!(docs, [kept.clone(), missing]);
+        assert_eq!(normalize_recent(paths, true), [kept]);
+
+        let many = (0..MAX_RECENT_DOCUMENTS + 5).map(|i| PathBuf::from(format!("/doc-{}.md", i)));
+        assert_eq!(normalize_recent(many, false).len(), MAX_RECENT_DOCUMENTS);
+    }
+
+    #[test]
+    fn test_read_documents_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(RECENT_DOCUMENTS_FILE);
+        assert!(read_documents_from(&path).unwrap().is_empty());
+
+        std::fs::write(&path, r#"["/a.md", "/b.md"]"#).unwrap();
+        assert_eq!(read_documents_from(&path).unwrap(), vec![PathBuf::from("/a.md"), PathBuf::from("/b.md")]);
+    }
+}
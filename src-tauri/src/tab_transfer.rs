@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter, Manager};
 
-use crate::window_manager;
+use crate::{quit, window_manager};
 
 /// Data transferred when a tab is dragged out to a new window.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,37 +18,482 @@ pub struct TabTransferData {
     pub content: String,
     pub saved_content: String,
     pub is_dirty: bool,
+    /// Editor view state, so the receiving window can restore the viewport.
+    /// Optional so payloads from older frontends still deserialize.
+    #[serde(default)]
+    pub scroll_top: Option<f64>,
+    #[serde(default)]
+    pub cursor_line: Option<u32>,
+    #[serde(default)]
+    pub cursor_col: Option<u32>,
 }
 
+/// Payload of `tab:transfer`, emitted to a window receiving a dropped tab.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabTransferEvent {
+    pub data: TabTransferData,
+    /// Tab bar slot to insert at; `None` appends at the end.
+    pub target_index: Option<usize>,
+    /// Window the tab came from; pass it to `confirm_tab_transfer`.
+    pub source_label: String,
+}
+
+/// Payload of `tab:transfer-result`, emitted back to the source window.
+/// The source removes its tab only when `accepted` is true.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabTransferResult {
+    pub tab_id: String,
+    pub target_label: String,
+    pub accepted: bool,
+}
+
+/// A document window under the cursor during a tab drag.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropTarget {
+    pub label: String,
+    /// Whether the target is the focused window (it wins when windows overlap).
+    pub focused: bool,
+    /// Cursor position across the window width, from 0.0 (left) to 1.0 (right).
+    pub relative_x: f64,
+    /// Cursor position relative to the window in logical pixels, so its tab
+    /// bar can pick a slot.
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Closed tabs remembered for "Reopen Closed Tab", across all windows.
+const MAX_CLOSED_TABS: usize = 20;
+
+/// Unclaimed transfers are dropped after this long (the new window never
+/// loaded, e.g. its renderer crashed before claiming).
+const TRANSFER_TTL: Duration = Duration::from_secs(60);
+
+/// How long the source waits for the target to accept or reject a tab
+/// before the transfer counts as rejected.
+const TRANSFER_RESULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Transfers into existing windows awaiting `confirm_tab_transfer`, keyed by
+/// (source label, tab id). The value identifies the attempt for the timeout.
+static PENDING_RESULTS: Mutex<Option<HashMap<(String, String), PendingResult>>> = Mutex::new(None);
+static NEXT_RESULT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Window a tab drag is hovering over, and when the hover began. Only the
+/// latest hover may raise its window once the dwell time has passed.
+static PENDING_HOVER: Mutex<Option<(String, Instant)>> = Mutex::new(None);
+
+struct PendingResult {
+    id: u64,
+    target_label: String,
+}
+
+/// Remove a pending result. With `id`, only that attempt is removed.
+fn take_pending_result(source_label: &str, tab_id: &str, id: Option<u64>) -> Option<PendingResult> {
+    let mut guard = PENDING_RESULTS.lock().unwrap();
+    let map = guard.as_mut()?;
+    let key = (source_label.to_string(), tab_id.to_string());
+    if id.is_some_and(|id| map.get(&key).map(|p| p.id) != Some(id)) {
+        return None;
+    }
+    map.remove(&key)
+}
+
+/// Tabs waiting to be claimed by a new window, in their original order.
+struct PendingTransfer {
+    created_at: Instant,
+    tabs: Vec<TabTransferData>,
+}
+
+/// Recently closed tabs, oldest first, with the window each was closed in.
+struct ClosedTabStack {
+    entries: VecDeque<(String, TabTransferData)>,
+}
+
+impl ClosedTabStack {
+    const fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, window_label: String, data: TabTransferData) {
+        self.entries.push_back((window_label, data));
+        while self.entries.len() > MAX_CLOSED_TABS {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Take the most recently closed tab of a window.
+    fn pop(&mut self, window_label: &str) -> Option<TabTransferData> {
+        let index = self
+            .entries
+            .iter()
+            .rposition(|(label, _)| label == window_label)?;
+        self.entries.remove(index).map(|(_, data)| data)
+    }
+
+    fn clear_window(&mut self, window_label: &str) {
+        self.entries.retain(|(label, _)| label != window_label);
+    }
+}
+
+static CLOSED_TABS: Mutex<ClosedTabStack> = Mutex::new(ClosedTabStack::new());
+
 /// Registry of pending tab transfers, keyed by target window label.
-static TRANSFER_REGISTRY: Mutex<Option<HashMap<String, TabTransferData>>> = Mutex::new(None);
+static TRANSFER_REGISTRY: Mutex<Option<HashMap<String, PendingTransfer>>> = Mutex::new(None);
 
-fn registry() -> std::sync::MutexGuard<'static, Option<HashMap<String, TabTransferData>>> {
+fn registry() -> std::sync::MutexGuard<'static, Option<HashMap<String, PendingTransfer>>> {
     TRANSFER_REGISTRY.lock().unwrap()
 }
 
+fn store_transfer(label: &str, tabs: Vec<TabTransferData>) {
+    let now = Instant::now();
+    {
+        let mut guard = registry();
+        let map = guard.get_or_insert_with(HashMap::new);
+        reap_expired_transfers(map, now);
+        map.insert(
+            label.to_string(),
+            PendingTransfer {
+                created_at: now,
+                tabs,
+            },
+        );
+    }
+
+    // Sweep again once this entry could have expired, in case nothing else
+    // is detached in the meantime.
+    tauri::async_runtime::spawn(async {
+        tokio::time::sleep(TRANSFER_TTL).await;
+        if let Some(map) = registry().as_mut() {
+            reap_expired_transfers(map, Instant::now());
+        }
+    });
+}
+
+/// Drop transfers older than `TRANSFER_TTL`.
+fn reap_expired_transfers(map: &mut HashMap<String, PendingTransfer>, now: Instant) {
+    map.retain(|_label, pending| {
+        let expired = now.duration_since(pending.created_at) >= TRANSFER_TTL;
+        #[cfg(debug_assertions)]
+        if expired {
+            eprintln!(
+                "[TabTransfer] Dropping unclaimed transfer for {} ({} tab(s))",
+                _label,
+                pending.tabs.len()
+            );
+        }
+        !expired
+    });
+}
+
 /// Create a new window and store transfer data for it.
 /// Returns the new window label.
 #[tauri::command]
-pub fn detach_tab_to_new_window(
+pub fn detach_tab_to_new_window(app: AppHandle, data: TabTransferData) -> Result<String, String> {
+    let label =
+        window_manager::create_document_window_for_transfer(&app).map_err(|e| e.to_string())?;
+
+    store_transfer(&label, vec![data]);
+
+    Ok(label)
+}
+
+/// Create one new window and store several tabs for it (a multi-selection
+/// dragged out together). Returns the new window label.
+#[tauri::command]
+pub fn detach_tabs_to_new_window(
+    app: AppHandle,
+    data: Vec<TabTransferData>,
+) -> Result<String, String> {
+    if data.is_empty() {
+        return Err("No tabs to transfer".to_string());
+    }
+
+    let label =
+        window_manager::create_document_window_for_transfer(&app).map_err(|e| e.to_string())?;
+
+    store_transfer(&label, data);
+
+    Ok(label)
+}
+
+/// Create a new window at the given screen rectangle (logical pixels) and store
+/// transfer data for it, so the detached window appears where the tab was dropped.
+/// Returns the new window label.
+#[tauri::command]
+pub fn detach_tab_to_new_window_at(
     app: AppHandle,
     data: TabTransferData,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
 ) -> Result<String, String> {
-    let label = window_manager::create_document_window_for_transfer(&app)
+    let requested = window_manager::LogicalRect {
+        x,
+        y,
+        width,
+        height,
+    };
+    let label = window_manager::create_document_window_for_transfer_at(&app, requested)
         .map_err(|e| e.to_string())?;
 
-    let mut guard = registry();
-    let map = guard.get_or_insert_with(HashMap::new);
-    map.insert(label.clone(), data);
+    store_transfer(&label, vec![data]);
 
     Ok(label)
 }
 
-/// Claim transfer data for a window. Returns the data and removes it from the registry.
+/// Point relative to `rect` if it lies inside it.
+fn point_in_rect(rect: &window_manager::LogicalRect, x: f64, y: f64) -> Option<(f64, f64)> {
+    rect.contains(x, y).then_some((x - rect.x, y - rect.y))
+}
+
+/// Build the drop target for a hit at `(x, y)` inside a window of `width`.
+fn drop_target(label: String, focused: bool, x: f64, y: f64, width: f64) -> DropTarget {
+    let relative_x = if width > 0.0 {
+        (x / width).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    DropTarget {
+        label,
+        focused,
+        relative_x,
+        x,
+        y,
+    }
+}
+
+/// Pick among overlapping hits: the focused window wins, otherwise the first hit.
+fn pick_drop_target(hits: Vec<DropTarget>) -> Option<DropTarget> {
+    let focused = hits.iter().position(|hit| hit.focused);
+    hits.into_iter().nth(focused.unwrap_or(0))
+}
+
+/// Find the document window (other than `source_label`) under a screen point.
+///
+/// `screen_x`/`screen_y` are logical pixels (as in a DOM `MouseEvent`'s
+/// `screenX`/`screenY`); each window is converted with its own scale factor.
+/// When windows overlap at the point, the focused one is preferred.
+#[tauri::command]
+pub fn find_drop_target_window(
+    app: AppHandle,
+    source_label: String,
+    screen_x: f64,
+    screen_y: f64,
+) -> Option<DropTarget> {
+    let mut hits = Vec::new();
+    for (label, window) in app.webview_windows() {
+        if label == source_label || !quit::is_document_window_label(&label) {
+            continue;
+        }
+        if !window.is_visible().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+            continue;
+        }
+        let (Ok(position), Ok(size), Ok(scale)) = (
+            window.outer_position(),
+            window.outer_size(),
+            window.scale_factor(),
+        ) else {
+            continue;
+        };
+
+        let rect = window_manager::logical_window_rect(position, size, scale);
+        if let Some((x, y)) = point_in_rect(&rect, screen_x, screen_y) {
+            let focused = window.is_focused().unwrap_or(false);
+            hits.push(drop_target(label, focused, x, y, rect.width));
+        }
+    }
+    pick_drop_target(hits)
+}
+
+/// Record a hover over `label`. Returns the hover's start time if a new dwell
+/// timer should be scheduled, or `None` if that window is already pending.
+fn begin_hover(label: &str, now: Instant) -> Option<Instant> {
+    let mut pending = PENDING_HOVER.lock().unwrap();
+    if pending
+        .as_ref()
+        .is_some_and(|(current, _)| current == label)
+    {
+        return None;
+    }
+    *pending = Some((label.to_string(), now));
+    Some(now)
+}
+
+/// Clear the pending hover if it is still the one that started at `started`.
+fn finish_hover(label: &str, started: Instant) -> bool {
+    let mut pending = PENDING_HOVER.lock().unwrap();
+    if pending.as_ref() != Some(&(label.to_string(), started)) {
+        return false;
+    }
+    *pending = None;
+    true
+}
+
+/// Spring-loaded focus: raise `window_label` once a tab drag has dwelt over it
+/// for `dwell_ms`. Hovering a different window (or calling
+/// `cancel_hover_drop_target`) before then cancels the pending focus; repeated
+/// calls for the same window keep the original dwell start.
+#[tauri::command]
+pub fn hover_drop_target(app: AppHandle, window_label: String, dwell_ms: u64) {
+    if !quit::is_document_window_label(&window_label) {
+        return;
+    }
+    let Some(started) = begin_hover(&window_label, Instant::now()) else {
+        return;
+    };
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(dwell_ms)).await;
+        if !finish_hover(&window_label, started) {
+            return;
+        }
+        if let Some(window) = app.get_webview_window(&window_label) {
+            #[cfg(debug_assertions)]
+            eprintln!("[TabTransfer] spring-loaded focus of {}", window_label);
+            let _ = window.set_focus();
+        }
+    });
+}
+
+/// Drop any pending spring-loaded focus (the drag left all windows or ended).
+#[tauri::command]
+pub fn cancel_hover_drop_target() {
+    *PENDING_HOVER.lock().unwrap() = None;
+}
+
+/// Move a tab into an existing window by emitting `tab:transfer` to it.
+/// `target_index` is the tab bar slot to insert at; `None` appends.
+///
+/// The source keeps its tab until `tab:transfer-result` arrives: the target
+/// answers via `confirm_tab_transfer`, or the transfer is rejected after
+/// `TRANSFER_RESULT_TIMEOUT`.
+#[tauri::command]
+pub fn transfer_tab_to_existing_window(
+    app: AppHandle,
+    source_label: String,
+    target_label: String,
+    data: TabTransferData,
+    target_index: Option<usize>,
+) -> Result<(), String> {
+    let window = app
+        .get_webview_window(&target_label)
+        .ok_or_else(|| format!("Window not found: {}", target_label))?;
+
+    let tab_id = data.tab_id.clone();
+    let id = NEXT_RESULT_ID.fetch_add(1, Ordering::SeqCst);
+    PENDING_RESULTS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(
+            (source_label.clone(), tab_id.clone()),
+            PendingResult {
+                id,
+                target_label: target_label.clone(),
+            },
+        );
+
+    let event = TabTransferEvent {
+        data,
+        target_index,
+        source_label: source_label.clone(),
+    };
+    if let Err(e) = app.emit_to(target_label.as_str(), "tab:transfer", event) {
+        take_pending_result(&source_label, &tab_id, Some(id));
+        return Err(e.to_string());
+    }
+    let _ = window.set_focus();
+
+    // Don't leave the source waiting on a target that never answers
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(TRANSFER_RESULT_TIMEOUT).await;
+        if let Some(pending) = take_pending_result(&source_label, &tab_id, Some(id)) {
+            #[cfg(debug_assertions)]
+            eprintln!(
+                "[TabTransfer] {} did not answer transfer of {} from {}",
+                pending.target_label, tab_id, source_label
+            );
+            let result = TabTransferResult {
+                tab_id,
+                target_label: pending.target_label,
+                accepted: false,
+            };
+            let _ = app.emit_to(source_label.as_str(), "tab:transfer-result", result);
+        }
+    });
+
+    Ok(())
+}
+
+/// Called by the target window after handling `tab:transfer`. Forwards the
+/// outcome to the source as `tab:transfer-result`.
+///
+/// Returns false if the source is no longer waiting (the transfer timed out);
+/// the target should then drop the tab, since the source kept it.
+#[tauri::command]
+pub fn confirm_tab_transfer(
+    app: AppHandle,
+    source_label: String,
+    tab_id: String,
+    accepted: bool,
+) -> bool {
+    let Some(pending) = take_pending_result(&source_label, &tab_id, None) else {
+        return false;
+    };
+
+    let result = TabTransferResult {
+        tab_id,
+        target_label: pending.target_label,
+        accepted,
+    };
+    let _ = app.emit_to(source_label.as_str(), "tab:transfer-result", result);
+    true
+}
+
+/// Claim transfer data for a window. Returns the first tab and removes the
+/// transfer from the registry; use `claim_tab_transfers` for multi-tab transfers.
 #[tauri::command]
 pub fn claim_tab_transfer(window_label: String) -> Option<TabTransferData> {
+    claim_tab_transfers(window_label).and_then(|tabs| tabs.into_iter().next())
+}
+
+/// Claim all transferred tabs for a window, in their original order.
+/// Removes them from the registry.
+#[tauri::command]
+pub fn claim_tab_transfers(window_label: String) -> Option<Vec<TabTransferData>> {
     let mut guard = registry();
-    guard.as_mut().and_then(|map| map.remove(&window_label))
+    guard
+        .as_mut()
+        .and_then(|map| map.remove(&window_label))
+        .map(|pending| pending.tabs)
+}
+
+/// Remember a tab the user just closed so it can be reopened.
+#[tauri::command]
+pub fn push_closed_tab(window_label: String, data: TabTransferData) {
+    if let Ok(mut stack) = CLOSED_TABS.lock() {
+        stack.push(window_label, data);
+    }
+}
+
+/// Pop the most recently closed tab of a window (Cmd+Shift+T) for the
+/// frontend to reopen. Returns `None` when there is nothing to reopen.
+#[tauri::command]
+pub fn reopen_last_closed_tab(window_label: String) -> Option<TabTransferData> {
+    CLOSED_TABS.lock().ok()?.pop(&window_label)
+}
+
+/// Forget the closed tabs of a destroyed window, so they can't be reopened
+/// into an unrelated window that later reuses the label.
+pub fn clear_closed_tabs(window_label: &str) {
+    if let Ok(mut stack) = CLOSED_TABS.lock() {
+        stack.clear_window(window_label);
+    }
 }
 
 /// Remove any unclaimed transfer data for a window that was destroyed.
@@ -57,3 +504,182 @@ pub fn clear_unclaimed_transfer(window_label: &str) {
         map.remove(window_label);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tab(id: &str) -> TabTransferData {
+        TabTransferData {
+            tab_id: id.to_string(),
+            title: id.to_string(),
+            file_path: None,
+            content: String::new(),
+            saved_content: String::new(),
+            is_dirty: false,
+            scroll_top: None,
+            cursor_line: None,
+            cursor_col: None,
+        }
+    }
+
+    #[test]
+    fn closed_tabs_reopen_per_window_newest_first() {
+        let mut stack = ClosedTabStack::new();
+        stack.push("doc-1".to_string(), tab("a"));
+        stack.push("doc-2".to_string(), tab("b"));
+        stack.push("doc-1".to_string(), tab("c"));
+
+        assert_eq!(stack.pop("doc-1").unwrap().tab_id, "c");
+        assert_eq!(stack.pop("doc-1").unwrap().tab_id, "a");
+        assert!(stack.pop("doc-1").is_none());
+
+        stack.clear_window("doc-2");
+        assert!(stack.pop("doc-2").is_none());
+    }
+
+    #[test]
+    fn closed_tab_stack_is_bounded() {
+        let mut stack = ClosedTabStack::new();
+        for i in 0..MAX_CLOSED_TABS + 5 {
+            stack.push("main".to_string(), tab(&i.to_string()));
+        }
+        assert_eq!(stack.entries.len(), MAX_CLOSED_TABS);
+        // The oldest entries were dropped
+        assert_eq!(stack.entries.front().unwrap().1.tab_id, "5");
+    }
+
+    #[test]
+    fn expired_transfers_are_reaped() {
+        let start = Instant::now();
+        let mut map = HashMap::new();
+        map.insert(
+            "doc-1".to_string(),
+            PendingTransfer {
+                created_at: start,
+                tabs: vec![tab("a")],
+            },
+        );
+        map.insert(
+            "doc-2".to_string(),
+            PendingTransfer {
+                created_at: start + Duration::from_secs(30),
+                tabs: vec![tab("b")],
+            },
+        );
+
+        reap_expired_transfers(&mut map, start + Duration::from_secs(59));
+        assert_eq!(map.len(), 2);
+
+        reap_expired_transfers(&mut map, start + TRANSFER_TTL);
+        assert!(!map.contains_key("doc-1"));
+        assert!(map.contains_key("doc-2"));
+    }
+
+    #[test]
+    fn stale_timeout_does_not_take_newer_attempt() {
+        let key = ("doc-src".to_string(), "tab-x".to_string());
+        PENDING_RESULTS
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(
+                key,
+                PendingResult {
+                    id: 7,
+                    target_label: "doc-dst".to_string(),
+                },
+            );
+
+        assert!(take_pending_result("doc-src", "tab-x", Some(6)).is_none());
+        let pending = take_pending_result("doc-src", "tab-x", Some(7)).unwrap();
+        assert_eq!(pending.target_label, "doc-dst");
+        assert!(take_pending_result("doc-src", "tab-x", None).is_none());
+    }
+
+    #[test]
+    fn focused_drop_target_wins() {
+        let hits = vec![
+            drop_target("doc-1".to_string(), false, 200.0, 10.0, 800.0),
+            drop_target("doc-2".to_string(), true, 600.0, 10.0, 800.0),
+        ];
+        let target = pick_drop_target(hits).unwrap();
+        assert_eq!(target.label, "doc-2");
+        assert_eq!(target.relative_x, 0.75);
+
+        let hits = vec![drop_target("doc-1".to_string(), false, 200.0, 10.0, 800.0)];
+        let target = pick_drop_target(hits).unwrap();
+        assert_eq!(target.label, "doc-1");
+        assert_eq!(target.relative_x, 0.25);
+        assert!(pick_drop_target(Vec::new()).is_none());
+        assert_eq!(
+            drop_target("doc-3".to_string(), false, 5.0, 5.0, 0.0).relative_x,
+            0.0
+        );
+    }
+
+    #[test]
+    fn hover_over_another_window_cancels_pending_focus() {
+        let now = Instant::now();
+        let first = begin_hover("doc-hover-a", now).unwrap();
+        // Moving within the same window keeps the original dwell start
+        assert!(begin_hover("doc-hover-a", now + Duration::from_millis(50)).is_none());
+
+        let second = begin_hover("doc-hover-b", now + Duration::from_millis(100)).unwrap();
+        assert!(!finish_hover("doc-hover-a", first));
+        assert!(finish_hover("doc-hover-b", second));
+        assert!(!finish_hover("doc-hover-b", second));
+    }
+
+    #[test]
+    fn drop_hit_test_on_scaled_display() {
+        // Window on a 2x display: physical (2000, 400) 1600x1200 is
+        // logical (1000, 200) 800x600.
+        let rect = window_manager::logical_window_rect(
+            tauri::PhysicalPosition { x: 2000, y: 400 },
+            tauri::PhysicalSize {
+                width: 1600,
+                height: 1200,
+            },
+            2.0,
+        );
+        assert_eq!(rect.x, 1000.0);
+        assert_eq!(rect.width, 800.0);
+
+        // Logical cursor inside the window, though left of its physical origin
+        assert_eq!(point_in_rect(&rect, 1100.0, 300.0), Some((100.0, 100.0)));
+        // Inside the physical bounds, but past the logical right edge
+        assert_eq!(point_in_rect(&rect, 2100.0, 500.0), None);
+    }
+
+    #[test]
+    fn view_state_is_optional() {
+        let legacy: TabTransferData = serde_json::from_value(serde_json::json!({
+            "tabId": "t1",
+            "title": "notes.md",
+            "filePath": null,
+            "content": "# Notes",
+            "savedContent": "# Notes",
+            "isDirty": false
+        }))
+        .unwrap();
+        assert!(legacy.scroll_top.is_none());
+        assert!(legacy.cursor_line.is_none());
+
+        let with_view: TabTransferData = serde_json::from_value(serde_json::json!({
+            "tabId": "t1",
+            "title": "notes.md",
+            "filePath": null,
+            "content": "# Notes",
+            "savedContent": "# Notes",
+            "isDirty": false,
+            "scrollTop": 120.5,
+            "cursorLine": 4,
+            "cursorCol": 2
+        }))
+        .unwrap();
+        assert_eq!(with_view.scroll_top, Some(120.5));
+        assert_eq!(with_view.cursor_line, Some(4));
+        assert_eq!(with_view.cursor_col, Some(2));
+    }
+}
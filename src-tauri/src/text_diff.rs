@@ -0,0 +1,324 @@
+//! Text diffing for compare views.
+//!
+//! Pure line- or word-level diff between two texts, used by the external
+//! change conflict view and the general compare feature. Tokens are compared
+//! by a normalized key (for whitespace handling) but the original text is
+//! always returned.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices_deadline, Algorithm};
+
+/// Upper bound on diff computation; similar falls back to a coarser result
+/// instead of running unbounded on pathological inputs.
+const DIFF_DEADLINE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffGranularity {
+    #[default]
+    Line,
+    Word,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WhitespaceMode {
+    /// Whitespace differences are significant
+    #[default]
+    Exact,
+    /// Ignore whitespace at the end of lines
+    IgnoreTrailing,
+    /// Ignore all whitespace differences
+    IgnoreAll,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffOpts {
+    #[serde(default)]
+    pub granularity: DiffGranularity,
+    #[serde(default)]
+    pub whitespace: WhitespaceMode,
+}
+
+/// A single diff operation. Text is the concatenation of the covered tokens.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum DiffOp {
+    Equal { text: String },
+    Insert { text: String },
+    Delete { text: String },
+    Replace { old: String, new: String },
+}
+
+/// Split into lines, keeping line endings attached.
+fn tokenize_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// Split into alternating runs of whitespace and non-whitespace.
+fn tokenize_words(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut prev_ws: Option<bool> = None;
+    for (i, ch) in text.char_indices() {
+        let ws = ch.is_whitespace();
+        if prev_ws.is_some_and(|p| p != ws) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        prev_ws = Some(ws);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Comparison key for a token under the given whitespace mode.
+fn token_key(token: &str, granularity: DiffGranularity, mode: WhitespaceMode) -> String {
+    match (granularity, mode) {
+        (_, WhitespaceMode::Exact) => token.to_string(),
+        (DiffGranularity::Line, WhitespaceMode::IgnoreTrailing) => token.trim_end().to_string(),
+        (DiffGranularity::Line, WhitespaceMode::IgnoreAll) => {
+            token.chars().filter(|c| !c.is_whitespace()).collect()
+        }
+        (DiffGranularity::Word, _) => {
+            if token.chars().all(char::is_whitespace) {
+                // Collapse whitespace runs; keep line breaks unless ignoring all
+                if mode == WhitespaceMode::IgnoreTrailing && token.contains('\n') {
+                    "\n".to_string()
+                } else {
+                    " ".to_string()
+                }
+            } else {
+                token.to_string()
+            }
+        }
+    }
+}
+
+/// Compute a diff between two texts.
+pub fn compute_diff(left: &str, right: &str, opts: &DiffOpts) -> Vec<DiffOp> {
+    let (old_tokens, new_tokens, algorithm) = match opts.granularity {
+        DiffGranularity::Line => (
+            tokenize_lines(left),
+            tokenize_lines(right),
+            Algorithm::Patience,
+        ),
+        DiffGranularity::Word => (
+            tokenize_words(left),
+            tokenize_words(right),
+            Algorithm::Myers,
+        ),
+    };
+    let old_keys: Vec<String> = old_tokens
+        .iter()
+        .map(|t| token_key(t, opts.granularity, opts.whitespace))
+        .collect();
+    let new_keys: Vec<String> = new_tokens
+        .iter()
+        .map(|t| token_key(t, opts.granularity, opts.whitespace))
+        .collect();
+
+    let deadline = Some(Instant::now() + DIFF_DEADLINE);
+    let ops = capture_diff_slices_deadline(algorithm, &old_keys, &new_keys, deadline);
+
+    let join = |tokens: &[&str], start: usize, len: usize| tokens[start..start + len].concat();
+
+    let mut result: Vec<DiffOp> = Vec::with_capacity(ops.len());
+    for op in ops {
+        let next = match op {
+            similar::DiffOp::Equal { old_index, len, .. } => DiffOp::Equal {
+                text: join(&old_tokens, old_index, len),
+            },
+            similar::DiffOp::Delete {
+                old_index, old_len, ..
+            } => DiffOp::Delete {
+                text: join(&old_tokens, old_index, old_len),
+            },
+            similar::DiffOp::Insert {
+                new_index, new_len, ..
+            } => DiffOp::Insert {
+                text: join(&new_tokens, new_index, new_len),
+            },
+            similar::DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => DiffOp::Replace {
+                old: join(&old_tokens, old_index, old_len),
+                new: join(&new_tokens, new_index, new_len),
+            },
+        };
+        // Merge adjacent equal runs (can happen when a deadline splits work)
+        if let (Some(DiffOp::Equal { text: prev }), DiffOp::Equal { text }) =
+            (result.last_mut(), &next)
+        {
+            prev.push_str(text);
+            continue;
+        }
+        result.push(next);
+    }
+    result
+}
+
+/// Compute a structured diff between two texts.
+#[tauri::command]
+pub fn diff_texts(left: String, right: String, opts: Option<DiffOpts>) -> Vec<DiffOp> {
+    compute_diff(&left, &right, &opts.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_opts(whitespace: WhitespaceMode) -> DiffOpts {
+        DiffOpts {
+            granularity: DiffGranularity::Line,
+            whitespace,
+        }
+    }
+
+    fn word_opts() -> DiffOpts {
+        DiffOpts {
+            granularity: DiffGranularity::Word,
+            whitespace: WhitespaceMode::Exact,
+        }
+    }
+
+    #[test]
+    fn identical_texts_are_one_equal_op() {
+        let ops = compute_diff("a\nb\n", "a\nb\n", &DiffOpts::default());
+        assert_eq!(
+            ops,
+            vec![DiffOp::Equal {
+                text: "a\nb\n".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn line_insert_and_delete() {
+        let ops = compute_diff("a\nb\nc\n", "a\nc\nd\n", &DiffOpts::default());
+        assert_eq!(
+            ops,
+            vec![
+                DiffOp::Equal { text: "a\n".into() },
+                DiffOp::Delete { text: "b\n".into() },
+                DiffOp::Equal { text: "c\n".into() },
+                DiffOp::Insert { text: "d\n".into() },
+            ]
+        );
+    }
+
+    #[test]
+    fn line_replace() {
+        let ops = compute_diff("a\nold\nc\n", "a\nnew\nc\n", &DiffOpts::default());
+        assert_eq!(
+            ops[1],
+            DiffOp::Replace {
+                old: "old\n".into(),
+                new: "new\n".into()
+            }
+        );
+    }
+
+    #[test]
+    fn trailing_whitespace_ignored_when_requested() {
+        let left = "a  \nb\n";
+        let right = "a\nb\n";
+        assert_eq!(
+            compute_diff(left, right, &line_opts(WhitespaceMode::Exact)).len(),
+            2
+        );
+        let ops = compute_diff(left, right, &line_opts(WhitespaceMode::IgnoreTrailing));
+        assert_eq!(ops, vec![DiffOp::Equal { text: left.into() }]);
+    }
+
+    #[test]
+    fn all_whitespace_ignored_when_requested() {
+        let ops = compute_diff(
+            "a b c\n",
+            "a  b\tc\n",
+            &line_opts(WhitespaceMode::IgnoreAll),
+        );
+        assert_eq!(ops.len(), 1);
+        assert!(matches!(ops[0], DiffOp::Equal { .. }));
+    }
+
+    #[test]
+    fn word_level_replace() {
+        let ops = compute_diff("the quick fox", "the slow fox", &word_opts());
+        assert_eq!(
+            ops,
+            vec![
+                DiffOp::Equal {
+                    text: "the ".into()
+                },
+                DiffOp::Replace {
+                    old: "quick".into(),
+                    new: "slow".into()
+                },
+                DiffOp::Equal {
+                    text: " fox".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_words_alternates_runs() {
+        assert_eq!(tokenize_words("a  b\nc"), vec!["a", "  ", "b", "\n", "c"]);
+        assert!(tokenize_words("").is_empty());
+    }
+
+    #[test]
+    fn ops_reconstruct_both_sides() {
+        let left = "one\ntwo\nthree\nfour\n";
+        let right = "zero\none\nthree\n4\n";
+        let ops = compute_diff(left, right, &DiffOpts::default());
+        let mut old = String::new();
+        let mut new = String::new();
+        for op in ops {
+            match op {
+                DiffOp::Equal { text } => {
+                    old.push_str(&text);
+                    new.push_str(&text);
+                }
+                DiffOp::Delete { text } => old.push_str(&text),
+                DiffOp::Insert { text } => new.push_str(&text),
+                DiffOp::Replace { old: o, new: n } => {
+                    old.push_str(&o);
+                    new.push_str(&n);
+                }
+            }
+        }
+        assert_eq!(old, left);
+        assert_eq!(new, right);
+    }
+
+    #[test]
+    fn large_input_completes() {
+        let left: String = (0..20_000).map(|i| format!("line {}\n", i)).collect();
+        let right: String = (0..20_000)
+            .map(|i| {
+                if i % 100 == 0 {
+                    format!("changed {}\n", i)
+                } else {
+                    format!("line {}\n", i)
+                }
+            })
+            .collect();
+        let ops = compute_diff(&left, &right, &DiffOpts::default());
+        assert_eq!(
+            ops.iter()
+                .filter(|op| matches!(op, DiffOp::Replace { .. }))
+                .count(),
+            200
+        );
+    }
+}
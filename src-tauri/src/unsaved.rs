@@ -0,0 +1,197 @@
+//! Unsaved-changes summary across document windows.
+//!
+//! Broadcasts a query to every document window and collects the dirty tab
+//! state each one reports, with a bounded wait. Windows that do not answer in
+//! time are reported as unresponsive rather than assumed clean.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tokio::time::{timeout, Duration};
+
+use crate::quit::is_document_window_label;
+
+const EVENT_UNSAVED_QUERY: &str = "app:unsaved-query";
+const EVENT_UNSAVED_RESPONSE: &str = "app:unsaved-response";
+
+/// How long to wait for all windows to answer
+const QUERY_TIMEOUT_MS: u64 = 1500;
+/// Polling interval while waiting for responses
+const RESPONSE_POLL_INTERVAL_MS: u64 = 25;
+
+/// Query payload sent to each window
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UnsavedQuery {
+    query_id: String,
+}
+
+/// Response payload emitted by a window
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UnsavedResponse {
+    query_id: String,
+    window_label: String,
+    dirty_titles: Vec<String>,
+}
+
+/// Unsaved state for a single window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowUnsaved {
+    pub label: String,
+    pub dirty_count: usize,
+    pub dirty_titles: Vec<String>,
+    /// False if the window did not answer in time (its state is unknown)
+    pub responded: bool,
+}
+
+/// Aggregate unsaved state across all document windows.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsavedSummary {
+    pub total_dirty: usize,
+    /// Number of windows that did not answer in time
+    pub unresponsive: usize,
+    pub windows: Vec<WindowUnsaved>,
+}
+
+/// Build the summary from the expected labels and the responses received.
+fn build_summary(labels: &[String], responses: &HashMap<String, Vec<String>>) -> UnsavedSummary {
+    let mut windows: Vec<WindowUnsaved> = labels
+        .iter()
+        .map(|label| match responses.get(label) {
+            Some(titles) => WindowUnsaved {
+                label: label.clone(),
+                dirty_count: titles.len(),
+                dirty_titles: titles.clone(),
+                responded: true,
+            },
+            None => WindowUnsaved {
+                label: label.clone(),
+                dirty_count: 0,
+                dirty_titles: Vec::new(),
+                responded: false,
+            },
+        })
+        .collect();
+    windows.sort_by(|a, b| a.label.cmp(&b.label));
+
+    UnsavedSummary {
+        total_dirty: windows.iter().map(|w| w.dirty_count).sum(),
+        unresponsive: windows.iter().filter(|w| !w.responded).count(),
+        windows,
+    }
+}
+
+/// Query every document window for its dirty tabs.
+#[tauri::command]
+pub async fn unsaved_summary(app: AppHandle) -> Result<UnsavedSummary, String> {
+    let labels: Vec<String> = app
+        .webview_windows()
+        .into_keys()
+        .filter(|label| is_document_window_label(label))
+        .collect();
+
+    if labels.is_empty() {
+        return Ok(build_summary(&labels, &HashMap::new()));
+    }
+
+    let query_id = format!("unsaved-{}", uuid::Uuid::new_v4());
+    let responses: Arc<Mutex<HashMap<String, Vec<String>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+
+    let responses_clone = responses.clone();
+    let expected_id = query_id.clone();
+    let expected_labels = labels.clone();
+    let unlisten = app.listen(EVENT_UNSAVED_RESPONSE, move |event| {
+        match serde_json::from_str::<UnsavedResponse>(event.payload()) {
+            Ok(response) => {
+                // Ignore stale responses and windows we didn't ask
+                if response.query_id != expected_id
+                    || !expected_labels.contains(&response.window_label)
+                {
+                    return;
+                }
+                let mut guard = responses_clone.lock().unwrap_or_else(|p| p.into_inner());
+                guard
+                    .entry(response.window_label)
+                    .or_insert(response.dirty_titles);
+            }
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("[Unsaved] Failed to parse response: {}", _e);
+            }
+        }
+    });
+
+    let query = UnsavedQuery { query_id };
+    for label in &labels {
+        if let Some(window) = app.get_webview_window(label) {
+            if let Err(_e) = window.emit(EVENT_UNSAVED_QUERY, &query) {
+                #[cfg(debug_assertions)]
+                eprintln!("[Unsaved] Failed to query {}: {}", label, _e);
+            }
+        }
+    }
+
+    let expected = labels.len();
+    let wait = {
+        let responses = responses.clone();
+        async move {
+            loop {
+                if responses.lock().unwrap_or_else(|p| p.into_inner()).len() >= expected {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(RESPONSE_POLL_INTERVAL_MS)).await;
+            }
+        }
+    };
+    let _ = timeout(Duration::from_millis(QUERY_TIMEOUT_MS), wait).await;
+
+    app.unlisten(unlisten);
+
+    let responses = responses.lock().unwrap_or_else(|p| p.into_inner());
+    Ok(build_summary(&labels, &responses))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_counts_dirty_tabs() {
+        let labels = vec!["main".to_string(), "doc-1".to_string()];
+        let mut responses = HashMap::new();
+        responses.insert("main".to_string(), vec!["a.md".to_string()]);
+        responses.insert("doc-1".to_string(), vec!["b.md".to_string(), "c.md".to_string()]);
+
+        let summary = build_summary(&labels, &responses);
+        assert_eq!(summary.total_dirty, 3);
+        assert_eq!(summary.unresponsive, 0);
+        assert_eq!(summary.windows[0].label, "doc-1");
+        assert_eq!(summary.windows[0].dirty_count, 2);
+    }
+
+    #[test]
+    fn summary_marks_unresponsive_windows() {
+        let labels = vec!["main".to_string(), "doc-2".to_string()];
+        let mut responses = HashMap::new();
+        responses.insert("main".to_string(), Vec::new());
+
+        let summary = build_summary(&labels, &responses);
+        assert_eq!(summary.total_dirty, 0);
+        assert_eq!(summary.unresponsive, 1);
+        let doc = summary.windows.iter().find(|w| w.label == "doc-2").unwrap();
+        assert!(!doc.responded);
+    }
+
+    #[test]
+    fn summary_empty_when_no_windows() {
+        let summary = build_summary(&[], &HashMap::new());
+        assert_eq!(summary.total_dirty, 0);
+        assert!(summary.windows.is_empty());
+    }
+}
@@ -1,12 +1,46 @@
+use serde::Serialize;
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{LazyLock, Mutex};
 use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
 
 use crate::PendingFileOpen;
 
 static WINDOW_COUNTER: AtomicU32 = AtomicU32::new(0);
 
+/// Label prefix used for document windows created by this module.
+const DOCUMENT_WINDOW_PREFIX: &str = "doc-";
+
+/// Label prefixes that identify document windows (in addition to "main").
+static DOCUMENT_WINDOW_PREFIXES: LazyLock<Mutex<Vec<String>>> =
+    LazyLock::new(|| Mutex::new(vec![DOCUMENT_WINDOW_PREFIX.to_string()]));
+
+/// Register a label prefix whose windows should be treated as document windows.
+/// Registering the same prefix twice is a no-op.
+pub fn register_document_window_prefix(prefix: &str) {
+    if prefix.is_empty() {
+        return;
+    }
+    if let Ok(mut prefixes) = DOCUMENT_WINDOW_PREFIXES.lock() {
+        if !prefixes.iter().any(|p| p == prefix) {
+            prefixes.push(prefix.to_string());
+        }
+    }
+}
+
+/// Determine whether a window label belongs to a document window
+/// ("main" or any registered prefix).
+pub fn is_document_window_label(label: &str) -> bool {
+    if label == "main" {
+        return true;
+    }
+    DOCUMENT_WINDOW_PREFIXES
+        .lock()
+        .map(|prefixes| prefixes.iter().any(|p| label.starts_with(p.as_str())))
+        .unwrap_or(false)
+}
+
 /// Compute workspace root from a file path (parent directory).
 /// Returns None if the file is at root level or path is invalid.
 ///
@@ -79,6 +113,94 @@ const MAX_CASCADE: u32 = 10;
 const MIN_WIDTH: f64 = 800.0;
 const MIN_HEIGHT: f64 = 600.0;
 
+/// Screen rectangle in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogicalRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl LogicalRect {
+    pub(crate) fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Outer window bounds in logical pixels. Tauri reports position and size in
+/// physical pixels, which differ from logical ones on scaled (e.g. 2x) displays.
+pub(crate) fn logical_window_rect(
+    position: tauri::PhysicalPosition<i32>,
+    size: tauri::PhysicalSize<u32>,
+    scale_factor: f64,
+) -> LogicalRect {
+    LogicalRect {
+        x: f64::from(position.x) / scale_factor,
+        y: f64::from(position.y) / scale_factor,
+        width: f64::from(size.width) / scale_factor,
+        height: f64::from(size.height) / scale_factor,
+    }
+}
+
+/// Fit a requested window rectangle onto the available monitors.
+///
+/// The monitor containing the requested top-left corner is used; the size is
+/// clamped between the minimum window size and the monitor size, and the
+/// position is shifted so the whole window stays on that monitor.
+/// Returns None if the point is not on any monitor (caller should cascade).
+pub fn fit_rect_to_monitors(
+    requested: LogicalRect,
+    monitors: &[LogicalRect],
+) -> Option<LogicalRect> {
+    let monitor = monitors
+        .iter()
+        .find(|m| m.contains(requested.x, requested.y))?;
+
+    let width = requested
+        .width
+        .max(MIN_WIDTH)
+        .min(monitor.width.max(MIN_WIDTH));
+    let height = requested
+        .height
+        .max(MIN_HEIGHT)
+        .min(monitor.height.max(MIN_HEIGHT));
+
+    // Keep the window inside the monitor (top-left wins if it cannot fit)
+    let x = requested
+        .x
+        .min(monitor.x + monitor.width - width)
+        .max(monitor.x);
+    let y = requested
+        .y
+        .min(monitor.y + monitor.height - height)
+        .max(monitor.y);
+
+    Some(LogicalRect {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+/// Collect monitor bounds in logical pixels (each monitor uses its own scale factor).
+fn monitor_rects(app: &AppHandle) -> Vec<LogicalRect> {
+    app.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| {
+            let scale = m.scale_factor();
+            LogicalRect {
+                x: m.position().x as f64 / scale,
+                y: m.position().y as f64 / scale,
+                width: m.size().width as f64 / scale,
+                height: m.size().height as f64 / scale,
+            }
+        })
+        .collect()
+}
+
 /// Get cascaded position based on window counter
 fn get_cascaded_position(count: u32) -> (f64, f64) {
     // Wrap around after MAX_CASCADE to avoid windows going off-screen
@@ -129,21 +251,31 @@ fn build_window_url_with_files(file_paths: &[String], workspace_root: Option<&st
 }
 
 /// Create a new document window from a pre-built URL.
+/// When `geometry` is None the window uses the default size and cascaded position.
 fn create_document_window_with_url(
     app: &AppHandle,
     url: String,
+    geometry: Option<LogicalRect>,
 ) -> Result<String, tauri::Error> {
     let count = WINDOW_COUNTER.fetch_add(1, Ordering::SeqCst);
-    let label = format!("doc-{}", count);
+    let label = format!("{}{}", DOCUMENT_WINDOW_PREFIX, count);
 
     let title = String::new();
-    let (x, y) = get_cascaded_position(count);
+    let rect = geometry.unwrap_or_else(|| {
+        let (x, y) = get_cascaded_position(count);
+        LogicalRect {
+            x,
+            y,
+            width: MIN_WIDTH,
+            height: MIN_HEIGHT,
+        }
+    });
 
     let mut builder = WebviewWindowBuilder::new(app, &label, WebviewUrl::App(url.into()))
         .title(&title)
-        .inner_size(MIN_WIDTH, MIN_HEIGHT)
+        .inner_size(rect.width, rect.height)
         .min_inner_size(800.0, 600.0)
-        .position(x, y)
+        .position(rect.x, rect.y)
         .resizable(true)
         .fullscreen(false)
         .focused(true);
@@ -163,10 +295,19 @@ fn create_document_window_with_url(
 
 /// Create a new document window for a tab transfer (drag-out).
 /// The URL includes `?transfer=true` so the frontend can claim the data.
-pub fn create_document_window_for_transfer(
+pub fn create_document_window_for_transfer(app: &AppHandle) -> Result<String, tauri::Error> {
+    create_document_window_with_url(app, "/?transfer=true".to_string(), None)
+}
+
+/// Create a new document window for a tab transfer at a specific screen rectangle.
+/// The rectangle is validated against the available monitors; if it is off-screen
+/// the window falls back to the default cascaded placement.
+pub fn create_document_window_for_transfer_at(
     app: &AppHandle,
+    requested: LogicalRect,
 ) -> Result<String, tauri::Error> {
-    create_document_window_with_url(app, "/?transfer=true".to_string())
+    let geometry = fit_rect_to_monitors(requested, &monitor_rects(app));
+    create_document_window_with_url(app, "/?transfer=true".to_string(), geometry)
 }
 
 /// Create a new document window with optional file path and workspace root.
@@ -182,7 +323,7 @@ pub fn create_document_window(
     workspace_root: Option<&str>,
 ) -> Result<String, tauri::Error> {
     let count = WINDOW_COUNTER.fetch_add(1, Ordering::SeqCst);
-    let label = format!("doc-{}", count);
+    let label = format!("{}{}", DOCUMENT_WINDOW_PREFIX, count);
 
     // Build URL with optional query params
     let url = build_window_url(file_path, workspace_root);
@@ -266,12 +407,8 @@ pub fn open_workspace_in_new_window(
     workspace_root: String,
     file_path: Option<String>,
 ) -> Result<String, String> {
-    create_document_window(
-        &app,
-        file_path.as_deref(),
-        Some(&workspace_root),
-    )
-    .map_err(|e| e.to_string())
+    create_document_window(&app, file_path.as_deref(), Some(&workspace_root))
+        .map_err(|e| e.to_string())
 }
 
 /// Open a workspace in a new window with multiple files.
@@ -282,7 +419,7 @@ pub fn open_workspace_with_files_in_new_window(
     file_paths: Vec<String>,
 ) -> Result<String, String> {
     let url = build_window_url_with_files(&file_paths, Some(&workspace_root));
-    create_document_window_with_url(&app, url).map_err(|e| e.to_string())
+    create_document_window_with_url(&app, url, None).map_err(|e| e.to_string())
 }
 
 /// Close a specific window by label
@@ -303,6 +440,67 @@ pub fn close_window(app: AppHandle, label: String) -> Result<(), String> {
     }
 }
 
+/// An open document window, as reported by `list_document_windows`.
+/// Position and size are the outer bounds in logical pixels.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowInfo {
+    pub label: String,
+    pub title: String,
+    pub focused: bool,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Order windows for display: "main" first, then by creation order
+/// (so `doc-2` sorts before `doc-10`), then by label.
+fn sort_window_infos(windows: &mut [WindowInfo]) {
+    fn key(label: &str) -> (bool, String, u64) {
+        let digits = label.len() - label.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+        let (prefix, number) = label.split_at(label.len() - digits);
+        (
+            label != "main",
+            prefix.to_string(),
+            number.parse().unwrap_or(0),
+        )
+    }
+    windows.sort_by_key(|w| key(&w.label));
+}
+
+/// List open document windows with their title, focus state, and bounds.
+/// Windows whose geometry cannot be read are skipped.
+#[tauri::command]
+pub fn list_document_windows(app: AppHandle) -> Vec<WindowInfo> {
+    let mut windows: Vec<WindowInfo> = app
+        .webview_windows()
+        .into_iter()
+        .filter(|(label, _)| is_document_window_label(label))
+        .filter_map(|(label, window)| {
+            let (Ok(position), Ok(size), Ok(scale)) = (
+                window.outer_position(),
+                window.outer_size(),
+                window.scale_factor(),
+            ) else {
+                return None;
+            };
+            let rect = logical_window_rect(position, size, scale);
+            Some(WindowInfo {
+                label,
+                title: window.title().unwrap_or_default(),
+                focused: window.is_focused().unwrap_or(false),
+                x: rect.x,
+                y: rect.y,
+                width: rect.width,
+                height: rect.height,
+            })
+        })
+        .collect();
+    sort_window_infos(&mut windows);
+    windows
+}
+
 /// Create or focus the settings window.
 /// If settings window exists, focuses it. Otherwise creates a new one.
 /// Returns the window label on success.
@@ -313,7 +511,10 @@ pub fn show_settings_window(app: &AppHandle) -> Result<String, tauri::Error> {
 /// Create or focus the settings window, optionally navigating to a specific section.
 /// If settings window exists, focuses it and navigates to the section.
 /// Otherwise creates a new one with the section in the URL.
-pub fn show_settings_window_section(app: &AppHandle, section: Option<&str>) -> Result<String, tauri::Error> {
+pub fn show_settings_window_section(
+    app: &AppHandle,
+    section: Option<&str>,
+) -> Result<String, tauri::Error> {
     use tauri::Emitter;
 
     const SETTINGS_LABEL: &str = "settings";
@@ -354,17 +555,13 @@ pub fn show_settings_window_section(app: &AppHandle, section: Option<&str>) -> R
     // Create new settings window
     // Note: Don't use .center() here as the window-state plugin may override it.
     // Instead, we build the window visible:false, then set size/position, then show.
-    let mut builder = WebviewWindowBuilder::new(
-        app,
-        SETTINGS_LABEL,
-        WebviewUrl::App(url.into()),
-    )
-    .title("Settings")
-    .inner_size(SETTINGS_WIDTH, SETTINGS_HEIGHT)
-    .min_inner_size(SETTINGS_MIN_WIDTH, SETTINGS_MIN_HEIGHT)
-    .resizable(true)
-    .visible(false) // Start hidden to avoid flash
-    .focused(true);
+    let mut builder = WebviewWindowBuilder::new(app, SETTINGS_LABEL, WebviewUrl::App(url.into()))
+        .title("Settings")
+        .inner_size(SETTINGS_WIDTH, SETTINGS_HEIGHT)
+        .min_inner_size(SETTINGS_MIN_WIDTH, SETTINGS_MIN_HEIGHT)
+        .resizable(true)
+        .visible(false) // Start hidden to avoid flash
+        .focused(true);
 
     #[cfg(target_os = "macos")]
     {
@@ -403,6 +600,63 @@ pub fn request_quit(app: AppHandle) {
 mod tests {
     use super::*;
 
+    // -- document window labels ------------------------------------------------
+
+    #[test]
+    fn test_document_window_label_default_prefixes() {
+        assert!(is_document_window_label("main"));
+        assert!(is_document_window_label("doc-0"));
+        assert!(!is_document_window_label("settings"));
+        assert!(!is_document_window_label("mainframe"));
+    }
+
+    #[test]
+    fn test_document_window_label_custom_prefix() {
+        assert!(!is_document_window_label("scratch-1"));
+        register_document_window_prefix("scratch-");
+        register_document_window_prefix("scratch-");
+        register_document_window_prefix("");
+        assert!(is_document_window_label("scratch-1"));
+        assert!(is_document_window_label("doc-4"));
+        assert!(!is_document_window_label("settings"));
+        let count = DOCUMENT_WINDOW_PREFIXES
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|p| p.as_str() == "scratch-")
+            .count();
+        assert_eq!(count, 1);
+    }
+
+    // -- list_document_windows -------------------------------------------------
+
+    #[test]
+    fn test_sort_window_infos() {
+        let info = |label: &str| WindowInfo {
+            label: label.to_string(),
+            title: String::new(),
+            focused: false,
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+        };
+        let mut windows = vec![
+            info("doc-10"),
+            info("scratch-1"),
+            info("doc-2"),
+            info("main"),
+        ];
+        sort_window_infos(&mut windows);
+        let labels: Vec<&str> = windows.iter().map(|w| w.label.as_str()).collect();
+        assert_eq!(labels, ["main", "doc-2", "doc-10", "scratch-1"]);
+
+        let json = serde_json::to_value(&windows[0]).unwrap();
+        assert_eq!(json["label"], "main");
+        assert_eq!(json["focused"], false);
+        assert_eq!(json["width"], 800.0);
+    }
+
     // -- get_workspace_root_for_file -------------------------------------------
 
     #[test]
@@ -565,4 +819,60 @@ mod tests {
         queue_pending_file_opens(&mut pending, vec![], Some("/a"));
         assert!(pending.is_empty());
     }
+
+    // -- fit_rect_to_monitors --------------------------------------------------
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> LogicalRect {
+        LogicalRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn fit_rect_inside_monitor_unchanged() {
+        let monitors = [rect(0.0, 0.0, 1920.0, 1080.0)];
+        let fitted = fit_rect_to_monitors(rect(100.0, 100.0, 900.0, 700.0), &monitors);
+        assert_eq!(fitted, Some(rect(100.0, 100.0, 900.0, 700.0)));
+    }
+
+    #[test]
+    fn fit_rect_shifted_to_stay_on_monitor() {
+        let monitors = [rect(0.0, 0.0, 1920.0, 1080.0)];
+        let fitted = fit_rect_to_monitors(rect(1800.0, 1000.0, 900.0, 700.0), &monitors);
+        assert_eq!(fitted, Some(rect(1020.0, 380.0, 900.0, 700.0)));
+    }
+
+    #[test]
+    fn fit_rect_enforces_minimum_size() {
+        let monitors = [rect(0.0, 0.0, 1920.0, 1080.0)];
+        let fitted = fit_rect_to_monitors(rect(10.0, 10.0, 100.0, 100.0), &monitors).unwrap();
+        assert_eq!(fitted.width, MIN_WIDTH);
+        assert_eq!(fitted.height, MIN_HEIGHT);
+    }
+
+    #[test]
+    fn fit_rect_uses_monitor_containing_point() {
+        let monitors = [
+            rect(0.0, 0.0, 1920.0, 1080.0),
+            rect(1920.0, 0.0, 1280.0, 800.0),
+        ];
+        let fitted = fit_rect_to_monitors(rect(2000.0, 50.0, 2000.0, 2000.0), &monitors).unwrap();
+        assert_eq!(fitted, rect(1920.0, 0.0, 1280.0, 800.0));
+    }
+
+    #[test]
+    fn fit_rect_off_screen_returns_none() {
+        let monitors = [rect(0.0, 0.0, 1920.0, 1080.0)];
+        assert_eq!(
+            fit_rect_to_monitors(rect(-500.0, 50.0, 800.0, 600.0), &monitors),
+            None
+        );
+        assert_eq!(
+            fit_rect_to_monitors(rect(50.0, 50.0, 800.0, 600.0), &[]),
+            None
+        );
+    }
 }
@@ -1,8 +1,43 @@
+use crate::app_paths::{self, ProgressFn, ReadJsonError};
+use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
 use tauri_plugin_dialog::{DialogExt, FilePath};
 
+/// Quiet period after the last change before the config is reloaded.
+/// An atomic save fires several events (create temp, rename, ...).
+const CONFIG_CHANGE_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Current workspace schema version.
+///
+/// - v0: flat legacy config (`excludeFolders`, `lastOpenTabs`, `ai` at top level)
+/// - v1: `.code-workspace` file without `vmark.version`
+/// - v2: `.code-workspace` file stamped with `vmark.version`
+pub const CURRENT_WORKSPACE_VERSION: u32 = 2;
+
+/// Trust levels stored in `WorkspaceIdentity::trust_level`
+const TRUST_LEVEL_TRUSTED: &str = "trusted";
+const TRUST_LEVEL_UNTRUSTED: &str = "untrusted";
+
+/// Workspace config watchers keyed by watch_id (typically window label)
+static CONFIG_WATCHERS: Mutex<Option<HashMap<String, RecommendedWatcher>>> = Mutex::new(None);
+
+/// Payload for `workspace:config-changed`.
+#[derive(Clone, Serialize)]
+pub struct WorkspaceConfigChangedEvent {
+    #[serde(rename = "watchId")]
+    pub watch_id: String,
+    #[serde(rename = "rootPath")]
+    pub root_path: String,
+    /// Reloaded config, or null if the file was removed
+    pub config: Option<WorkspaceConfig>,
+}
+
 /// VS Code-compatible workspace file with VMark namespace extensions.
 /// Stored in `.vmark/vmark.code-workspace`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +74,9 @@ pub struct WorkspaceIdentity {
 /// Settings block with VMark-namespaced fields
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct WorkspaceSettings {
+    /// Schema version (VMark extension; absent before v2)
+    #[serde(rename = "vmark.version", default)]
+    pub version: u32,
     /// Folders to exclude from file tree (VMark extension)
     #[serde(rename = "vmark.excludeFolders", default)]
     pub exclude_folders: Vec<String>,
@@ -52,7 +90,11 @@ pub struct WorkspaceSettings {
     #[serde(rename = "vmark.ai", default, skip_serializing_if = "Option::is_none")]
     pub ai: Option<serde_json::Value>,
     /// Workspace identity and trust info (VMark extension)
-    #[serde(rename = "vmark.identity", default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "vmark.identity",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub identity: Option<WorkspaceIdentity>,
 }
 
@@ -63,6 +105,7 @@ impl Default for WorkspaceFile {
                 path: ".".to_string(),
             }],
             settings: WorkspaceSettings {
+                version: CURRENT_WORKSPACE_VERSION,
                 exclude_folders: vec![
                     ".git".to_string(),
                     "node_modules".to_string(),
@@ -106,12 +149,16 @@ pub struct WorkspaceConfig {
     pub ai: Option<serde_json::Value>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub identity: Option<WorkspaceIdentity>,
+    /// Set when the workspace file was unreadable and the config was rebuilt
+    /// from the backup or defaults, so the UI can warn. Never stored.
+    #[serde(rename = "configRecovered", default)]
+    pub config_recovered: bool,
 }
 
 impl Default for WorkspaceConfig {
     fn default() -> Self {
         Self {
-            version: 1,
+            version: CURRENT_WORKSPACE_VERSION,
             exclude_folders: vec![
                 ".git".to_string(),
                 "node_modules".to_string(),
@@ -121,6 +168,7 @@ impl Default for WorkspaceConfig {
             last_open_tabs: vec![],
             ai: None,
             identity: None,
+            config_recovered: false,
         }
     }
 }
@@ -128,12 +176,13 @@ impl Default for WorkspaceConfig {
 impl From<WorkspaceFile> for WorkspaceConfig {
     fn from(file: WorkspaceFile) -> Self {
         Self {
-            version: 1,
+            version: file.settings.version,
             exclude_folders: file.settings.exclude_folders,
             show_hidden_files: file.settings.show_hidden_files,
             last_open_tabs: file.settings.last_open_tabs,
             ai: file.settings.ai,
             identity: file.settings.identity,
+            config_recovered: false,
         }
     }
 }
@@ -145,6 +194,7 @@ impl From<WorkspaceConfig> for WorkspaceFile {
                 path: ".".to_string(),
             }],
             settings: WorkspaceSettings {
+                version: CURRENT_WORKSPACE_VERSION,
                 exclude_folders: config.exclude_folders,
                 show_hidden_files: config.show_hidden_files,
                 last_open_tabs: config.last_open_tabs,
@@ -164,6 +214,7 @@ impl From<LegacyWorkspaceConfig> for WorkspaceConfig {
             last_open_tabs: legacy.last_open_tabs,
             ai: legacy.ai,
             identity: None, // Legacy configs don't have identity
+            config_recovered: false,
         }
     }
 }
@@ -173,6 +224,61 @@ fn get_workspace_file_path(root_path: &Path) -> std::path::PathBuf {
     root_path.join(".vmark").join("vmark.code-workspace")
 }
 
+/// Get the path to the single-generation backup of the workspace file
+fn get_workspace_backup_path(root_path: &Path) -> std::path::PathBuf {
+    root_path.join(".vmark").join("vmark.code-workspace.bak")
+}
+
+/// Where an unreadable workspace file is moved before it is repaired
+fn get_workspace_corrupt_path(root_path: &Path) -> std::path::PathBuf {
+    root_path
+        .join(".vmark")
+        .join("vmark.code-workspace.corrupt")
+}
+
+/// Read a workspace file as raw JSON (any schema version). A missing file
+/// counts as an I/O error.
+fn read_workspace_value(path: &Path) -> Result<serde_json::Value, ReadJsonError> {
+    app_paths::read_json(path)?
+        .ok_or_else(|| ReadJsonError::Io(format!("Failed to read {path:?}: file not found")))
+}
+
+/// Schema version of a raw workspace payload (see `CURRENT_WORKSPACE_VERSION`).
+fn stored_version(value: &serde_json::Value) -> u32 {
+    if value.get("settings").is_none() && value.get("folders").is_none() {
+        return 0;
+    }
+    value
+        .get("settings")
+        .and_then(|settings| settings.get("vmark.version"))
+        .and_then(|v| v.as_u64())
+        .map_or(1, |v| v as u32)
+}
+
+/// Upgrade a raw workspace payload of any known version to the current shape.
+pub fn migrate_config(value: serde_json::Value) -> Result<WorkspaceConfig, String> {
+    let version = stored_version(&value);
+    if version > CURRENT_WORKSPACE_VERSION {
+        return Err(format!(
+            "Workspace file version {version} is newer than supported ({CURRENT_WORKSPACE_VERSION}); please update VMark"
+        ));
+    }
+
+    // v0 -> v1: flat legacy fields move under `settings`
+    let file: WorkspaceFile = if version == 0 {
+        let legacy: LegacyWorkspaceConfig = serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse workspace file: {e}"))?;
+        WorkspaceConfig::from(legacy).into()
+    } else {
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse workspace file: {e}"))?
+    };
+
+    // v1 -> v2: new settings take their serde defaults; stamp the version
+    let mut config: WorkspaceConfig = file.into();
+    config.version = CURRENT_WORKSPACE_VERSION;
+    Ok(config)
+}
+
 /// Get the path to the legacy config file (.vmark as a file)
 fn get_legacy_config_path(root_path: &Path) -> std::path::PathBuf {
     root_path.join(".vmark")
@@ -203,12 +309,15 @@ fn read_legacy_config(root_path: &Path) -> Result<Option<LegacyWorkspaceConfig>,
 
 /// Migrate legacy .vmark file to new .vmark/vmark.code-workspace format.
 /// Returns true if migration occurred, false if no migration needed.
-fn migrate_legacy_config(root_path: &Path) -> Result<bool, String> {
+/// Each stage is reported through `progress` before it runs.
+fn migrate_legacy_config(root_path: &Path, progress: ProgressFn) -> Result<bool, String> {
+    const TOTAL: u32 = 3;
     if !is_legacy_config(root_path) {
         return Ok(false);
     }
 
     // Read legacy config
+    app_paths::report_migration_progress(progress, 1, TOTAL, "Reading legacy workspace config");
     let legacy = match read_legacy_config(root_path)? {
         Some(c) => c,
         None => return Ok(false),
@@ -222,6 +331,7 @@ fn migrate_legacy_config(root_path: &Path) -> Result<bool, String> {
     let vmark_dir = root_path.join(".vmark");
 
     // Rename legacy file to backup before creating directory
+    app_paths::report_migration_progress(progress, 2, TOTAL, "Backing up legacy workspace config");
     let legacy_path = get_legacy_config_path(root_path);
     let backup_path = root_path.join(".vmark.backup");
 
@@ -233,6 +343,7 @@ fn migrate_legacy_config(root_path: &Path) -> Result<bool, String> {
         .map_err(|e| format!("Failed to create .vmark directory: {e}"))?;
 
     // Write new workspace file
+    app_paths::report_migration_progress(progress, 3, TOTAL, "Writing workspace file");
     let workspace_path = get_workspace_file_path(root_path);
     let content = serde_json::to_string_pretty(&workspace_file)
         .map_err(|e| format!("Failed to serialize workspace: {e}"))?;
@@ -246,10 +357,11 @@ fn migrate_legacy_config(root_path: &Path) -> Result<bool, String> {
     Ok(true)
 }
 
-/// Open folder dialog and return selected path
+/// Open folder dialog and return selected path.
+/// Awaits the dialog callback instead of blocking a runtime worker thread.
 #[tauri::command]
 pub async fn open_folder_dialog(app: tauri::AppHandle) -> Result<Option<String>, String> {
-    let (tx, rx) = std::sync::mpsc::channel::<Option<FilePath>>();
+    let (tx, rx) = tokio::sync::oneshot::channel::<Option<FilePath>>();
 
     app.dialog()
         .file()
@@ -258,20 +370,103 @@ pub async fn open_folder_dialog(app: tauri::AppHandle) -> Result<Option<String>,
             let _ = tx.send(folder);
         });
 
-    match rx.recv() {
+    match rx.await {
+        Ok(Some(path)) => Ok(Some(path.to_string())),
+        Ok(None) => Ok(None),
+        Err(e) => Err(format!("Dialog error: {e}")),
+    }
+}
+
+/// Filters for the file-open dialogs: the given ones, or Markdown plus
+/// "All Files" when none are given.
+fn dialog_filters(filters: Vec<(String, Vec<String>)>) -> Vec<(String, Vec<String>)> {
+    if !filters.is_empty() {
+        return filters;
+    }
+    vec![
+        (
+            "Markdown".to_string(),
+            vec!["md".to_string(), "markdown".to_string(), "mdx".to_string()],
+        ),
+        ("All Files".to_string(), vec!["*".to_string()]),
+    ]
+}
+
+/// Open file dialog and return the selected file.
+/// `filters` are (name, extensions) pairs; empty means Markdown + All Files.
+#[tauri::command]
+pub async fn open_file_dialog(
+    app: tauri::AppHandle,
+    filters: Vec<(String, Vec<String>)>,
+) -> Result<Option<String>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel::<Option<FilePath>>();
+
+    let mut dialog = app.dialog().file().set_title("Open File");
+    for (name, extensions) in dialog_filters(filters) {
+        let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+        dialog = dialog.add_filter(name, &extensions);
+    }
+    dialog.pick_file(move |file| {
+        let _ = tx.send(file);
+    });
+
+    match rx.await {
         Ok(Some(path)) => Ok(Some(path.to_string())),
         Ok(None) => Ok(None),
         Err(e) => Err(format!("Dialog error: {e}")),
     }
 }
 
+/// Multi-select variant of `open_file_dialog`. Cancelling returns an empty list.
+#[tauri::command]
+pub async fn open_files_dialog(
+    app: tauri::AppHandle,
+    filters: Vec<(String, Vec<String>)>,
+) -> Result<Vec<String>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel::<Option<Vec<FilePath>>>();
+
+    let mut dialog = app.dialog().file().set_title("Open Files");
+    for (name, extensions) in dialog_filters(filters) {
+        let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+        dialog = dialog.add_filter(name, &extensions);
+    }
+    dialog.pick_files(move |files| {
+        let _ = tx.send(files);
+    });
+
+    match rx.await {
+        Ok(files) => Ok(files
+            .unwrap_or_default()
+            .iter()
+            .map(|path| path.to_string())
+            .collect()),
+        Err(e) => Err(format!("Dialog error: {e}")),
+    }
+}
+
 /// Read workspace config, with automatic migration from legacy format.
+/// Migration stages are emitted as `migration:progress` events.
 #[tauri::command]
-pub fn read_workspace_config(root_path: &str) -> Result<Option<WorkspaceConfig>, String> {
+pub fn read_workspace_config(
+    app: AppHandle,
+    root_path: &str,
+) -> Result<Option<WorkspaceConfig>, String> {
+    read_workspace_config_impl(
+        root_path,
+        Some(&|p| app_paths::emit_migration_progress(&app, p)),
+    )
+}
+
+/// Read workspace config - core implementation, reporting legacy migration
+/// stages through `progress`.
+fn read_workspace_config_impl(
+    root_path: &str,
+    progress: ProgressFn,
+) -> Result<Option<WorkspaceConfig>, String> {
     let root = Path::new(root_path);
 
     // Try to migrate legacy config first
-    let _ = migrate_legacy_config(root);
+    let _ = migrate_legacy_config(root, progress);
 
     // Read from new location
     let workspace_path = get_workspace_file_path(root);
@@ -284,13 +479,65 @@ pub fn read_workspace_config(root_path: &str) -> Result<Option<WorkspaceConfig>,
         return Ok(None);
     }
 
-    let content = fs::read_to_string(&workspace_path)
-        .map_err(|e| format!("Failed to read workspace file: {e}"))?;
+    // Only a corrupt file is repaired; a failed read is passed on untouched
+    let value = match read_workspace_value(&workspace_path) {
+        Ok(value) => value,
+        Err(ReadJsonError::Parse(e)) => return recover_workspace_config(root_path, &e).map(Some),
+        Err(ReadJsonError::Io(e)) => return Err(e),
+    };
+
+    let needs_upgrade = stored_version(&value) < CURRENT_WORKSPACE_VERSION;
+    let config = migrate_config(value)?;
+
+    // Persist the upgrade so migration runs once
+    if needs_upgrade {
+        if let Err(_e) = write_workspace_config(root_path, config.clone()) {
+            #[cfg(debug_assertions)]
+            eprintln!("[Workspace] Failed to write migrated workspace file: {_e}");
+        }
+    }
+
+    Ok(Some(config))
+}
+
+/// Rebuild the config after the workspace file failed to parse
+/// (truncated, not UTF-8): from the last good backup if there is one,
+/// otherwise defaults. The broken file is kept as `.corrupt` and the
+/// repaired config written in its place.
+fn recover_workspace_config(root_path: &str, _error: &str) -> Result<WorkspaceConfig, String> {
+    let root = Path::new(root_path);
+    let workspace_path = get_workspace_file_path(root);
+
+    let mut config = match read_workspace_value(&get_workspace_backup_path(root)) {
+        Ok(backup) => {
+            #[cfg(debug_assertions)]
+            eprintln!(
+                "[Workspace] {_error}; restoring backup of {:?}",
+                workspace_path
+            );
+            migrate_config(backup)?
+        }
+        Err(_) => {
+            #[cfg(debug_assertions)]
+            eprintln!(
+                "[Workspace] {_error}; resetting {:?} to defaults",
+                workspace_path
+            );
+            WorkspaceFile::default().into()
+        }
+    };
 
-    let workspace_file: WorkspaceFile = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse workspace file: {e}"))?;
+    if let Err(_e) = fs::rename(&workspace_path, get_workspace_corrupt_path(root)) {
+        #[cfg(debug_assertions)]
+        eprintln!("[Workspace] Failed to set aside corrupt workspace file: {_e}");
+    }
+    if let Err(_e) = write_workspace_config(root_path, config.clone()) {
+        #[cfg(debug_assertions)]
+        eprintln!("[Workspace] Failed to write repaired workspace file: {_e}");
+    }
 
-    Ok(Some(workspace_file.into()))
+    config.config_recovered = true;
+    Ok(config)
 }
 
 /// Write workspace config to .vmark/vmark.code-workspace
@@ -308,12 +555,185 @@ pub fn write_workspace_config(root_path: &str, config: WorkspaceConfig) -> Resul
     let workspace_file: WorkspaceFile = config.into();
     let workspace_path = get_workspace_file_path(root);
 
-    let content = serde_json::to_string_pretty(&workspace_file)
-        .map_err(|e| format!("Failed to serialize workspace: {e}"))?;
+    // Keep one backup of the previous file, but never replace a good backup
+    // with a file that no longer parses.
+    if read_workspace_value(&workspace_path).is_ok() {
+        if let Err(_e) = fs::copy(&workspace_path, get_workspace_backup_path(root)) {
+            #[cfg(debug_assertions)]
+            eprintln!("[Workspace] Failed to back up workspace file: {_e}");
+        }
+    }
 
-    fs::write(&workspace_path, content)
-        .map_err(|e| format!("Failed to write workspace file: {e}"))?;
+    app_paths::write_json_atomic(&workspace_path, &workspace_file)
+}
+
+/// Load the config (or a fresh default), ensure it has an identity, apply
+/// `update` to it and write the result back.
+fn update_workspace_identity(
+    root_path: &str,
+    update: impl FnOnce(&mut WorkspaceIdentity),
+) -> Result<WorkspaceIdentity, String> {
+    let mut config = read_workspace_config_impl(root_path, None)?
+        .unwrap_or_else(|| WorkspaceFile::default().into());
+
+    let identity = config.identity.get_or_insert_with(|| WorkspaceIdentity {
+        id: uuid::Uuid::new_v4().to_string(),
+        created_at: chrono::Utc::now().timestamp_millis(),
+        trust_level: TRUST_LEVEL_UNTRUSTED.to_string(),
+        trusted_at: None,
+    });
+    update(identity);
+    let identity = identity.clone();
+
+    write_workspace_config(root_path, config)?;
+    Ok(identity)
+}
+
+/// Mark the workspace as trusted, creating its identity if needed.
+#[tauri::command]
+pub fn grant_workspace_trust(root_path: &str) -> Result<WorkspaceIdentity, String> {
+    let identity = update_workspace_identity(root_path, |identity| {
+        identity.trust_level = TRUST_LEVEL_TRUSTED.to_string();
+        identity.trusted_at = Some(chrono::Utc::now().timestamp_millis());
+    })?;
+    sync_bridge_trust(root_path, true);
+    Ok(identity)
+}
+
+/// Mark the workspace as untrusted, creating its identity if needed.
+#[tauri::command]
+pub fn revoke_workspace_trust(root_path: &str) -> Result<WorkspaceIdentity, String> {
+    let identity = update_workspace_identity(root_path, |identity| {
+        identity.trust_level = TRUST_LEVEL_UNTRUSTED.to_string();
+        identity.trusted_at = None;
+    })?;
+    sync_bridge_trust(root_path, false);
+    Ok(identity)
+}
+
+/// Let the MCP bridge pick up a trust change without a restart.
+fn sync_bridge_trust(root_path: &str, trusted: bool) {
+    let root_path = root_path.to_string();
+    tauri::async_runtime::spawn(async move {
+        crate::mcp_bridge::set_workspace_trusted(&root_path, trusted).await;
+    });
+}
+
+/// Whether the workspace has been explicitly trusted (gates AI features).
+///
+/// Read-only: the file is never migrated, rewritten or repaired here. A missing
+/// or corrupt file counts as untrusted.
+#[tauri::command]
+pub fn is_workspace_trusted(root_path: &str) -> Result<bool, String> {
+    let workspace_path = get_workspace_file_path(Path::new(root_path));
+    if !workspace_path.exists() {
+        return Ok(false);
+    }
+    let value = match read_workspace_value(&workspace_path) {
+        Ok(value) => value,
+        Err(ReadJsonError::Parse(_)) => return Ok(false),
+        Err(ReadJsonError::Io(e)) => return Err(e),
+    };
+    Ok(migrate_config(value)?
+        .identity
+        .is_some_and(|identity| identity.trust_level == TRUST_LEVEL_TRUSTED))
+}
+
+/// Whether a notify event touches the workspace file.
+fn is_workspace_file_event(event: &Event, workspace_path: &Path) -> bool {
+    event.paths.iter().any(|p| p == workspace_path)
+}
+
+/// Watch `.vmark/vmark.code-workspace` and emit `workspace:config-changed`
+/// with the reloaded config after external writes (e.g. another window).
+///
+/// The `.vmark` directory is watched rather than the file, because atomic
+/// saves replace the file and would end a watch on it.
+#[tauri::command]
+pub fn watch_workspace_config(
+    app: AppHandle,
+    watch_id: String,
+    root_path: String,
+) -> Result<(), String> {
+    let root = Path::new(&root_path);
+    let vmark_dir = root.join(".vmark");
+    if !vmark_dir.is_dir() {
+        fs::create_dir_all(&vmark_dir)
+            .map_err(|e| format!("Failed to create .vmark directory: {e}"))?;
+    }
+
+    unwatch_workspace_config(watch_id.clone())?;
+
+    let workspace_path = get_workspace_file_path(root);
+    let (tx, rx) = mpsc::channel::<()>();
+
+    let mut watcher = RecommendedWatcher::new(
+        move |res: Result<Event, notify::Error>| {
+            if let Ok(event) = res {
+                if is_workspace_file_event(&event, &workspace_path) {
+                    let _ = tx.send(());
+                }
+            }
+        },
+        Config::default(),
+    )
+    .map_err(|e| format!("Failed to create watcher: {e}"))?;
+
+    watcher
+        .watch(&vmark_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch workspace config: {e}"))?;
+
+    // Debounce: wait for a quiet period, then reload once. The thread ends
+    // when the watcher (and with it the sender) is dropped.
+    let emit_watch_id = watch_id.clone();
+    std::thread::spawn(move || {
+        while rx.recv().is_ok() {
+            loop {
+                match rx.recv_timeout(CONFIG_CHANGE_DEBOUNCE) {
+                    Ok(()) => continue,
+                    Err(mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            let progress = |p| app_paths::emit_migration_progress(&app, p);
+            match read_workspace_config_impl(&root_path, Some(&progress)) {
+                Ok(config) => {
+                    let _ = app.emit(
+                        "workspace:config-changed",
+                        WorkspaceConfigChangedEvent {
+                            watch_id: emit_watch_id.clone(),
+                            root_path: root_path.clone(),
+                            config,
+                        },
+                    );
+                }
+                Err(_e) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("[Workspace] Failed to reload changed config: {_e}");
+                }
+            }
+        }
+    });
+
+    let mut guard = CONFIG_WATCHERS
+        .lock()
+        .map_err(|e| format!("Lock error: {e}"))?;
+    guard
+        .get_or_insert_with(HashMap::new)
+        .insert(watch_id, watcher);
+
+    Ok(())
+}
 
+/// Stop watching the workspace config for a watch_id (e.g. when the workspace closes).
+#[tauri::command]
+pub fn unwatch_workspace_config(watch_id: String) -> Result<(), String> {
+    let mut guard = CONFIG_WATCHERS
+        .lock()
+        .map_err(|e| format!("Lock error: {e}"))?;
+    if let Some(watchers) = guard.as_mut() {
+        watchers.remove(&watch_id);
+    }
     Ok(())
 }
 
@@ -348,6 +768,7 @@ mod tests {
             last_open_tabs: vec!["file.md".to_string()],
             ai: None,
             identity: None,
+            config_recovered: false,
         };
 
         let file: WorkspaceFile = config.clone().into();
@@ -361,7 +782,7 @@ mod tests {
     #[test]
     fn test_read_nonexistent_workspace() {
         let dir = tempdir().unwrap();
-        let result = read_workspace_config(dir.path().to_str().unwrap());
+        let result = read_workspace_config_impl(dir.path().to_str().unwrap(), None);
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
     }
@@ -378,14 +799,19 @@ mod tests {
             last_open_tabs: vec!["doc.md".to_string()],
             ai: None,
             identity: None,
+            config_recovered: false,
         };
 
         write_workspace_config(root, config.clone()).unwrap();
 
         // Verify file was created in new location
-        assert!(dir.path().join(".vmark").join("vmark.code-workspace").exists());
+        assert!(dir
+            .path()
+            .join(".vmark")
+            .join("vmark.code-workspace")
+            .exists());
 
-        let read = read_workspace_config(root).unwrap().unwrap();
+        let read = read_workspace_config_impl(root, None).unwrap().unwrap();
         assert_eq!(read.exclude_folders, config.exclude_folders);
         assert_eq!(read.last_open_tabs, config.last_open_tabs);
     }
@@ -407,7 +833,9 @@ mod tests {
         assert!(is_legacy_config(root));
 
         // Read should trigger migration
-        let config = read_workspace_config(root.to_str().unwrap()).unwrap().unwrap();
+        let config = read_workspace_config_impl(root.to_str().unwrap(), None)
+            .unwrap()
+            .unwrap();
 
         // Verify migration occurred
         assert!(!is_legacy_config(root)); // Legacy file should be gone
@@ -415,10 +843,29 @@ mod tests {
         assert!(get_workspace_file_path(root).exists()); // New file should exist
 
         // Verify data was preserved
-        assert!(config.exclude_folders.contains(&"legacy_folder".to_string()));
+        assert!(config
+            .exclude_folders
+            .contains(&"legacy_folder".to_string()));
         assert!(config.last_open_tabs.contains(&"old.md".to_string()));
     }
 
+    #[test]
+    fn test_migrate_legacy_config_reports_progress() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join(".vmark"), r#"{"version": 1}"#).unwrap();
+
+        let steps = std::cell::RefCell::new(Vec::new());
+        let record = |p: app_paths::MigrationProgress| steps.borrow_mut().push((p.step, p.total));
+        read_workspace_config_impl(root.to_str().unwrap(), Some(&record)).unwrap();
+        assert_eq!(*steps.borrow(), [(1, 3), (2, 3), (3, 3)]);
+
+        // Already migrated: nothing to report
+        steps.borrow_mut().clear();
+        read_workspace_config_impl(root.to_str().unwrap(), Some(&record)).unwrap();
+        assert!(steps.borrow().is_empty());
+    }
+
     #[test]
     fn test_has_workspace_config_new_format() {
         let dir = tempdir().unwrap();
@@ -444,6 +891,225 @@ mod tests {
         assert!(has_workspace_config(root.to_str().unwrap()));
     }
 
+    #[test]
+    fn test_grant_and_revoke_trust() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_str().unwrap();
+
+        assert!(!is_workspace_trusted(root).unwrap());
+
+        let granted = grant_workspace_trust(root).unwrap();
+        assert_eq!(granted.trust_level, "trusted");
+        assert!(granted.trusted_at.is_some());
+        assert!(is_workspace_trusted(root).unwrap());
+
+        let revoked = revoke_workspace_trust(root).unwrap();
+        assert_eq!(revoked.id, granted.id);
+        assert_eq!(revoked.created_at, granted.created_at);
+        assert_eq!(revoked.trust_level, "untrusted");
+        assert!(revoked.trusted_at.is_none());
+        assert!(!is_workspace_trusted(root).unwrap());
+    }
+
+    #[test]
+    fn test_trust_query_does_not_touch_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_str().unwrap();
+        let workspace_path = get_workspace_file_path(dir.path());
+        fs::create_dir_all(workspace_path.parent().unwrap()).unwrap();
+
+        // An old-version file is not upgraded on disk
+        let v0 = r#"{"version": 1, "excludeFolders": ["dist"]}"#;
+        fs::write(&workspace_path, v0).unwrap();
+        assert!(!is_workspace_trusted(root).unwrap());
+        assert_eq!(fs::read_to_string(&workspace_path).unwrap(), v0);
+
+        // A corrupt file is untrusted and left in place
+        fs::write(&workspace_path, "{ garbage").unwrap();
+        assert!(!is_workspace_trusted(root).unwrap());
+        assert!(workspace_path.exists());
+        assert!(!get_workspace_corrupt_path(dir.path()).exists());
+    }
+
+    #[test]
+    fn test_grant_trust_keeps_existing_settings() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_str().unwrap();
+
+        let mut config: WorkspaceConfig = WorkspaceFile::default().into();
+        config.last_open_tabs = vec!["notes.md".to_string()];
+        write_workspace_config(root, config).unwrap();
+
+        grant_workspace_trust(root).unwrap();
+
+        let config = read_workspace_config_impl(root, None).unwrap().unwrap();
+        assert_eq!(config.last_open_tabs, vec!["notes.md".to_string()]);
+        assert!(config.identity.is_some());
+    }
+
+    #[test]
+    fn test_write_keeps_backup_of_previous_config() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_str().unwrap();
+
+        let mut config: WorkspaceConfig = WorkspaceFile::default().into();
+        config.last_open_tabs = vec!["first.md".to_string()];
+        write_workspace_config(root, config.clone()).unwrap();
+        assert!(!get_workspace_backup_path(dir.path()).exists());
+
+        config.last_open_tabs = vec!["second.md".to_string()];
+        write_workspace_config(root, config).unwrap();
+
+        let backup = read_workspace_value(&get_workspace_backup_path(dir.path())).unwrap();
+        let backup = migrate_config(backup).unwrap();
+        assert_eq!(backup.last_open_tabs, vec!["first.md".to_string()]);
+    }
+
+    #[test]
+    fn test_read_falls_back_to_backup_when_corrupt() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_str().unwrap();
+
+        let mut config: WorkspaceConfig = WorkspaceFile::default().into();
+        config.last_open_tabs = vec!["good.md".to_string()];
+        write_workspace_config(root, config.clone()).unwrap();
+        write_workspace_config(root, config).unwrap();
+
+        fs::write(get_workspace_file_path(dir.path()), "{ garbage").unwrap();
+
+        let config = read_workspace_config_impl(root, None).unwrap().unwrap();
+        assert_eq!(config.last_open_tabs, vec!["good.md".to_string()]);
+        assert!(config.config_recovered);
+
+        // A corrupt file is not copied over the good backup
+        write_workspace_config(root, config).unwrap();
+        assert!(read_workspace_value(&get_workspace_backup_path(dir.path())).is_ok());
+    }
+
+    #[test]
+    fn test_read_resets_unreadable_config_without_backup() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_str().unwrap();
+        fs::create_dir_all(dir.path().join(".vmark")).unwrap();
+
+        // Not UTF-8, so not JSON either
+        fs::write(get_workspace_file_path(dir.path()), [0xff, 0xfe, 0x00]).unwrap();
+
+        let config = read_workspace_config_impl(root, None).unwrap().unwrap();
+        assert!(config.config_recovered);
+        assert_eq!(
+            config.exclude_folders,
+            WorkspaceConfig::default().exclude_folders
+        );
+
+        // The broken file is kept aside and the repaired one reads cleanly
+        assert!(get_workspace_corrupt_path(dir.path()).exists());
+        let config = read_workspace_config_impl(root, None).unwrap().unwrap();
+        assert!(!config.config_recovered);
+    }
+
+    #[test]
+    fn test_read_error_does_not_reset_config() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_str().unwrap();
+
+        // A directory in place of the file: reading fails with an I/O error
+        fs::create_dir_all(get_workspace_file_path(dir.path())).unwrap();
+
+        let err = read_workspace_config_impl(root, None).unwrap_err();
+        assert!(err.contains("Failed to read"));
+        assert!(!get_workspace_corrupt_path(dir.path()).exists());
+        assert!(get_workspace_file_path(dir.path()).is_dir());
+    }
+
+    #[test]
+    fn test_migrate_v0_flat_config() {
+        let value = serde_json::json!({
+            "version": 1,
+            "excludeFolders": ["dist"],
+            "lastOpenTabs": ["a.md"],
+            "ai": { "provider": "ollama" }
+        });
+
+        let config = migrate_config(value).unwrap();
+        assert_eq!(config.version, CURRENT_WORKSPACE_VERSION);
+        assert_eq!(config.exclude_folders, vec!["dist".to_string()]);
+        assert_eq!(config.last_open_tabs, vec!["a.md".to_string()]);
+        assert!(config.ai.is_some());
+        assert!(!config.show_hidden_files);
+    }
+
+    #[test]
+    fn test_migrate_v1_unversioned_file() {
+        let value = serde_json::json!({
+            "folders": [{ "path": "." }],
+            "settings": { "vmark.excludeFolders": ["build"] }
+        });
+        assert_eq!(stored_version(&value), 1);
+
+        let config = migrate_config(value).unwrap();
+        assert_eq!(config.version, CURRENT_WORKSPACE_VERSION);
+        assert_eq!(config.exclude_folders, vec!["build".to_string()]);
+        assert!(config.last_open_tabs.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_rejects_newer_version() {
+        let value = serde_json::json!({
+            "folders": [],
+            "settings": { "vmark.version": CURRENT_WORKSPACE_VERSION + 1 }
+        });
+        assert!(migrate_config(value).unwrap_err().contains("newer"));
+    }
+
+    #[test]
+    fn test_read_persists_migrated_config() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_str().unwrap();
+        fs::create_dir_all(dir.path().join(".vmark")).unwrap();
+        fs::write(
+            get_workspace_file_path(dir.path()),
+            r#"{ "folders": [{ "path": "." }], "settings": { "vmark.lastOpenTabs": ["x.md"] } }"#,
+        )
+        .unwrap();
+
+        let config = read_workspace_config_impl(root, None).unwrap().unwrap();
+        assert_eq!(config.last_open_tabs, vec!["x.md".to_string()]);
+
+        let stored = read_workspace_value(&get_workspace_file_path(dir.path())).unwrap();
+        assert_eq!(stored_version(&stored), CURRENT_WORKSPACE_VERSION);
+    }
+
+    #[test]
+    fn test_workspace_file_event_filter() {
+        let root = Path::new("/project");
+        let workspace_path = get_workspace_file_path(root);
+        let event = |path: &str| Event::new(notify::EventKind::Any).add_path(path.into());
+
+        assert!(is_workspace_file_event(
+            &event("/project/.vmark/vmark.code-workspace"),
+            &workspace_path
+        ));
+        assert!(!is_workspace_file_event(
+            &event("/project/.vmark/.vmark.code-workspace.tmp.42"),
+            &workspace_path
+        ));
+    }
+
+    #[test]
+    fn test_dialog_filters_default_to_markdown() {
+        let defaults = dialog_filters(Vec::new());
+        assert_eq!(defaults[0].0, "Markdown");
+        assert!(defaults[0].1.contains(&"mdx".to_string()));
+        assert_eq!(
+            defaults[1],
+            ("All Files".to_string(), vec!["*".to_string()])
+        );
+
+        let custom = vec![("Text".to_string(), vec!["txt".to_string()])];
+        assert_eq!(dialog_filters(custom.clone()), custom);
+    }
+
     #[test]
     fn test_malformed_legacy_json_error() {
         let dir = tempdir().unwrap();
@@ -452,7 +1118,7 @@ mod tests {
         // Create malformed legacy file
         fs::write(root.join(".vmark"), "not valid json").unwrap();
 
-        let result = read_workspace_config(root.to_str().unwrap());
+        let result = read_workspace_config_impl(root.to_str().unwrap(), None);
         assert!(result.is_err());
     }
 }
@@ -8,6 +8,7 @@ import {
   exists,
 } from "@tauri-apps/plugin-fs";
 import { ask } from "@tauri-apps/plugin-dialog";
+import { invoke } from "@tauri-apps/api/core";
 import { join, basename } from "@tauri-apps/api/path";
 import { emit } from "@tauri-apps/api/event";
 import { writeText } from "@tauri-apps/plugin-clipboard-manager";
@@ -18,6 +19,9 @@ import { reconcilePathChange } from "@/utils/pathReconciliation";
 import { applyPathReconciliation } from "@/hooks/commands";
 import { showError, FileErrors } from "@/utils/errorDialog";
 
+// Error prefix from the move_to_trash command when the volume has no trash
+const TRASH_UNSUPPORTED = "TRASH_UNSUPPORTED";
+
 // Re-entry guards
 const isCreatingRef = { current: false };
 const isDeletingRef = { current: false };
@@ -159,7 +163,17 @@ export function useExplorerOperations() {
         // Get open file paths before delete
         const openFilePaths = useTabStore.getState().getAllOpenFilePaths();
 
-        await remove(path, { recursive: isFolder });
+        try {
+          await invoke("move_to_trash", { path });
+        } catch (error) {
+          if (!String(error).startsWith(TRASH_UNSUPPORTED)) throw error;
+          const deletePermanently = await ask(
+            `"${name}" can't be moved to the Trash. Delete it permanently?`,
+            { title: `Delete ${itemType}`, kind: "warning" }
+          );
+          if (!deletePermanently) return false;
+          await remove(path, { recursive: isFolder });
+        }
 
         // Reconcile: mark any open tabs/documents as missing
         const results = reconcilePathChange({
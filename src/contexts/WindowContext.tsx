@@ -25,6 +25,9 @@ interface TabTransferData {
   content: string;
   savedContent: string;
   isDirty: boolean;
+  scrollTop?: number | null;
+  cursorLine?: number | null;
+  cursorCol?: number | null;
 }
 
 /**
@@ -24,4 +24,6 @@ export interface McpResponse {
   success: boolean;
   data?: unknown;
   error?: string;
+  /** Machine-readable error code (e.g. "NOT_FOUND"), passed through to the sidecar */
+  code?: string;
 }
@@ -506,6 +506,50 @@ export function useFileOperations() {
       if (cancelled) { unlistenSaveAllQuit(); return; }
       unlistenRefs.current.push(unlistenSaveAllQuit);
 
+      // Save-all quit coordinated by Rust (save_all_and_quit): save this
+      // window's dirty documents, then report the outcome
+      const unlistenSaveAll = await currentWindow.listen<string>("app:save-all", async (event) => {
+        if (event.payload !== windowLabel) return;
+
+        // Keep Rust's force-close timeout paused while saving (Save As may prompt)
+        await invoke("set_quit_window_busy", { label: windowLabel, busy: true }).catch((e) => {
+          if (import.meta.env.DEV) console.warn("[SaveAll] set_quit_window_busy failed:", e);
+        });
+
+        let error: string | null = null;
+        try {
+          flushActiveWysiwygNow();
+          const docStore = useDocumentStore.getState();
+          const tabs = useTabStore.getState().tabs[windowLabel] ?? [];
+          const contexts: CloseSaveContext[] = [];
+          for (const tab of tabs) {
+            const doc = docStore.getDocument(tab.id);
+            if (!doc?.isDirty) continue;
+            contexts.push({
+              windowLabel,
+              tabId: tab.id,
+              title: tab.title || doc.filePath || "Untitled",
+              filePath: doc.filePath,
+              content: doc.content,
+            });
+          }
+
+          const result = await saveAllDocuments(contexts);
+          if (result.action === "cancelled") {
+            error = "Save cancelled";
+          }
+        } catch (e) {
+          console.error("[SaveAll] Failed:", e);
+          error = String(e);
+        }
+
+        await invoke("report_saved", { label: windowLabel, error }).catch((e) => {
+          console.error("[SaveAll] report_saved failed:", e);
+        });
+      });
+      if (cancelled) { unlistenSaveAll(); return; }
+      unlistenRefs.current.push(unlistenSaveAll);
+
       // Listen for open-file from FileExplorer (window-local event, payload contains path)
       const unlistenOpenFile = await currentWindow.listen<{ path: string }>(
         "open-file",
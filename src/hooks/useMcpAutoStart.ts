@@ -31,9 +31,9 @@ export function useMcpAutoStart() {
     // Start only the MCP bridge (WebSocket server).
     // AI clients (Claude Code, Codex, etc.) spawn their own sidecars that connect to this bridge.
     // We don't start a local sidecar - that would conflict with the AI client's sidecar.
-    invoke("mcp_bridge_start", { port: mcpServer.port })
-      .then(() => {
-        console.log("[MCP] Auto-started MCP bridge on port", mcpServer.port);
+    invoke<{ port: number | null }>("mcp_bridge_start", { port: mcpServer.port })
+      .then((status) => {
+        console.log("[MCP] Auto-started MCP bridge on port", status.port);
       })
       .catch((error) => {
         console.error("[MCP] Failed to auto-start MCP bridge:", error);
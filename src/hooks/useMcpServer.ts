@@ -15,6 +15,11 @@ interface McpServerStatus {
   port: number | null;
 }
 
+interface McpPortFallback {
+  requested: number;
+  actual: number;
+}
+
 interface UseMcpServerResult {
   /** Whether the server is currently running */
   running: boolean;
@@ -75,7 +80,7 @@ export function useMcpServer(): UseMcpServerResult {
     setLoading(true);
     setError(null);
     try {
-      // Port parameter is ignored - OS assigns an available port
+      // Port 0 lets the OS assign an available port
       const status = await invoke<McpServerStatus>("mcp_bridge_start", { port: 0 });
       setRunning(status.running);
       setPort(status.port);
@@ -109,17 +114,25 @@ export function useMcpServer(): UseMcpServerResult {
   useEffect(() => {
     refresh();
 
-    const unlistenStarted = listen<number>("mcp-server:started", () => {
+    const unlistenStarted = listen<number>("mcp-server:started", (event) => {
       setRunning(true);
+      setPort(event.payload);
       setError(null);
     });
 
+    const unlistenFallback = listen<McpPortFallback>("mcp-server:port-fallback", (event) => {
+      const { requested, actual } = event.payload;
+      console.warn(`[MCP] Port ${requested} is in use, bridge started on port ${actual}`);
+      setPort(actual);
+    });
+
     const unlistenStopped = listen("mcp-server:stopped", () => {
       setRunning(false);
     });
 
     return () => {
       safeUnlistenAsync(unlistenStarted);
+      safeUnlistenAsync(unlistenFallback);
       safeUnlistenAsync(unlistenStopped);
     };
   }, [refresh]);
@@ -57,9 +57,9 @@ export function useRecentFilesMenuEvents(): void {
       unlistenRefs.current.push(unlistenClearRecent);
 
       // Open Recent File - uses workspace boundary policy
-      // Payload is now (path, windowLabel) - path from Rust snapshot prevents race conditions
-      const unlistenOpenRecent = await currentWindow.listen<[string, string]>("menu:open-recent-file", async (event) => {
-        const [filePath, targetLabel] = event.payload;
+      // Payload is { index, path, windowLabel } - path from Rust snapshot prevents race conditions
+      const unlistenOpenRecent = await currentWindow.listen<{ index: number; path: string; windowLabel: string }>("menu:open-recent-file", async (event) => {
+        const { path: filePath, windowLabel: targetLabel } = event.payload;
         if (targetLabel !== windowLabel) return;
 
         // Find file in store by path (or create minimal file object)
@@ -165,6 +165,14 @@ export function useWindowClose() {
             return;
           }
 
+          // Tell Rust this window is responsive, so its force-close timeout
+          // doesn't fire while a save prompt is open
+          await invoke("set_quit_window_busy", { label: windowLabel, busy: true }).catch((e) => {
+            if (import.meta.env.DEV) {
+              console.warn("[WindowClose] set_quit_window_busy failed:", e);
+            }
+          });
+
           const closed = await handleCloseRequest();
           if (closed) {
             // Notify Rust so it can advance to the next window in the queue.
@@ -177,9 +185,12 @@ export function useWindowClose() {
               }
             });
           } else {
-            invoke("cancel_quit").catch((e) => {
+            invoke("cancel_quit_with_reason", {
+              label: windowLabel,
+              reason: "Save prompt cancelled",
+            }).catch((e) => {
               if (import.meta.env.DEV) {
-                console.warn("[WindowClose] cancel_quit failed:", e);
+                console.warn("[WindowClose] cancel_quit_with_reason failed:", e);
               }
             });
           }
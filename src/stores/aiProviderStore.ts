@@ -109,6 +109,7 @@ export const useAiProviderStore = create<AiProviderState & AiProviderActions>()(
             command: string;
             available: boolean;
             path?: string;
+            version?: string | null;
           };
           const raw: RawEntry[] = await invoke("detect_ai_providers");
 
@@ -121,6 +122,7 @@ export const useAiProviderStore = create<AiProviderState & AiProviderActions>()(
             command: r.command,
             available: r.available,
             path: r.path,
+            version: r.version ?? undefined,
           }));
           set({ cliProviders: providers, detecting: false });
 
@@ -34,12 +34,12 @@ async function updateNativeMenu(files: RecentFile[]) {
   }
 }
 
-/** Register file with macOS Dock recent documents (silently ignored on other platforms) */
+/** Register file with Open Recent and the OS recent documents list */
 async function registerDockRecent(path: string) {
   try {
     await invoke("register_dock_recent", { path });
   } catch {
-    // Silently ignore — command only exists on macOS
+    // Silently ignore — recent documents are best-effort
   }
 }
 
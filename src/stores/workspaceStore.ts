@@ -12,12 +12,13 @@ import { windowScopedStorage } from "@/utils/workspaceStorage";
 
 // Workspace configuration stored in .vmark file
 export interface WorkspaceConfig {
-  version: 1;
+  version: number;
   excludeFolders: string[];
   lastOpenTabs: string[]; // File paths for session restore
   showHiddenFiles: boolean;
   ai?: Record<string, unknown>; // Future AI settings
   identity?: WorkspaceIdentity; // Workspace identity and trust info
+  configRecovered?: boolean; // Set by Rust when an unreadable config was reset
 }
 
 // Runtime workspace state
@@ -46,6 +46,7 @@ export interface CliProviderInfo {
   command: string;
   available: boolean;
   path?: string;
+  version?: string;
 }
 
 export interface RestProviderConfig {
@@ -65,4 +66,6 @@ export interface AiResponseChunk {
   chunk: string;
   done: boolean;
   error?: string;
+  /** Request state, e.g. "queued", "started", or "refused" (with `error`) */
+  status?: string;
 }
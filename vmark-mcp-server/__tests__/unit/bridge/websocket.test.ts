@@ -69,6 +69,20 @@ describe('WebSocketBridge', () => {
       await expect(badBridge.connect()).rejects.toThrow();
     });
 
+    it('should use wss:// when the bridge publishes a TLS certificate', async () => {
+      const tlsBridge = new WebSocketBridge({
+        port: TEST_PORT,
+        timeout: 1000,
+        autoReconnect: false,
+        tlsCertResolver: () => '-----BEGIN CERTIFICATE-----\n-----END CERTIFICATE-----\n',
+      });
+
+      // The plain ws:// test server can't complete a TLS handshake
+      await expect(tlsBridge.connect()).rejects.toThrow();
+      expect(tlsBridge.isConnected()).toBe(false);
+      await tlsBridge.disconnect();
+    });
+
     it('should be idempotent when already connected', async () => {
       await bridge.connect();
       await bridge.connect(); // Should not throw
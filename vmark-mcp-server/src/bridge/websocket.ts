@@ -5,6 +5,7 @@
  * Connects to VMark's WebSocket server and relays MCP commands.
  */
 
+import { rootCertificates } from 'node:tls';
 import WebSocket from 'ws';
 import type { Bridge, BridgeRequest, BridgeResponse } from './types.js';
 
@@ -48,6 +49,16 @@ export interface ClientIdentity {
  */
 export type PortResolver = () => number | undefined;
 
+/**
+ * Function to resolve the bridge auth token (e.g., read from file).
+ */
+export type TokenResolver = () => string | undefined;
+
+/**
+ * Function to resolve the bridge's TLS certificate (PEM), if it serves wss://.
+ */
+export type TlsCertResolver = () => string | undefined;
+
 /**
  * Configuration for WebSocketBridge.
  */
@@ -58,6 +69,13 @@ export interface WebSocketBridgeConfig {
   port?: number;
   /** Function to resolve port dynamically (called on each connect attempt) */
   portResolver?: PortResolver;
+  /** Function to resolve the auth token (called on each connect attempt) */
+  tokenResolver?: TokenResolver;
+  /**
+   * Function to resolve the bridge's TLS certificate (called on each connect
+   * attempt). When it returns a PEM, connects via wss:// and trusts it.
+   */
+  tlsCertResolver?: TlsCertResolver;
   /** Request timeout in ms (default: 30000) */
   timeout?: number;
   /** Whether to auto-reconnect on disconnect (default: true) */
@@ -114,6 +132,8 @@ export class WebSocketBridge implements Bridge {
   private readonly host: string;
   private port: number | undefined;
   private readonly portResolver: PortResolver | undefined;
+  private readonly tokenResolver: TokenResolver | undefined;
+  private readonly tlsCertResolver: TlsCertResolver | undefined;
   private readonly timeout: number;
   private readonly autoReconnect: boolean;
   private readonly maxReconnectAttempts: number;
@@ -146,6 +166,8 @@ export class WebSocketBridge implements Bridge {
     this.host = config.host ?? '127.0.0.1'; // Use IPv4 explicitly to avoid IPv6 issues
     this.port = config.port; // May be undefined - will use portResolver
     this.portResolver = config.portResolver;
+    this.tokenResolver = config.tokenResolver;
+    this.tlsCertResolver = config.tlsCertResolver;
     this.timeout = config.timeout ?? 30000;
     this.autoReconnect = config.autoReconnect ?? true;
     this.maxReconnectAttempts = config.maxReconnectAttempts ?? 10;
@@ -183,8 +205,8 @@ export class WebSocketBridge implements Bridge {
   /**
    * Get the WebSocket URL.
    */
-  private getUrl(port: number): string {
-    return `ws://${this.host}:${port}`;
+  private getUrl(port: number, secure: boolean): string {
+    return `${secure ? 'wss' : 'ws'}://${this.host}:${port}`;
   }
 
   /**
@@ -266,11 +288,16 @@ export class WebSocketBridge implements Bridge {
       );
     }
 
-    const url = this.getUrl(port);
+    // A TLS bridge publishes its certificate; trust it alongside the system CAs
+    const tlsCert = this.tlsCertResolver?.();
+    const url = this.getUrl(port, tlsCert !== undefined);
 
     return new Promise((resolve, reject) => {
       try {
-        this.ws = new WebSocket(url);
+        this.ws =
+          tlsCert !== undefined
+            ? new WebSocket(url, { ca: [...rootCertificates, tlsCert] })
+            : new WebSocket(url);
 
         const connectionTimeout = setTimeout(() => {
           if (!this.connected) {
@@ -290,6 +317,16 @@ export class WebSocketBridge implements Bridge {
           this.connecting = false;
           this.reconnectAttempts = 0;
 
+          // Authenticate first - VMark closes the socket if this isn't the first message
+          const token = this.tokenResolver?.();
+          if (token) {
+            try {
+              this.ws!.send(JSON.stringify({ type: 'auth', token }));
+            } catch (error) {
+              this.logger.warn('Failed to send auth message:', error);
+            }
+          }
+
           // Send client identification if configured
           if (this.clientIdentity) {
             const identifyMsg = {
@@ -110,6 +110,7 @@ import { McpServer } from '@modelcontextprotocol/sdk/server/mcp.js';
 import { StdioServerTransport } from '@modelcontextprotocol/sdk/server/stdio.js';
 import { z, ZodTypeAny } from 'zod';
 import { execSync } from 'child_process';
+import { createHash } from 'crypto';
 import { readFileSync } from 'fs';
 import { join } from 'path';
 import { homedir, platform } from 'os';
@@ -157,6 +158,37 @@ function isNotFoundError(err: unknown): boolean {
   );
 }
 
+/**
+ * Parse the bootstrap file contents into a path.
+ *
+ * Current VMark writes `{ "path": "...", "sha256": "..." }`, where the hash
+ * covers the path string; a mismatch means the file is corrupted or was
+ * edited, so it is ignored. Older versions wrote the bare path, which is
+ * accepted unverified.
+ */
+function parseBootstrapContents(contents: string): string | null {
+  const trimmed = contents.trim();
+  if (!trimmed.startsWith('{')) {
+    return trimmed || null;
+  }
+  try {
+    const parsed = JSON.parse(trimmed) as { path?: unknown; sha256?: unknown };
+    if (typeof parsed.path !== 'string') return null;
+    if (typeof parsed.sha256 === 'string') {
+      const expected = createHash('sha256').update(parsed.path, 'utf8').digest('hex');
+      if (parsed.sha256.toLowerCase() !== expected) {
+        if (process.env.VMARK_DEBUG) {
+          console.error('[VMark MCP] Bootstrap file checksum mismatch, ignoring it');
+        }
+        return null;
+      }
+    }
+    return parsed.path;
+  } catch {
+    return null;
+  }
+}
+
 /**
  * Get the app data directory path.
  *
@@ -171,7 +203,7 @@ function getAppDataDir(): string {
   // 1. Try bootstrap file (written by Rust on app startup)
   const bootstrapPath = join(getLegacyDir(), 'app-data-path');
   try {
-    const appDataPath = readFileSync(bootstrapPath, 'utf8').trim();
+    const appDataPath = parseBootstrapContents(readFileSync(bootstrapPath, 'utf8'));
     // Trust the bootstrap file if it contains an absolute path
     if (appDataPath && appDataPath.startsWith('/')) {
       return appDataPath;
@@ -275,6 +307,48 @@ function readPortFromFile(): number | undefined {
   return undefined;
 }
 
+/**
+ * Read the bridge auth token written by VMark next to the port file.
+ * Returns undefined if the file doesn't exist (auth disabled or VMark not running).
+ */
+function readTokenFromFile(): string | undefined {
+  const tokenFilePath = join(getAppDataDir(), 'mcp-token');
+
+  try {
+    const token = readFileSync(tokenFilePath, 'utf8').trim();
+    return token || undefined;
+  } catch (err) {
+    if (!isNotFoundError(err)) {
+      if (process.env.VMARK_DEBUG) {
+        console.error('[VMark MCP] Failed to read token file:', err);
+      }
+    }
+  }
+
+  return undefined;
+}
+
+/**
+ * Read the certificate VMark's bridge serves when TLS is enabled.
+ * Returns undefined if the file doesn't exist (plain ws://).
+ */
+function readTlsCertFromFile(): string | undefined {
+  const certFilePath = join(getAppDataDir(), 'mcp-tls-ca.pem');
+
+  try {
+    const cert = readFileSync(certFilePath, 'utf8');
+    return cert.trim() ? cert : undefined;
+  } catch (err) {
+    if (!isNotFoundError(err)) {
+      if (process.env.VMARK_DEBUG) {
+        console.error('[VMark MCP] Failed to read TLS certificate file:', err);
+      }
+    }
+  }
+
+  return undefined;
+}
+
 /**
  * Parse command line arguments.
  * Port resolution order:
@@ -557,6 +631,8 @@ async function main(): Promise<void> {
   const bridge = new WebSocketBridge({
     port, // May be undefined - will use portResolver
     portResolver: readPortFromFile, // Re-read port file on each connection attempt
+    tokenResolver: readTokenFromFile, // Token changes every time VMark restarts the bridge
+    tlsCertResolver: readTlsCertFromFile, // Present only while the bridge serves wss://
     autoReconnect: true,
     maxReconnectAttempts: 30, // Reasonable limit to avoid infinite reconnection storms
     reconnectDelay: 2000, // Start with 2 second delay